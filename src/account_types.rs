@@ -0,0 +1,201 @@
+/*!
+ * Maps the Ledger accounts to the cash transaction types.
+ *
+ * The same rules select the accounts in the Ledger query and classify the
+ * postings in the Ledger output, so that they can be compared to the IB
+ * cash transactions of the same type.
+ * The rules can be configured in `ibflex.toml`, i.e.
+ *
+ * ```toml
+ * [[account_types]]
+ * type = "Dividend"
+ * terms = ["revenue:dividends"]
+ * ```
+ */
+
+use serde::Deserialize;
+
+use crate::{flex_enums::CashAction, Error, Result};
+
+/// The characters that would change the meaning of the term in the Ledger query.
+const RESERVED_CHARS: &str = r#"/"\()[]{}.*+?^$|"#;
+
+/**
+ * The Ledger account rule for one cash transaction type.
+ * The account matches when it contains all the terms, ignoring the case.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AccountRule {
+    /// The cash action type, i.e. `Dividend`.
+    pub r#type: String,
+    pub terms: Vec<String>,
+}
+
+impl AccountRule {
+    pub fn new(r#type: impl ToString, terms: &[&str]) -> Self {
+        AccountRule {
+            r#type: r#type.to_string(),
+            terms: terms.iter().map(|term| term.to_string()).collect(),
+        }
+    }
+
+    /// Checks the type name and the terms, and uses the canonical type name.
+    fn validate(self) -> Result<AccountRule> {
+        let action = CashAction::from_name(&self.r#type).ok_or_else(|| {
+            Error::Config(format!("Unknown cash action type {:?} in the account types", self.r#type))
+        })?;
+        if self.terms.is_empty() {
+            return Err(Error::Config(format!("No account terms for the type {action}")));
+        }
+        if let Some(term) = self
+            .terms
+            .iter()
+            .find(|term| term.trim().is_empty() || term.contains(|c| RESERVED_CHARS.contains(c)))
+        {
+            return Err(Error::Config(format!(
+                "Invalid account term {term:?} for the type {action}"
+            )));
+        }
+
+        Ok(AccountRule {
+            r#type: action.to_string(),
+            terms: self.terms,
+        })
+    }
+
+    pub fn matches(&self, account: &str) -> bool {
+        let account = account.to_lowercase();
+        self.terms
+            .iter()
+            .all(|term| account.contains(&term.to_lowercase()))
+    }
+
+    /// The Ledger expression, i.e. `(account =~ /income/ and account =~ /ib/)`.
+    fn to_query(&self) -> String {
+        let conditions: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| format!("account =~ /{term}/"))
+            .collect();
+        format!("({})", conditions.join(" and "))
+    }
+}
+
+/**
+ * The ordered account rules. The first matching rule gives the type, so the
+ * more specific rules come first.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountRules {
+    pub rules: Vec<AccountRule>,
+}
+
+impl Default for AccountRules {
+    fn default() -> Self {
+        AccountRules {
+            rules: vec![
+                AccountRule::new(CashAction::WhTax, &["expenses", "ib", "withh"]),
+                AccountRule::new(CashAction::BondIntPaid, &["expenses", "ib", "bond"]),
+                AccountRule::new(CashAction::Fees, &["expenses", "ib", "fee"]),
+                AccountRule::new(CashAction::CommAdj, &["expenses", "ib", "commission"]),
+                AccountRule::new(CashAction::BrokerIntPaid, &["expenses", "ib:interest"]),
+                AccountRule::new(CashAction::BondIntRcvd, &["income", "ib", "bond"]),
+                AccountRule::new(CashAction::BrokerIntRcvd, &["income", "ib:interest"]),
+                AccountRule::new(CashAction::Dividend, &["income", "ib"]),
+                AccountRule::new(CashAction::DepositWithdraw, &["transfer", "ib"]),
+            ],
+        }
+    }
+}
+
+impl AccountRules {
+    /// Creates the rules from the configuration. The rules are validated.
+    pub fn new(rules: Vec<AccountRule>) -> Result<AccountRules> {
+        let rules = rules
+            .into_iter()
+            .map(AccountRule::validate)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(AccountRules { rules })
+    }
+
+    /// Keeps only the rules for the given types, to select their accounts in the
+    /// Ledger query. Classify with all the rules, as the general rules would
+    /// otherwise match the accounts of the more specific ones.
+    pub fn for_types(&self, types: &[CashAction]) -> AccountRules {
+        AccountRules {
+            rules: self
+                .rules
+                .iter()
+                .filter(|rule| types.iter().any(|tx_type| tx_type.to_string() == rule.r#type))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Gets the cash action type of the Ledger account.
+    pub fn get_type(&self, account: &str) -> Option<CashAction> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(account))
+            .map(|rule| {
+                CashAction::from_name(&rule.r#type).unwrap_or_else(|| CashAction::Other(rule.r#type.to_owned()))
+            })
+    }
+
+    /// The Ledger query that selects the accounts of all the rules.
+    pub fn to_query(&self) -> String {
+        let expressions: Vec<String> = self.rules.iter().map(AccountRule::to_query).collect();
+        expressions.join(" or ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountRule, AccountRules};
+    use crate::{flex_enums::CashAction, Error};
+
+    #[test]
+    fn test_get_type() {
+        let rules = AccountRules::default();
+
+        assert_eq!(Some(CashAction::Dividend), rules.get_type("Income:Investment:IB:TRET_AS"));
+        assert_eq!(Some(CashAction::WhTax), rules.get_type("Expenses:Investment:IB:Withholding Tax"));
+        assert_eq!(Some(CashAction::BrokerIntRcvd), rules.get_type("Income:Investment:IB:Interest"));
+        // The fund distributions booked as interest income are dividends.
+        assert_eq!(Some(CashAction::Dividend), rules.get_type("Income:Investment:Interest:IB:TCF_AX"));
+        assert_eq!(None, rules.get_type("Expenses:Food"));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let rules = AccountRules::new(vec![
+            AccountRule::new("whtax", &["taxes:foreign"]),
+            AccountRule::new("Dividend", &["revenue", "dividends"]),
+        ])
+        .unwrap();
+
+        assert_eq!(Some(CashAction::Dividend), rules.get_type("Revenue:Dividends:Broker"));
+        assert_eq!(Some(CashAction::WhTax), rules.get_type("Taxes:Foreign"));
+        assert_eq!(None, rules.get_type("Income:Investment:IB:TRET_AS"));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let unknown_type = AccountRules::new(vec![AccountRule::new("Dividends", &["revenue"])]);
+        let regex_term = AccountRules::new(vec![AccountRule::new("Dividend", &["^revenue"])]);
+
+        assert!(matches!(unknown_type, Err(Error::Config(_))));
+        assert!(matches!(regex_term, Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_query() {
+        let rules = AccountRules::default().for_types(&[CashAction::Dividend, CashAction::WhTax]);
+
+        assert_eq!(
+            "(account =~ /expenses/ and account =~ /ib/ and account =~ /withh/) or (account =~ /income/ and account =~ /ib/)",
+            rules.to_query()
+        );
+    }
+}
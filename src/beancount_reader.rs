@@ -0,0 +1,326 @@
+/*!
+ * Reads the transactions from a Beancount journal, without running Beancount.
+ *
+ * Supports the subset of the syntax needed for the comparison: the
+ * transactions with their postings (one elided amount per currency is
+ * inferred), the metadata, the comments, and the `include` directives.
+ * The metadata after a posting belongs to the posting, and the postings
+ * inherit the transaction metadata.
+ * The other directives are skipped.
+ * Beancount has no effective dates. The `effective_date` transaction metadata
+ * is used instead, as written by [`beancount_writer`](crate::beancount_writer).
+ */
+
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::{
+    journal_backend::{JournalBackend, JournalQuery},
+    journal_reader::{self, strip_comment, JournalPosting},
+    model::CommonTransaction,
+    Error, Result, ISO_DATE_FORMAT,
+};
+
+/// The transaction metadata key for the effective date.
+pub const EFFECTIVE_DATE_KEY: &str = "effective_date";
+
+/// Reads the Beancount journal file.
+#[derive(Debug, Default)]
+pub struct BeancountBackend;
+
+impl JournalBackend for BeancountBackend {
+    fn get_transactions(&self, query: &JournalQuery) -> Result<Vec<CommonTransaction>> {
+        let Some(journal_file) = &query.journal_file else {
+            return Err(Error::Config(
+                "The journal file is mandatory for Beancount. Use --ledger-journal-file.".into(),
+            ));
+        };
+        let entries = read_journal(Path::new(journal_file))?;
+
+        let postings = entries.iter().flat_map(|entry| {
+            let date = match entry.effective_date {
+                Some(effective_date) if query.effective_dates => effective_date,
+                _ => entry.date,
+            };
+            entry.postings.iter().map(move |posting| JournalPosting {
+                date,
+                payee: &entry.payee,
+                symbol: entry.symbol(),
+                description: &entry.narration,
+                account: &posting.account,
+                amount: posting
+                    .amount
+                    .as_ref()
+                    .map(|(amount, currency)| (*amount, currency.as_str())),
+                tx_metadata: &entry.metadata,
+                metadata: &posting.metadata,
+            })
+        });
+
+        journal_reader::get_transactions(postings, query)
+    }
+}
+
+/// A Beancount transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Entry {
+    date: NaiveDate,
+    effective_date: Option<NaiveDate>,
+    payee: String,
+    narration: String,
+    postings: Vec<Posting>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl Entry {
+    /// The symbol is the first word of the payee, or of the narration without the payee.
+    fn symbol(&self) -> &str {
+        let text = if self.payee.is_empty() {
+            &self.narration
+        } else {
+            &self.payee
+        };
+        text.split_whitespace().next().unwrap_or_default()
+    }
+
+    /// Infers the elided posting amount, which balances the transaction.
+    fn close(mut self) -> Entry {
+        let currencies: Vec<&String> = self
+            .postings
+            .iter()
+            .filter_map(|posting| posting.amount.as_ref().map(|(_, currency)| currency))
+            .collect();
+        let single_currency = currencies.first().filter(|first| currencies.iter().all(|c| c == *first));
+
+        if let Some(currency) = single_currency.map(|c| c.to_string()) {
+            let total: Decimal = self
+                .postings
+                .iter()
+                .filter_map(|posting| posting.amount.as_ref().map(|(amount, _)| amount))
+                .sum();
+            if let Some(posting) = self.postings.iter_mut().find(|p| p.amount.is_none()) {
+                posting.amount = Some((-total, currency));
+            }
+        }
+
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Posting {
+    account: String,
+    /// The amount and the currency, if not elided.
+    amount: Option<(Decimal, String)>,
+    metadata: BTreeMap<String, String>,
+}
+
+/// Reads the transactions from the journal file and the included files.
+fn read_journal(path: &Path) -> Result<Vec<Entry>> {
+    let (content, dir) = journal_reader::read_file(path)?;
+
+    parse_journal(&content, &dir)
+}
+
+/// Parses the journal content. The includes are relative to the directory.
+fn parse_journal(content: &str, dir: &Path) -> Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = vec![];
+    let mut current: Option<Entry> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(';') {
+            continue;
+        }
+
+        let indented = line.starts_with([' ', '\t']);
+        if indented {
+            if let Some(entry) = current.as_mut() {
+                parse_entry_line(entry, trimmed)?;
+            }
+            continue;
+        }
+
+        // A new directive closes the current transaction.
+        if let Some(entry) = current.take() {
+            entries.push(entry.close());
+        }
+
+        if let Some(include) = trimmed.strip_prefix("include ") {
+            let strings = get_strings(include);
+            let Some(include_path) = strings.first() else {
+                return Err(Error::Ledger(format!("Invalid include: {trimmed:?}")));
+            };
+            for included in journal_reader::get_include_paths(include_path, dir)? {
+                entries.append(&mut read_journal(&included)?);
+            }
+            continue;
+        }
+
+        current = parse_header(trimmed)?;
+    }
+
+    if let Some(entry) = current {
+        entries.push(entry.close());
+    }
+
+    Ok(entries)
+}
+
+/// Parses the transaction header line, i.e. `2022-12-15 * "TRET_AS" "Distribution"`.
+/// Returns None for the other directives.
+fn parse_header(line: &str) -> Result<Option<Entry>> {
+    let Some(date_str) = line.get(0..10) else {
+        return Ok(None);
+    };
+    let Ok(date) = NaiveDate::parse_from_str(date_str, ISO_DATE_FORMAT) else {
+        return Ok(None);
+    };
+
+    let rest = line[10..].trim_start();
+    let flag = rest.split_whitespace().next().unwrap_or_default();
+    if !matches!(flag, "*" | "!" | "txn") {
+        // open, close, balance, price, etc.
+        return Ok(None);
+    }
+
+    let mut strings = get_strings(&rest[flag.len()..]);
+    let (payee, narration) = match strings.len() {
+        0 => (String::default(), String::default()),
+        1 => (String::default(), strings.remove(0)),
+        _ => (strings.remove(0), strings.remove(0)),
+    };
+
+    Ok(Some(Entry {
+        date,
+        payee,
+        narration,
+        ..Default::default()
+    }))
+}
+
+/// Parses the posting or the metadata line of the transaction.
+fn parse_entry_line(entry: &mut Entry, line: &str) -> Result<()> {
+    let (line, _comment) = strip_comment(line);
+
+    // metadata, i.e. `effective_date: 2022-12-15`
+    if let Some((key, value)) = line.split_once(':') {
+        let is_key = key.starts_with(|c: char| c.is_ascii_lowercase())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if is_key {
+            if key == EFFECTIVE_DATE_KEY {
+                let value = value.trim();
+                let effective_date = NaiveDate::parse_from_str(value, ISO_DATE_FORMAT)
+                    .map_err(|_| Error::InvalidDate(value.to_owned()))?;
+                entry.effective_date = Some(effective_date);
+            }
+
+            let value = value.trim();
+            let value = get_strings(value).pop().unwrap_or_else(|| value.to_owned());
+            match entry.postings.last_mut() {
+                Some(posting) => posting.metadata.insert(key.to_owned(), value),
+                None => entry.metadata.insert(key.to_owned(), value),
+            };
+            return Ok(());
+        }
+    }
+
+    // posting, with the optional flag
+    let line = line
+        .strip_prefix("* ")
+        .or_else(|| line.strip_prefix("! "))
+        .unwrap_or(line)
+        .trim_start();
+    let mut parts = line.split_whitespace();
+    let account = parts.next().unwrap_or_default().to_owned();
+    let amount = match (parts.next(), parts.next()) {
+        (Some(number), Some(currency)) => {
+            let value = Decimal::from_str(&number.replace(',', ""))
+                .map_err(|_| Error::InvalidAmount(line.to_owned()))?;
+            Some((value, currency.to_owned()))
+        }
+        (None, _) => None,
+        (Some(_), None) => return Err(Error::InvalidAmount(line.to_owned())),
+    };
+
+    entry.postings.push(Posting {
+        account,
+        amount,
+        ..Default::default()
+    });
+    Ok(())
+}
+
+/// Gets the quoted strings from the line. Escaped quotes are supported.
+fn get_strings(text: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let mut current: Option<String> = None;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, current.as_mut()) {
+            ('"', None) => current = Some(String::default()),
+            ('"', Some(_)) => strings.extend(current.take()),
+            ('\\', Some(value)) => value.extend(chars.next()),
+            (c, Some(value)) => value.push(c),
+            (';', None) => break,
+            _ => {}
+        }
+    }
+
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, str::FromStr};
+
+    use rust_decimal::Decimal;
+
+    use super::{parse_journal, read_journal};
+
+    #[test]
+    fn test_parse_transactions() {
+        let content = r#"
+option "operating_currency" "EUR"
+2022-01-01 open Income:Investment:IB:TRET
+
+2022-12-15 * "TRET_AS" "Distribution" #ib
+  effective_date: 2022-12-16
+  ; matches the Flex report
+  Income:Investment:IB:TRET                 -38.40 EUR
+    isin: "NL0009690239"
+  Expenses:Investment:IB:WithholdingTax       5.77 EUR ; NL tax
+  Assets:Investment:IB:Cash
+
+2022-12-16 txn "Groceries"
+  Expenses:Food  3 EUR
+  Assets:Cash   -3 EUR
+"#;
+
+        let actual = parse_journal(content, Path::new("tests")).unwrap();
+
+        assert_eq!(2, actual.len());
+        let entry = &actual[0];
+        assert_eq!("TRET_AS", entry.symbol());
+        assert_eq!("Distribution", entry.narration);
+        assert_eq!("2022-12-16", entry.effective_date.unwrap().to_string());
+        assert_eq!(3, entry.postings.len());
+        assert_eq!(
+            Some((Decimal::from_str("32.63").unwrap(), "EUR".to_string())),
+            entry.postings[2].amount
+        );
+        assert_eq!("Groceries", actual[1].symbol());
+        assert_eq!("NL0009690239", entry.postings[0].metadata["isin"]);
+        assert!(!entry.metadata.contains_key("isin"));
+    }
+
+    #[test]
+    fn test_include() {
+        let actual = read_journal(Path::new("tests/journal.beancount")).unwrap();
+
+        assert!(actual.iter().any(|entry| entry.payee == "TCBT_AS"));
+    }
+}
@@ -0,0 +1,160 @@
+/*!
+ * Generates Beancount entries from the new IB transactions.
+ *
+ * The transactions are grouped as for Ledger, see [`ledger_writer`]. The IB
+ * date is written as the `effective_date` metadata, which the
+ * [`beancount_reader`](crate::beancount_reader) uses for the comparison with
+ * the effective dates.
+ */
+
+use std::fmt::Write;
+
+use crate::{
+    beancount_reader::EFFECTIVE_DATE_KEY,
+    ledger_writer::{self, EntryGroup, LedgerAccounts},
+    model::{CommonTransaction, ISIN_TAG, TRANSACTION_ID_TAG},
+};
+
+pub const DEFAULT_WITHHOLDING_ACCOUNT: &str = "Expenses:Investment:IB:WithholdingTax";
+/// The indentation of the postings and the metadata.
+const INDENT: &str = "  ";
+/// Replaces the account name components that would be empty.
+const UNKNOWN_COMPONENT: &str = "Unknown";
+
+/// The default account templates, with the account names valid in Beancount.
+pub fn default_accounts() -> LedgerAccounts {
+    LedgerAccounts {
+        withholding_tax: DEFAULT_WITHHOLDING_ACCOUNT.to_owned(),
+        ..Default::default()
+    }
+}
+
+/**
+ * Generates the Beancount transactions for the given (new) IB transactions.
+ * The transactions are expected in the order in which they should be written.
+ */
+pub fn write_transactions<'a>(
+    txs: impl IntoIterator<Item = &'a CommonTransaction>,
+    accounts: &LedgerAccounts,
+) -> String {
+    ledger_writer::group_transactions(txs)
+        .iter()
+        .map(|group| write_transaction(group, accounts))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn write_transaction(group: &EntryGroup, accounts: &LedgerAccounts) -> String {
+    let narration = if group.has_dividend() {
+        "Distribution"
+    } else {
+        "Tax Adjustment"
+    };
+
+    let mut output = String::default();
+    writeln!(
+        output,
+        "{} * \"{}\" \"{}\"",
+        group.report_date,
+        escape(&group.symbol),
+        narration
+    )
+    .unwrap();
+    writeln!(output, "  {EFFECTIVE_DATE_KEY}: {}", group.date).unwrap();
+    if let Some(isin) = group.isin() {
+        writeln!(output, "  {ISIN_TAG}: \"{}\"", escape(isin)).unwrap();
+    }
+
+    for tx in &group.txs {
+        writeln!(output, "  ; {}", tx.description).unwrap();
+
+        let (account, amount) = group.posting(tx, accounts);
+        let account = to_account_name(&account);
+        ledger_writer::write_posting(&mut output, INDENT, &account, amount, &group.currency);
+        if !tx.transaction_id.is_empty() {
+            writeln!(output, "    {TRANSACTION_ID_TAG}: \"{}\"", escape(&tx.transaction_id)).unwrap();
+        }
+    }
+
+    let (cash_account, cash) = group.cash_posting(accounts);
+    let cash_account = to_account_name(&cash_account);
+    ledger_writer::write_posting(&mut output, INDENT, &cash_account, cash, &group.currency);
+
+    output
+}
+
+/**
+ * Makes the account name valid in Beancount: each component starts with a
+ * capital letter or a digit and contains only letters, digits, and dashes.
+ * i.e. `Income:Investment:IB:TCBT_AS` -> `Income:Investment:IB:TCBT-AS`.
+ * The empty components, i.e. the `{symbol}` of a transaction without a symbol,
+ * are replaced with `Unknown`.
+ */
+fn to_account_name(account: &str) -> String {
+    account
+        .split(':')
+        .map(|component| {
+            let component: String = component
+                .trim()
+                .chars()
+                .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '-' })
+                .collect();
+            let mut chars = component.trim_start_matches('-').chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => {
+                    log::warn!("Invalid component in the account {account:?}, using {UNKNOWN_COMPONENT}");
+                    UNKNOWN_COMPONENT.to_owned()
+                }
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_accounts, to_account_name, write_transactions};
+    use crate::{model::CommonTransaction, test_fixtures::*};
+
+    #[rstest::rstest]
+    fn test_dividend_with_tax(dividend_with_tax: Vec<CommonTransaction>) {
+        let actual = write_transactions(&dividend_with_tax, &default_accounts());
+
+        let expected = r#"2022-12-14 * "TCBT_AS" "Distribution"
+  effective_date: 2022-12-15
+  ; TCBT CASH DIVIDEND
+  Income:Investment:IB:TCBT-AS                        -6.05 EUR
+  ; TCBT CASH DIVIDEND - NL TAX
+  Expenses:Investment:IB:WithholdingTax                0.91 EUR
+  Assets:Investment:IB:Cash                            5.14 EUR
+"#;
+        assert_eq!(expected, actual);
+    }
+
+    #[rstest::rstest]
+    fn test_tags(tagged_dividend_with_tax: Vec<CommonTransaction>) {
+        let actual = write_transactions(&tagged_dividend_with_tax, &default_accounts());
+
+        assert!(actual.contains("\n  isin: \"NL0009690247\"\n"));
+        assert!(actual.contains(" EUR\n    ib_tx: \"1001\"\n"));
+    }
+
+    #[test]
+    fn test_account_name() {
+        assert_eq!("Expenses:Withholding-Tax", to_account_name("Expenses:Withholding Tax"));
+        assert_eq!("Income:Dividends:Exxw-de", to_account_name("Income:Dividends:exxw.de"));
+    }
+
+    /// The empty components and the leading dashes are invalid in Beancount.
+    #[test]
+    fn test_invalid_account_name() {
+        assert_eq!("Income:Investment:IB:Unknown", to_account_name("Income:Investment:IB:"));
+        assert_eq!("Income:Dividends:AS", to_account_name("Income:Dividends:_AS"));
+        assert_eq!("Income:Unknown:Cash", to_account_name("Income:--:Cash"));
+    }
+}
@@ -3,6 +3,7 @@
  */
 
 use clap::{Parser, Subcommand};
+use ibflex::{journal_backend::Backend, report_writer::OutputFormat};
 
 #[derive(Parser)]
 pub struct Cli {
@@ -15,7 +16,9 @@ pub enum Commands {
     #[command(about = "downloads the IB Flex Cash Tx report")]
     Dl(DlParams),
     #[command(about = "compares IB Flex Cash Tx report and Ledger")]
-    Cmp(CmpParams)
+    Cmp(CmpParams),
+    #[command(about = "lists the attributes and sections of the IB Flex report that are not modelled")]
+    Inspect(InspectParams)
 }
 
 #[derive(Debug, clap::Args)]
@@ -24,6 +27,21 @@ pub struct DlParams {
     pub query_id: Option<u32>,
     #[arg(short, long)]
     pub token: Option<String>,
+    #[arg(short, long, help="The name of the query profile from the configuration", conflicts_with="all")]
+    pub profile: Option<String>,
+    #[arg(short, long, help="Download the reports for all the configured profiles",
+        conflicts_with_all=["query_id", "token"])]
+    pub all: bool,
+    #[arg(short, long, help="Directory to save the reports into. Defaults to the reports directory")]
+    pub output_dir: Option<String>,
+    #[arg(long, help="The report filename. May contain {date}, {from_date}, {to_date}, {query_name}, {account_id}, {profile}")]
+    pub filename_template: Option<String>,
+    #[arg(long, help="Seconds to wait before the first statement download attempt")]
+    pub retry_delay: Option<u64>,
+    #[arg(long, help="The longest wait between the download attempts, in seconds")]
+    pub max_retry_delay: Option<u64>,
+    #[arg(long, help="Seconds to wait for the statement to become available")]
+    pub timeout: Option<u64>,
 }
 
 #[derive(Debug, clap::Args)]
@@ -32,11 +50,48 @@ pub struct CmpParams {
     pub flex_report_path: Option<String>,
     #[arg(short='d', long, help="Directory that contains the Flex .xml reports")]
     pub flex_reports_dir: Option<String>,
+    #[arg(long, help="The filename template of the reports in the directory")]
+    pub filename_template: Option<String>,
+    #[arg(short, long, help="Find the latest report of this query profile in the directory")]
+    pub profile: Option<String>,
     #[arg(short='j', long, help="Ledger journal file to use (.ledger)")]
     pub ledger_journal_file: Option<String>,
+    #[arg(long, default_value_t, help="The journal format and tool: ledger, ledger-native, hledger, or beancount")]
+    pub backend: Backend,
     #[arg(short, long, help="The symbols data file")]
-    pub symbols_path: String,
+    pub symbols_path: Option<String>,
     /// Use Ledger's effective dates
     #[arg(short, long)]
     pub effective: bool,
-}
\ No newline at end of file
+    /// Also report the Ledger postings in the report period that are not in IB
+    #[arg(short, long)]
+    pub bidirectional: bool,
+    #[arg(short, long, value_delimiter = ',',
+        help="Cash action types to compare, i.e. Dividend,WhTax,BrokerIntRcvd. Default: WhTax,Dividend,PaymentInLieu")]
+    pub include: Vec<String>,
+    #[arg(long, default_value_t = 0, help="Match the Ledger postings up to this many days from the IB date")]
+    pub date_tolerance: u32,
+    #[arg(long, default_value_t, help="Output format: text, json, or csv")]
+    pub format: OutputFormat,
+    /// Print the Ledger (or Beancount) entries for the new transactions (text format)
+    #[arg(short, long)]
+    pub ledger_entries: bool,
+    #[arg(long, help="Income account for the generated entries. May contain {symbol} and {currency}")]
+    pub income_account: Option<String>,
+    #[arg(long, help="Withholding tax account for the generated entries")]
+    pub tax_account: Option<String>,
+    #[arg(long, help="Cash account for the generated entries")]
+    pub cash_account: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct InspectParams {
+    #[arg(short, long, help="The report .xml to inspect")]
+    pub flex_report_path: Option<String>,
+    #[arg(short='d', long, help="Directory that contains the Flex .xml reports")]
+    pub flex_reports_dir: Option<String>,
+    #[arg(long, help="The filename template of the reports in the directory")]
+    pub filename_template: Option<String>,
+    #[arg(short, long, help="Find the latest report of this query profile in the directory")]
+    pub profile: Option<String>,
+}
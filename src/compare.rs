@@ -4,39 +4,208 @@
 
 use std::{
     collections::HashMap,
+    fmt::{Display, Write},
     ops::Mul,
     path::{Path, PathBuf},
 };
 
-use anyhow::{Error, Ok};
 use as_symbols::SymbolMetadata;
+use chrono::{Days, NaiveDate};
 use rust_decimal::Decimal;
+use serde::Serialize;
 
 use crate::{
-    flex_enums::{cash_action, CashAction},
-    flex_query::{CashTransaction, FlexQueryResponse},
-    flex_reader::load_report,
-    ledger_runner::{self, get_ledger_start_date},
+    account_types::AccountRules,
+    beancount_writer,
+    flex_enums::CashAction,
+    flex_query::{format_date, CashTransaction, FlexQueryResponse},
+    flex_reader::get_report_path,
+    journal_backend::{Backend, JournalQuery},
+    ledger_runner::get_ledger_start_date,
+    ledger_writer::{self, LedgerAccounts},
     model::CommonTransaction,
-    ISO_DATE_FORMAT,
+    Error, Result, ISO_DATE_FORMAT,
 };
 
 pub const TRANSACTION_DAYS: u8 = 60;
 
+/// The cash action types compared when none are given.
+pub const DEFAULT_INCLUDED_TYPES: [CashAction; 3] =
+    [CashAction::WhTax, CashAction::Dividend, CashAction::PaymentInLieu];
+
+/**
+ * The result of the comparison.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ComparisonReport {
+    /// The Flex report used for the comparison.
+    pub report_path: String,
+    /// The IB transactions that have no matching record in Ledger.
+    pub new: Vec<CommonTransaction>,
+    /// The IB transactions that are already recorded in Ledger.
+    pub matched: Vec<MatchedTransaction>,
+    /// The IB transactions that are not compared.
+    pub skipped: Vec<SkippedTransaction>,
+    /// The Ledger postings without a matching IB transaction: the surplus
+    /// postings for the repeated transactions and, in the bidirectional
+    /// comparison, all the unmatched postings in the report period.
+    pub unmatched_ledger: Vec<CommonTransaction>,
+    /// The period covered by the Flex report.
+    pub period: ReportPeriod,
+}
+
+/// The period of the Flex report, from the statements.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportPeriod {
+    pub from_date: String,
+    pub to_date: String,
+}
+
+impl ReportPeriod {
+    /// Checks if the date (ISO format) is in the period. An unknown period
+    /// contains all dates.
+    pub fn contains(&self, date: &str) -> bool {
+        (self.from_date.is_empty() || self.from_date.as_str() <= date)
+            && (self.to_date.is_empty() || date <= self.to_date.as_str())
+    }
+}
+
+/// The IB transaction with the matching Ledger posting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedTransaction {
+    pub ib: CommonTransaction,
+    pub ledger: CommonTransaction,
+    /// The days from the IB comparison date to the Ledger date.
+    /// Non-zero only with the date tolerance, or for the postings matched by the IB id.
+    pub date_offset: i64,
+    /// The Ledger amount minus the expected one, the IB amount with the opposite sign.
+    /// Non-zero only for the postings matched by the IB transaction id.
+    pub amount_difference: Decimal,
+}
+
+/// The IB transaction that was not compared, with the reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedTransaction {
+    pub transaction: CashTransaction,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The transaction type (cash action) is not compared.
+    ExcludedType(CashAction),
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::ExcludedType(action) => write!(f, "{action} is not compared"),
+        }
+    }
+}
+
+/// The number of records in each category of the comparison.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ComparisonSummary {
+    pub new: usize,
+    pub matched: usize,
+    /// The matched transactions with a different amount in Ledger.
+    pub amount_differences: usize,
+    pub skipped: usize,
+    pub unmatched_ledger: usize,
+}
+
+impl ComparisonReport {
+    pub fn summary(&self) -> ComparisonSummary {
+        ComparisonSummary {
+            new: self.new.len(),
+            matched: self.matched.len(),
+            amount_differences: self.matched.iter().filter(|m| !m.amount_difference.is_zero()).count(),
+            skipped: self.skipped.len(),
+            unmatched_ledger: self.unmatched_ledger.len(),
+        }
+    }
+
+    /**
+     * Renders the report as text, the way the CLI displays it:
+     * the report used, the skipped and the new transactions.
+     */
+    pub fn to_text(&self) -> String {
+        let mut output = format!("Using {}\n", self.report_path);
+
+        for skipped in &self.skipped {
+            writeln!(output, "Skipped: {}", skipped.transaction).unwrap();
+        }
+
+        if self.new.is_empty() && self.matched.is_empty() && self.unmatched_ledger.is_empty() {
+            output.push_str("No new IB transactions found. Exiting...\n");
+            return output;
+        }
+
+        for ibtx in &self.new {
+            writeln!(output, "New: {}", ibtx).unwrap();
+        }
+        for matched in self.matched.iter().filter(|m| m.date_offset != 0) {
+            writeln!(output, "Matched {:+} days: {}", matched.date_offset, matched.ib).unwrap();
+        }
+        for matched in self.matched.iter().filter(|m| !m.amount_difference.is_zero()) {
+            writeln!(output, "Amount differs by {:+}: {}", matched.amount_difference, matched.ib)
+                .unwrap();
+        }
+        for ledger_tx in &self.unmatched_ledger {
+            writeln!(
+                output,
+                "Not in IB: {} {:7} {:8} {:>7} {}, {}",
+                ledger_tx.date.date(),
+                ledger_tx.symbol,
+                ledger_tx.r#type,
+                ledger_tx.amount,
+                ledger_tx.currency,
+                ledger_tx.account
+            )
+            .unwrap();
+        }
+        output.push_str("Complete.\n");
+
+        output
+    }
+
+    /// Creates the Ledger entries for the new transactions.
+    pub fn ledger_entries(&self, accounts: &LedgerAccounts) -> String {
+        ledger_writer::write_transactions(&self.new, accounts)
+    }
+
+    /// Creates the Beancount entries for the new transactions.
+    pub fn beancount_entries(&self, accounts: &LedgerAccounts) -> String {
+        beancount_writer::write_transactions(&self.new, accounts)
+    }
+}
+
 /**
  * Compares transactions in the downloaded IB Flex report to Ledger.
  */
-pub fn compare(params: CompareParams) -> anyhow::Result<String> {
+pub fn compare(params: CompareParams) -> Result<ComparisonReport> {
     log::debug!("comparing distributions, params: {:?}", params);
     // let cfg = get_cmp_config(&params);
 
     // get_ib_report_tx
-    let mut ib_txs = get_ib_tx(&params);
+    let report_path = get_report_path(
+        params.flex_report_path.to_owned(),
+        params.flex_reports_dir.to_owned(),
+        params.filename_template.as_deref(),
+    )?;
+    let included_types = get_included_types(&params.included_types)?;
+    let (cash_txs, period) = read_flex_report(&report_path)?;
+    let (mut ib_txs, skipped) =
+        convert_ib_txs_into_common(cash_txs, &params.symbols_path, &included_types)?;
     log::debug!("Found {} IB transactions", ib_txs.len());
-    if ib_txs.len() == 0 {
-        let msg = "No new IB transactions found. Exiting...\n";
-        print!("{}", msg);
-        return Ok(msg.into());
+    if ib_txs.is_empty() && !params.bidirectional {
+        return Ok(ComparisonReport {
+            report_path,
+            skipped,
+            period,
+            ..Default::default()
+        });
     }
 
     // sort IB records by dates, symbol, type
@@ -45,36 +214,93 @@ pub fn compare(params: CompareParams) -> anyhow::Result<String> {
             tx.report_date.to_owned(),
             tx.date,
             tx.symbol.to_owned(),
-            tx.r#type.to_owned(),
+            tx.r#type.to_string(),
         )
     });
     log::debug!("sorted: {:?}", ib_txs);
 
     // identify the start date for the tx range:
-    let start_date = get_oldest_ib_date(&ib_txs, params.effective_dates);
+    let mut start_date = get_oldest_ib_date(&ib_txs, params.effective_dates)?;
+    // The whole report period is needed to find the Ledger postings missing in IB.
+    if params.bidirectional && !period.from_date.is_empty() && period.from_date < start_date {
+        start_date = period.from_date.to_owned();
+    }
+    if params.date_tolerance > 0 {
+        start_date = subtract_days(&start_date, params.date_tolerance)?;
+    }
 
-    // get_ledger_tx
-    let ledger_txs = ledger_runner::get_ledger_tx(
-        params.ledger_journal_file,
-        start_date,
-        params.effective_dates,
-    );
+    // get the journal postings
+    let comparison_types = get_comparison_types(&included_types);
+    let ledger_txs: Vec<CommonTransaction> = params
+        .backend
+        .create()
+        .get_transactions(&JournalQuery {
+            journal_file: params.ledger_journal_file,
+            start_date,
+            effective_dates: params.effective_dates,
+            account_rules: &params.account_rules,
+            types: &comparison_types,
+        })?
+        .into_iter()
+        .filter(|tx| comparison_types.contains(&tx.r#type))
+        .collect();
     log::debug!("Found {} Ledger transactions", ledger_txs.len());
 
     // compare
-    let result = compare_txs(ib_txs, ledger_txs, params.effective_dates)?;
+    let (new, matched, unconsumed) = compare_txs(
+        ib_txs,
+        &ledger_txs,
+        params.effective_dates,
+        params.date_tolerance,
+    );
+    let unmatched_ledger = find_unmatched_ledger_txs(
+        unconsumed,
+        &matched,
+        params.bidirectional.then_some(&period),
+        params.effective_dates,
+        params.date_tolerance,
+    );
 
-    Ok(result)
+    Ok(ComparisonReport {
+        report_path,
+        new,
+        matched,
+        skipped,
+        unmatched_ledger,
+        period,
+    })
 }
 
+/// Splits the IB transactions into the new ones, which have no matching record
+/// in Ledger, and the matched ones.
+/// Each Ledger posting matches one IB transaction only, so the repeated IB
+/// records need as many Ledger postings. The Ledger postings that were not
+/// matched are returned as the third element.
+/// Within the date tolerance, the closest Ledger posting is matched.
 fn compare_txs(
     ib_txs: Vec<CommonTransaction>,
-    ledger_txs: Vec<CommonTransaction>,
+    ledger_txs: &[CommonTransaction],
     use_effective_date: bool,
-) -> anyhow::Result<String> {
-    let mut result = String::default();
+    date_tolerance: u32,
+) -> (Vec<CommonTransaction>, Vec<MatchedTransaction>, Vec<CommonTransaction>) {
+    let mut new_txs = vec![];
+    let mut matched_txs = vec![];
+    let mut consumed = vec![false; ledger_txs.len()];
+
+    // The Ledger postings tagged with the IB ids are matched first.
+    let mut id_matches = vec![];
+    for ibtx in &ib_txs {
+        let id_match = ledger_txs
+            .iter()
+            .enumerate()
+            .find(|(i, tx)| !consumed[*i] && is_id_match(ibtx, tx));
+        id_matches.push(id_match.map(|(i, tx)| {
+            consumed[i] = true;
+            (i, get_date_offset(ibtx, tx, use_effective_date).unwrap_or_default())
+        }));
+    }
 
-    for ibtx in ib_txs {
+    for (ibtx, id_match) in ib_txs.into_iter().zip(id_matches) {
         log::debug!(
             "Searching for matches for ib tx: {:?}\n among {:?}",
             ibtx,
@@ -84,30 +310,146 @@ fn compare_txs(
         let ib_comparison_date = get_comparison_date(&ibtx, use_effective_date);
         log::debug!("using ib date for comparison: {:?}", ib_comparison_date);
 
-        let matches: Vec<&CommonTransaction> = ledger_txs
-            .iter()
-            .filter(|tx| {
-                // Compare:
-                tx.date.date().format(ISO_DATE_FORMAT).to_string() == ib_comparison_date
-                    && tx.symbol == ibtx.symbol
-                    && tx.amount == ibtx.amount.mul(Decimal::NEGATIVE_ONE)
-                    && tx.currency == ibtx.currency
-                    && tx.r#type == ibtx.r#type
-            })
-            .collect();
-        
-        // log::debug!("date: {:?}", ledger_txs[0].date.date().format(ISO_DATE_FORMAT).to_string());
-        log::debug!("matching ledger txs: {:?}", matches);
-
-        if matches.is_empty() {
-            let output = format!("New: {}\n", ibtx);
-            print!("{}", output);
-            result.push_str(&output);
+        let closest = id_match.or_else(|| {
+            ledger_txs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !consumed[*i])
+                .filter_map(|(i, tx)| {
+                    get_match_offset(&ibtx, tx, use_effective_date, date_tolerance)
+                        .map(|offset| (i, offset))
+                })
+                .min_by_key(|(_, offset)| offset.abs())
+        });
+        log::debug!("matching ledger tx: {:?}", closest.map(|(i, _)| &ledger_txs[i]));
+
+        match closest {
+            Some((i, date_offset)) => {
+                consumed[i] = true;
+                let ledger = ledger_txs[i].clone();
+                matched_txs.push(MatchedTransaction {
+                    amount_difference: ledger.amount + ibtx.amount,
+                    ib: ibtx,
+                    ledger,
+                    date_offset,
+                });
+            }
+            None => new_txs.push(ibtx),
         }
     }
-    println!("Complete.");
 
-    Ok(result)
+    let unconsumed = ledger_txs
+        .iter()
+        .zip(consumed)
+        .filter(|(_, consumed)| !consumed)
+        .map(|(tx, _)| tx.clone())
+        .collect();
+
+    (new_txs, matched_txs, unconsumed)
+}
+
+/// Selects the unmatched Ledger postings to report: the surplus postings,
+/// which repeat an already matched IB transaction, and, in the bidirectional
+/// comparison, all the postings in the report period.
+fn find_unmatched_ledger_txs(
+    unconsumed: Vec<CommonTransaction>,
+    matched: &[MatchedTransaction],
+    period: Option<&ReportPeriod>,
+    use_effective_date: bool,
+    date_tolerance: u32,
+) -> Vec<CommonTransaction> {
+    unconsumed
+        .into_iter()
+        .filter(|tx| {
+            period.is_some_and(|period| {
+                period.contains(&tx.date.date().format(ISO_DATE_FORMAT).to_string())
+            }) || matched
+                .iter()
+                .any(|m| get_match_offset(&m.ib, tx, use_effective_date, date_tolerance).is_some())
+        })
+        .collect()
+}
+
+/**
+ * Checks if the Ledger posting is tagged as the IB transaction: with the same
+ * transaction id (`ib_tx`), or with the same action id (`ib_action`), type, and amount.
+ * The action id alone is not unique, i.e. the dividend and its withholding tax share it.
+ * A different amount does not prevent the match by the transaction id; it is reported
+ * as the `amount_difference` of the match.
+ */
+fn is_id_match(ibtx: &CommonTransaction, ledger_tx: &CommonTransaction) -> bool {
+    if !ibtx.transaction_id.is_empty() && ledger_tx.transaction_id == ibtx.transaction_id {
+        return true;
+    }
+
+    !ibtx.action_id.is_empty()
+        && ledger_tx.transaction_id.is_empty()
+        && ledger_tx.action_id == ibtx.action_id
+        && ledger_tx.r#type == ibtx.r#type
+        && ledger_tx.amount == ibtx.amount.mul(Decimal::NEGATIVE_ONE)
+}
+
+/**
+ * Checks if the Ledger posting records the IB transaction.
+ * The Ledger date may differ from the IB comparison date by up to
+ * `date_tolerance` days. Returns the difference in days if matched.
+ */
+fn get_match_offset(
+    ibtx: &CommonTransaction,
+    ledger_tx: &CommonTransaction,
+    use_effective_date: bool,
+    date_tolerance: u32,
+) -> Option<i64> {
+    // The ids, when both are known, must be the same.
+    let different_id = |ib_id: &str, ledger_id: &str| {
+        !ib_id.is_empty() && !ledger_id.is_empty() && ib_id != ledger_id
+    };
+    if different_id(&ibtx.transaction_id, &ledger_tx.transaction_id)
+        || different_id(&ibtx.action_id, &ledger_tx.action_id)
+    {
+        return None;
+    }
+
+    // The ISIN identifies the security better than the symbol. The transactions
+    // without a symbol, like deposits, are not compared by symbol.
+    let same_security = if !ibtx.isin.is_empty() && !ledger_tx.isin.is_empty() {
+        ledger_tx.isin == ibtx.isin
+    } else {
+        ibtx.symbol.is_empty() || ledger_tx.symbol == ibtx.symbol
+    };
+
+    if !same_security
+        || ledger_tx.amount != ibtx.amount.mul(Decimal::NEGATIVE_ONE)
+        || ledger_tx.currency != ibtx.currency
+        || ledger_tx.r#type != ibtx.r#type
+    {
+        return None;
+    }
+
+    let offset = get_date_offset(ibtx, ledger_tx, use_effective_date)?;
+
+    (offset.unsigned_abs() <= u64::from(date_tolerance)).then_some(offset)
+}
+
+/// The days from the IB comparison date to the Ledger date.
+fn get_date_offset(
+    ibtx: &CommonTransaction,
+    ledger_tx: &CommonTransaction,
+    use_effective_date: bool,
+) -> Option<i64> {
+    let ib_date =
+        NaiveDate::parse_from_str(&get_comparison_date(ibtx, use_effective_date), ISO_DATE_FORMAT)
+            .ok()?;
+    Some((ledger_tx.date.date() - ib_date).num_days())
+}
+
+/// Moves the ISO date back by the given number of days.
+fn subtract_days(date: &str, days: u32) -> Result<String> {
+    NaiveDate::parse_from_str(date, ISO_DATE_FORMAT)
+        .ok()
+        .and_then(|date| date.checked_sub_days(Days::new(days.into())))
+        .map(|date| date.format(ISO_DATE_FORMAT).to_string())
+        .ok_or_else(|| Error::InvalidDate(date.to_owned()))
 }
 
 fn get_comparison_date(ibtx: &CommonTransaction, use_effective_date: bool) -> String {
@@ -119,102 +461,125 @@ fn get_comparison_date(ibtx: &CommonTransaction, use_effective_date: bool) -> St
 
 /// Finds the date of the oldest transaction in the report.
 /// This date is to be used for time-boxing Ledger report.
-fn get_oldest_ib_date(ib_txs: &Vec<CommonTransaction>, use_effective_date: bool) -> String {
-    if ib_txs.is_empty() {
-        return get_ledger_start_date(None);
-    }
-
+fn get_oldest_ib_date(ib_txs: &[CommonTransaction], use_effective_date: bool) -> Result<String> {
     //ib_txs.sort_unstable_by_key(|ibtx| get_comparison_date(&ibtx, use_effective_date) );
-    let oldest_date_record = ib_txs
+    let Some(oldest_date_record) = ib_txs
         .iter()
-        .min_by_key(|ibtx| get_comparison_date(&ibtx, use_effective_date))
-        .expect("got oldest date");
+        .min_by_key(|ibtx| get_comparison_date(ibtx, use_effective_date))
+    else {
+        return get_ledger_start_date(None);
+    };
 
     log::debug!("oldest tx: {:?}", oldest_date_record);
 
-    get_comparison_date(&oldest_date_record, use_effective_date)
+    Ok(get_comparison_date(oldest_date_record, use_effective_date))
 }
 
 /// Load the symbol mappings.
 /// The resulting hashmap is <symbol, ledger symbol>.
-fn load_symbols(path: &PathBuf) -> Result<HashMap<String, String>, Error> {
+pub fn load_symbols(path: &PathBuf) -> Result<HashMap<String, String>> {
     log::debug!("loading symbols from {:?}", path);
 
     // confirm the path exists
     let real_path = Path::new(path);
     if !real_path.exists() {
-        panic!("The symbols file {:?} does not exist!", path);
+        return Err(Error::Symbols(format!(
+            "The symbols file {:?} does not exist!",
+            path
+        )));
     }
 
-    let securities = as_symbols::read_symbols(path)
-        .expect("Parsed symbols")
+    as_symbols::read_symbols(path)
+        .map_err(|e| Error::Symbols(format!("Could not read {:?}: {e:#}", path)))?
         .iter()
-        .map(|sym| map_symbols(sym))
-        .collect();
-    Ok(securities)
+        .map(map_symbols)
+        .collect()
 }
 
 /// Maps the SymbolMetadata into a hashmap of (ib_symbol, ledger_symbol) records.
-fn map_symbols(meta: &SymbolMetadata) -> (String, String) {
-    (
-        match &meta.ib_symbol {
-            Some(ib_sym) => ib_sym.to_owned(),
-            None => {
-                let Some(ref namespace) = meta.namespace
-                    else { panic!("Missing namespace found!") };
-
-                format!("{}:{}", namespace, meta.symbol)
-            },
-        },
-        match &meta.ledger_symbol {
-            Some(ldg_sym) => ldg_sym.to_owned(),
-            None => meta.symbol.to_owned(),
-        },
-    )
+fn map_symbols(meta: &SymbolMetadata) -> Result<(String, String)> {
+    let ib_symbol = match &meta.ib_symbol {
+        Some(ib_sym) => ib_sym.to_owned(),
+        None => {
+            let Some(ref namespace) = meta.namespace else {
+                return Err(Error::Symbols(format!(
+                    "Missing namespace for {}",
+                    meta.symbol
+                )));
+            };
+
+            format!("{}:{}", namespace, meta.symbol)
+        }
+    };
+    let ledger_symbol = match &meta.ledger_symbol {
+        Some(ldg_sym) => ldg_sym.to_owned(),
+        None => meta.symbol.to_owned(),
+    };
+    Ok((ib_symbol, ledger_symbol))
 }
 
 /**
-Returns transactions from the Flex Report, for comparison.
-symbols is a HashMap of symbol rewrites.
-*/
-fn get_ib_tx(cfg: &CompareParams) -> Vec<CommonTransaction> {
-    let ib_txs = read_flex_report(cfg);
+ * Validates the cash action types to compare. Uses the default types if none
+ * are given.
+ */
+fn get_included_types(types: &[String]) -> Result<Vec<CashAction>> {
+    if types.is_empty() {
+        return Ok(DEFAULT_INCLUDED_TYPES.to_vec());
+    }
+
+    types
+        .iter()
+        .map(|name| {
+            CashAction::from_name(name)
+                .ok_or_else(|| Error::Config(format!("Unknown cash action type {name:?}")))
+        })
+        .collect()
+}
 
-    convert_ib_txs_into_common(ib_txs, cfg.symbols_path.as_str())
+/// The types of the Ledger postings. The payments in lieu are compared as
+/// dividends.
+fn get_comparison_types(included_types: &[CashAction]) -> Vec<CashAction> {
+    included_types
+        .iter()
+        .map(|tx_type| match tx_type {
+            CashAction::PaymentInLieu => CashAction::Dividend,
+            _ => tx_type.to_owned(),
+        })
+        .collect()
 }
 
 /// Converts IB CashTransaction XML record into a Common Transaction.
-fn convert_ib_txs_into_common(ib_txs: Vec<CashTransaction>, symbols_path_str: &str) -> Vec<CommonTransaction> {
+/// Returns the converted transactions and the skipped ones.
+fn convert_ib_txs_into_common(
+    ib_txs: Vec<CashTransaction>,
+    symbols_path_str: &str,
+    to_include: &[CashAction],
+) -> Result<(Vec<CommonTransaction>, Vec<SkippedTransaction>)> {
     // load symbols. Need a mapping to the ledger symbols for comparison.
     let symbols_path = PathBuf::from(symbols_path_str);
-    let symbols = load_symbols(&symbols_path).unwrap();
+    let symbols = load_symbols(&symbols_path)?;
     log::debug!("symbols loaded: {:?}", symbols);
 
     let mut txs: Vec<CommonTransaction> = vec![];
+    let mut skipped: Vec<SkippedTransaction> = vec![];
 
-    let to_include = [
-        CashAction::WhTax.to_string(),
-        CashAction::Dividend.to_string(),
-        CashAction::PaymentInLieu.to_string()
-    ];
     log::debug!("to include: {:?}", to_include);
 
     for tx in ib_txs {
-        log::debug!(
-            "Converting ib tx: {:?} {:?} ({:?})",
-            tx.symbol,
-            tx.r#type,
-            cash_action(&tx.r#type)
-        );
+        log::debug!("Converting ib tx: {:?} ({:?})", tx.symbol, tx.r#type);
 
         // skip any not matching the expected types.
-        if !to_include.contains(&cash_action(&tx.r#type)) {
+        if !to_include.contains(&tx.r#type) {
             log::debug!("Skipping. Wrong type ({:?})", &tx.r#type);
-            println!("Skipped: {}", tx);
+            let action = tx.r#type.to_owned();
+            skipped.push(SkippedTransaction {
+                transaction: tx,
+                reason: SkipReason::ExcludedType(action),
+            });
             continue;
         }
 
-        let mut ltx: CommonTransaction = (&tx).into();
+        let mut ltx = CommonTransaction::try_from(&tx)?;
 
         // use adjusted symbols
         if symbols.contains_key(&ltx.symbol) {
@@ -225,58 +590,111 @@ fn convert_ib_txs_into_common(ib_txs: Vec<CashTransaction>, symbols_path_str: &s
         txs.push(ltx);
     }
 
-    txs
+    Ok((txs, skipped))
 }
 
 /**
  * Reads the Cash Transaction records from the Flex Report.
  * Sorts by date/time, symbol, type.
+ * Also returns the period that the statements cover.
  */
-fn read_flex_report(cfg: &CompareParams) -> Vec<CashTransaction> {
-    let content = load_report(cfg.flex_report_path.to_owned(), cfg.flex_reports_dir.to_owned());
-    let response = FlexQueryResponse::from(content);
+fn read_flex_report(report_path: &str) -> Result<(Vec<CashTransaction>, ReportPeriod)> {
+    let content =
+        std::fs::read_to_string(report_path).map_err(|e| Error::io(report_path, e))?;
+    let response = FlexQueryResponse::from_xml(&content)?;
+
+    let statements = &response.flex_statements.flex_statement;
+    let period = ReportPeriod {
+        from_date: statements
+            .iter()
+            .filter_map(|stmt| stmt.from_date)
+            .min()
+            .map(|date| format_date(Some(date)))
+            .unwrap_or_default(),
+        to_date: statements
+            .iter()
+            .filter_map(|stmt| stmt.to_date)
+            .max()
+            .map(|date| format_date(Some(date)))
+            .unwrap_or_default(),
+    };
 
     let mut ib_txs: Vec<CashTransaction> = response
         .flex_statements
         .flex_statement
         .into_iter()
-        .flat_map(|s| s.cash_transactions.cash_transaction)
+        .flat_map(|s| {
+            let account_id = s.account_id;
+            s.cash_transactions
+                .cash_transaction
+                .into_iter()
+                .map(move |mut tx| {
+                    if tx.account_id.is_empty() {
+                        tx.account_id = account_id.to_owned();
+                    }
+                    tx
+                })
+        })
         .collect();
 
     // txs.sort(key=operator.attrgetter("dateTime", "symbol", "type.name"))
     ib_txs.sort_unstable_by_key(|ct| {
         (
-            ct.date_time.to_owned(),
+            ct.date_time,
             ct.symbol.to_owned(),
-            ct.r#type.to_owned(),
+            ct.r#type.ib_name().to_owned(),
         )
     });
 
-    ib_txs
+    Ok((ib_txs, period))
 }
 
 /**
  * Parameters for comparing the IB Flex report and Ledger report.
  */
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct CompareParams {
     pub flex_report_path: Option<String>,
     pub flex_reports_dir: Option<String>,
+    /// The filename template of the reports in the directory.
+    pub filename_template: Option<String>,
     pub ledger_journal_file: Option<String>,
     pub symbols_path: String,
     pub effective_dates: bool,
+    /// Also report the Ledger postings that have no matching IB transaction.
+    pub bidirectional: bool,
+    /// The maximum number of days between the IB and the Ledger dates.
+    pub date_tolerance: u32,
+    /// The cash action types to compare, i.e. `BrokerIntRcvd`.
+    /// If empty, the [`DEFAULT_INCLUDED_TYPES`] are compared.
+    pub included_types: Vec<String>,
+    /// Select and classify the Ledger postings.
+    pub account_rules: AccountRules,
+    /// The tool that reads the journal.
+    pub backend: Backend,
 }
 
 // Tests
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, str::FromStr};
+
+    use rust_decimal::Decimal;
 
-    use super::{compare, load_symbols};
+    use super::{compare, get_included_types, load_symbols};
     use crate::{
-        compare::{convert_ib_txs_into_common, CompareParams},
+        Error,
+        account_types::{AccountRule, AccountRules},
+        beancount_writer,
+        journal_backend::Backend,
+        compare::{
+            compare_txs, convert_ib_txs_into_common, find_unmatched_ledger_txs, CompareParams,
+            SkipReason,
+        },
+        flex_enums::CashAction,
         flex_query::CashTransaction,
+        model::CommonTransaction,
         test_fixtures::*,
     };
 
@@ -290,14 +708,45 @@ mod tests {
         assert!(!symbols.is_empty());
     }
 
+    #[test]
+    fn symbols_missing_test() {
+        let symbol_path = PathBuf::from("tests/no-symbols.csv");
+
+        let actual = load_symbols(&symbol_path);
+
+        assert!(matches!(actual, Err(Error::Symbols(_))));
+    }
+
     #[rstest::rstest]
     #[test_log::test]
     fn test_convert_ib_txs(cash_transactions: Vec<CashTransaction>) {
         let symbols_path = "tests/symbols.csv";
 
-        let ib_tx = convert_ib_txs_into_common(cash_transactions, symbols_path);
+        let included_types = get_included_types(&[]).unwrap();
+
+        let (ib_tx, skipped) =
+            convert_ib_txs_into_common(cash_transactions, symbols_path, &included_types).unwrap();
 
         assert!(!ib_tx.is_empty());
+        assert!(skipped.is_empty());
+    }
+
+    /// The unknown cash types are skipped, not rejected.
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_convert_unknown_type(cash_transactions: Vec<CashTransaction>) {
+        let unknown = CashAction::Other("Lottery Winnings".into());
+        let ib_txs = vec![CashTransaction {
+            r#type: unknown.clone(),
+            ..cash_transactions[0].clone()
+        }];
+
+        let (ib_tx, skipped) =
+            convert_ib_txs_into_common(ib_txs, "tests/symbols.csv", &get_included_types(&[]).unwrap()).unwrap();
+
+        assert!(ib_tx.is_empty());
+        assert_eq!(SkipReason::ExcludedType(unknown), skipped[0].reason);
+        assert_eq!("Lottery Winnings is not compared", skipped[0].reason.to_string());
     }
 
     #[rstest::rstest]
@@ -307,12 +756,12 @@ mod tests {
 
         let actual = compare(cmp_params);
 
-        assert!(!actual.is_err());
+        assert!(actual.is_ok());
     }
 
-    /// tax adjustments come on one day and match several records in the past year.
-    /// The report date needs to be matched to the effective date in this case,
-    /// in addition to the transaction date/transaction date.
+    /// tax adjustments come on one day for several records in the past year.
+    /// Each Ledger posting matches only one IB record, on the report date,
+    /// so the other records of the same amount are new.
     #[test_log::test]
     fn test_compare_w_multiple_matches() {
         let cmp_params = CompareParams {
@@ -321,14 +770,67 @@ mod tests {
             ledger_journal_file: Some("tests/tax_adj_journal.ledger".into()),
             symbols_path: "tests/symbols.csv".to_owned(),
             effective_dates: false,
+            ..Default::default()
         };
         let actual = compare(cmp_params).unwrap();
 
-        println!("result: {:?}", actual);
+        // Ledger has one posting for each amount, on the report date.
+        assert_eq!(4, actual.new.len());
+        assert_eq!(2, actual.matched.len());
+        assert!(actual.unmatched_ledger.is_empty());
+    }
 
-        let expected = "";
+    /// Each Ledger posting is matched once. The surplus on either side is reported.
+    #[rstest::rstest]
+    fn test_compare_one_to_one(cash_transactions: Vec<CashTransaction>) {
+        let ib_tx = CommonTransaction {
+            symbol: "TCBT_AS".into(),
+            ..CommonTransaction::try_from(&cash_transactions[0]).unwrap()
+        };
+        let ledger_tx = CommonTransaction {
+            amount: -ib_tx.amount,
+            account: "Expenses:Investment:IB:Withholding Tax".into(),
+            ..ib_tx.clone()
+        };
 
-        assert_eq!(expected, actual);
+        // Two IB records, one Ledger posting.
+        let (new, matched, unconsumed) =
+            compare_txs(vec![ib_tx.clone(), ib_tx], std::slice::from_ref(&ledger_tx), true, 0);
+        assert_eq!(1, new.len());
+        assert_eq!(1, matched.len());
+        assert!(unconsumed.is_empty());
+
+        // One IB record, two Ledger postings.
+        let (new, matched, unconsumed) =
+            compare_txs(vec![matched[0].ib.clone()], &[ledger_tx.clone(), ledger_tx], true, 0);
+        assert!(new.is_empty());
+        let actual = find_unmatched_ledger_txs(unconsumed, &matched, None, true, 0);
+        assert_eq!(1, actual.len());
+        assert_eq!("Expenses:Investment:IB:Withholding Tax", actual[0].account);
+    }
+
+    /// The posting tagged with the IB transaction id is matched with a different
+    /// amount, and the difference is reported.
+    #[rstest::rstest]
+    fn test_amount_difference(cash_transactions: Vec<CashTransaction>) {
+        let ib_tx = CommonTransaction {
+            transaction_id: "1001".into(),
+            ..CommonTransaction::try_from(&cash_transactions[0]).unwrap()
+        };
+        let ledger_tx = CommonTransaction {
+            amount: -ib_tx.amount + Decimal::ONE,
+            ..ib_tx.clone()
+        };
+
+        let (new, matched, _) = compare_txs(vec![ib_tx], &[ledger_tx], true, 0);
+
+        assert!(new.is_empty());
+        assert_eq!(Decimal::ONE, matched[0].amount_difference);
+
+        let report = super::ComparisonReport { matched, ..Default::default() };
+        assert_eq!(1, report.summary().amount_differences);
+        let text = report.to_text();
+        assert!(text.contains("Amount differs by +1.00: "), "{text}");
     }
 
     /// Same test but using effective dates.
@@ -340,11 +842,13 @@ mod tests {
             ledger_journal_file: Some("tests/tax_adj_journal.ledger".into()),
             symbols_path: "tests/symbols.csv".into(),
             effective_dates: true,
+            ..Default::default()
         };
         let actual = compare(cmp_params).unwrap();
 
         println!("result: {:?}", actual);
 
+        let actual: String = actual.new.iter().map(|tx| format!("New: {tx}\n")).collect();
         let expected = r#"New: 2023-01-24/2022-04-01 BBN     WhTax       0.66 USD, BBN(US09248X1000) CASH DIVIDEND USD 0.1229 PER SHARE - US TAX
 New: 2023-01-24/2022-04-01 BBN     WhTax      -0.53 USD, BBN(US09248X1000) CASH DIVIDEND USD 0.1229 PER SHARE - US TAX
 New: 2023-01-24/2022-04-30 BBN     WhTax       0.66 USD, BBN(US09248X1000) CASH DIVIDEND USD 0.1229 PER SHARE - US TAX
@@ -367,12 +871,12 @@ New: 2023-01-24/2022-04-30 BBN     WhTax      -0.53 USD, BBN(US09248X1000) CASH
             ledger_journal_file: Some("tests/tcf.ledger".into()),
             symbols_path: "tests/symbols.csv".into(),
             effective_dates: false,
+            ..Default::default()
         };
         let actual = compare(cmp_params).unwrap();
 
-        let expected = "";
-
-        assert_eq!(expected, actual);
+        assert!(actual.new.is_empty());
+        assert!(!actual.matched.is_empty());
     }
 
     #[test]
@@ -383,14 +887,182 @@ New: 2023-01-24/2022-04-30 BBN     WhTax      -0.53 USD, BBN(US09248X1000) CASH
             ledger_journal_file: Some("tests/in-lieu.ledger".into()),
             symbols_path: "tests/symbols.csv".into(),
             effective_dates: false,
+            ..Default::default()
         };
 
         let actual = compare(params).unwrap();
 
         //let expected = r#"New: 2023-03-15/2023-03-16 EXXW    PaymentInLieu    3.74 EUR, EXXW(DE000A0H0744) PAYMENT IN LIEU OF DIVIDEND (Mixed Income)\n"#;
-        let expected = "";
-        
-        assert_eq!(expected, actual);
+        assert!(actual.new.is_empty());
+        // The payment in lieu is compared as a dividend.
+        assert_eq!(CashAction::Dividend, actual.matched[0].ledger.r#type);
+    }
+
+    /// The Beancount journal, with an included file.
+    #[test]
+    fn test_beancount_backend() {
+        let params = CompareParams {
+            flex_report_path: Some("tests/report_1.xml".to_owned()),
+            ledger_journal_file: Some("tests/journal.beancount".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            backend: Backend::Beancount,
+            effective_dates: true,
+            ..Default::default()
+        };
+
+        let actual = compare(params).unwrap();
+
+        assert_eq!(1, actual.new.len());
+        assert_eq!(CashAction::Dividend, actual.new[0].r#type);
+        assert_eq!(3, actual.matched.len());
+        let entries = actual.beancount_entries(&beancount_writer::default_accounts());
+        assert!(entries.starts_with("2022-12-14 * \"TCBT_AS\" \"Distribution\"\n"));
+    }
+
+    /// The tagged postings are matched by the IB ids, regardless of the dates,
+    /// and are not matched to the other transactions.
+    #[test]
+    fn test_match_by_id() {
+        let params = CompareParams {
+            flex_report_path: Some("tests/tagged.xml".to_owned()),
+            ledger_journal_file: Some("tests/tagged.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            backend: Backend::LedgerNative,
+            ..Default::default()
+        };
+
+        let actual = compare(params).unwrap();
+
+        assert_eq!(1, actual.new.len());
+        assert_eq!("1004", actual.new[0].transaction_id);
+        assert_eq!(3, actual.matched.len());
+        let tcbt: Vec<&super::MatchedTransaction> =
+            actual.matched.iter().filter(|m| m.ib.action_id == "9001").collect();
+        assert_eq!(2, tcbt.len());
+        assert!(tcbt.iter().all(|m| m.date_offset == 6));
+        assert!(actual.unmatched_ledger.is_empty());
+    }
+
+    /// The native reader gives the same results as Ledger.
+    #[rstest::rstest]
+    #[case("tests/tcf.xml", "tests/tcf.ledger")]
+    #[case("tests/in-lieu.xml", "tests/in-lieu.ledger")]
+    #[case("tests/tax_adj_report.xml", "tests/tax_adj_journal.ledger")]
+    #[case("tests/report_1.xml", "tests/other_types.ledger")]
+    fn test_ledger_native_backend(#[case] report: &str, #[case] journal: &str) {
+        let params = CompareParams {
+            flex_report_path: Some(report.to_owned()),
+            ledger_journal_file: Some(journal.to_owned()),
+            symbols_path: "tests/symbols.csv".into(),
+            effective_dates: true,
+            ..Default::default()
+        };
+        let native_params = CompareParams {
+            backend: Backend::LedgerNative,
+            ..params.clone()
+        };
+
+        let expected = compare(params).unwrap();
+        let actual = compare(native_params).unwrap();
+
+        assert_eq!(expected.new, actual.new);
+        assert_eq!(expected.matched, actual.matched);
+    }
+
+    /// The interest, deposit, and commission refund are compared to their Ledger accounts.
+    #[test]
+    fn test_included_types() {
+        let params = CompareParams {
+            flex_report_path: Some("tests/report_1.xml".to_owned()),
+            ledger_journal_file: Some("tests/other_types.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            included_types: vec!["BrokerIntRcvd".into(), "depositwithdraw".into(), "CommAdj".into()],
+            ..Default::default()
+        };
+
+        let actual = compare(params).unwrap();
+
+        assert!(actual.new.is_empty());
+        assert_eq!(3, actual.matched.len());
+        assert_eq!("Assets:Transfers:IB", actual.matched[1].ledger.account);
+        assert_eq!(4, actual.skipped.len());
+    }
+
+    /// The interest account is not a dividend account, although the dividend rule
+    /// (income and ib) matches it, as the interest rule comes first.
+    #[rstest::rstest]
+    #[case(Backend::Ledger)]
+    #[case(Backend::LedgerNative)]
+    fn test_excluded_type_not_in_ib(#[case] backend: Backend) {
+        let params = CompareParams {
+            flex_report_path: Some("tests/report_1.xml".to_owned()),
+            ledger_journal_file: Some("tests/other_types.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            bidirectional: true,
+            backend,
+            ..Default::default()
+        };
+
+        let actual = compare(params).unwrap();
+
+        assert!(
+            actual.unmatched_ledger.is_empty(),
+            "{:?}",
+            actual.unmatched_ledger
+        );
+    }
+
+    /// The Ledger accounts are classified with the configured rules.
+    #[test]
+    fn test_custom_account_rules() {
+        let params = CompareParams {
+            flex_report_path: Some("tests/report_1.xml".to_owned()),
+            ledger_journal_file: Some("tests/custom_accounts.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            account_rules: AccountRules::new(vec![
+                AccountRule::new("Dividend", &["revenue", "dividends"]),
+                AccountRule::new("WhTax", &["taxes:foreign"]),
+            ])
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let actual = compare(params).unwrap();
+
+        assert_eq!(2, actual.new.len());
+        assert_eq!(2, actual.matched.len());
+        assert_eq!("Revenue:Dividends:Broker", actual.matched[0].ledger.account);
+        assert_eq!("Taxes:Foreign", actual.matched[1].ledger.account);
+    }
+
+    #[test]
+    fn test_unknown_included_type() {
+        let actual = get_included_types(&["Interest".into()]);
+
+        assert!(matches!(actual, Err(Error::Config(_))));
+    }
+
+    /// The payment is booked on the report date, a day before the IB date.
+    #[test]
+    fn test_date_tolerance() {
+        let params = CompareParams {
+            flex_report_path: Some("tests/in-lieu.xml".to_owned()),
+            ledger_journal_file: Some("tests/in-lieu.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            effective_dates: true,
+            ..Default::default()
+        };
+
+        let actual = compare(params.clone()).unwrap();
+        assert_eq!(1, actual.new.len());
+
+        let actual = compare(CompareParams {
+            date_tolerance: 1,
+            ..params
+        })
+        .unwrap();
+        assert!(actual.new.is_empty());
+        assert_eq!(-1, actual.matched[0].date_offset);
     }
 
     #[test_log::test]
@@ -401,13 +1073,62 @@ New: 2023-01-24/2022-04-30 BBN     WhTax      -0.53 USD, BBN(US09248X1000) CASH
             ledger_journal_file: Some("tests/same_symbol.ledger".into()),
             symbols_path: "tests/symbols.csv".into(),
             effective_dates: false,
+            ..Default::default()
         };
         let actual = compare(cmp_params).unwrap();
 
 //         let expected = r#"New: 2023-09-14/2023-09-15 ARCA:SDIV Dividend    5.04 USD, SDIV(US37960A6698) CASH DIVIDEND USD 0.21 PER SHARE (Ordinary Dividend)
 // New: 2023-09-21/2023-09-22 BVME.ETF:SDIV    Dividend   10.26 USD, SDIV(IE00077FRP95) CASH DIVIDEND USD 0.09 PER SHARE (Mixed Income)"#;
-        let expected = "";
+        assert!(actual.new.is_empty());
+    }
 
-        assert_eq!(expected, actual);
+    /// The report lists the matching Ledger posting, and the skipped transactions.
+    #[test_log::test]
+    fn test_comparison_report() {
+        let cmp_params = CompareParams {
+            flex_report_path: Some("tests/report_1.xml".into()),
+            ledger_journal_file: Some("tests/journal.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            ..Default::default()
+        };
+
+        let actual = compare(cmp_params).unwrap();
+
+        let summary = actual.summary();
+        assert_eq!(2, summary.new);
+        assert_eq!(2, summary.matched);
+        assert_eq!(3, summary.skipped);
+
+        let matched = &actual.matched[0];
+        assert_eq!("TRET_AS", matched.ib.symbol);
+        assert_eq!(matched.ib.amount, -matched.ledger.amount);
+        assert_eq!("Income:Investment:IB:TRET_AS", matched.ledger.account);
+
+        let skipped = &actual.skipped[0];
+        assert_eq!(CashAction::CommAdj, skipped.transaction.r#type);
+        assert_eq!(SkipReason::ExcludedType(CashAction::CommAdj), skipped.reason);
+    }
+
+    /// The mistyped dividend in Ledger is reported, the posting after the
+    /// report period is not.
+    #[test_log::test]
+    fn test_bidirectional() {
+        let cmp_params = CompareParams {
+            flex_report_path: Some("tests/report_1.xml".into()),
+            ledger_journal_file: Some("tests/reverse.ledger".into()),
+            symbols_path: "tests/symbols.csv".into(),
+            bidirectional: true,
+            ..Default::default()
+        };
+
+        let actual = compare(cmp_params).unwrap();
+
+        assert_eq!("2022-11-24", actual.period.from_date);
+        assert_eq!(1, actual.new.len());
+        assert_eq!(1, actual.unmatched_ledger.len());
+        let ledger_tx = &actual.unmatched_ledger[0];
+        assert_eq!("TCBT_AS", ledger_tx.symbol);
+        assert_eq!(Decimal::from_str("-6.50").unwrap(), ledger_tx.amount);
+        assert_eq!("Income:Investment:IB:TCBT_AS", ledger_tx.account);
     }
 }
@@ -0,0 +1,303 @@
+/*!
+ * Configuration
+ *
+ * The parameters are read from the command-line arguments, the environment
+ * variables, and the configuration file, in that order of precedence.
+ * The configuration file `ibflex.toml` is searched for in the current directory
+ * and then in the user's configuration directory (`$XDG_CONFIG_HOME/ibflex/`).
+ */
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{account_types::AccountRule, Error, Result};
+
+pub const CONFIG_FILENAME: &str = "ibflex.toml";
+
+pub const ENV_QUERY_ID: &str = "IBFLEX_QUERYID";
+pub const ENV_TOKEN: &str = "IBFLEX_TOKEN";
+pub const ENV_SYMBOLS_PATH: &str = "IBFLEX_SYMBOLS_PATH";
+pub const ENV_REPORT_PATH: &str = "IBFLEX_REPORT_PATH";
+pub const ENV_REPORTS_DIR: &str = "IBFLEX_REPORTS_DIR";
+pub const ENV_FILENAME_TEMPLATE: &str = "IBFLEX_FILENAME_TEMPLATE";
+pub const ENV_JOURNAL_FILE: &str = "IBFLEX_JOURNAL_FILE";
+pub const ENV_FLEX_URL: &str = "IBFLEX_FLEX_URL";
+
+/**
+ * The configuration values. All are optional, as they can be provided from
+ * several sources.
+ */
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default, deserialize_with = "deserialize_query_id")]
+    pub flex_query_id: Option<u32>,
+    pub ib_token: Option<String>,
+    pub symbols_path: Option<String>,
+    pub flex_report_path: Option<String>,
+    /// The directory with the reports. The downloaded reports are saved here.
+    pub flex_reports_dir: Option<String>,
+    /// The filename of the downloaded reports. See [`report_filename`](crate::report_filename).
+    pub filename_template: Option<String>,
+    pub ledger_journal_file: Option<String>,
+    /// The base URL of the Flex Web Service. Defaults to IB's.
+    pub flex_url: Option<String>,
+    /// Named Flex queries, i.e. one per report type or per account.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, QueryProfile>,
+    /// The Ledger account rules for the cash action types, in order.
+    /// Replace the default rules. See [`account_types`](crate::account_types).
+    #[serde(default)]
+    pub account_types: Vec<AccountRule>,
+}
+
+/**
+ * A named Flex query. The token is optional and defaults to the main `ib_token`.
+ */
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct QueryProfile {
+    #[serde(default, deserialize_with = "deserialize_query_id")]
+    pub flex_query_id: Option<u32>,
+    pub ib_token: Option<String>,
+}
+
+impl Config {
+    /**
+     * Loads the configuration from the environment variables and the configuration
+     * file. The environment variables take precedence.
+     */
+    pub fn load() -> Result<Config> {
+        let env_config = Config::from_env(|name| std::env::var(name).ok())?;
+
+        let file_config = match find_config_file() {
+            Some(path) => Config::from_file(&path)?,
+            None => Config::default(),
+        };
+
+        Ok(env_config.or(file_config))
+    }
+
+    /// Reads the configuration file.
+    pub fn from_file(path: &Path) -> Result<Config> {
+        log::debug!("reading configuration from {:?}", path);
+
+        let content =
+            std::fs::read_to_string(path).map_err(|e| Error::io(path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| Error::Config(format!("Invalid configuration file {:?}: {e}", path)))
+    }
+
+    /// Reads the configuration from the environment variables, using the given lookup.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Config> {
+        let flex_query_id = match var(ENV_QUERY_ID) {
+            Some(value) => Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|e| Error::Config(format!("Invalid {ENV_QUERY_ID} {value:?}: {e}")))?,
+            ),
+            None => None,
+        };
+
+        Ok(Config {
+            flex_query_id,
+            ib_token: var(ENV_TOKEN),
+            symbols_path: var(ENV_SYMBOLS_PATH),
+            flex_report_path: var(ENV_REPORT_PATH),
+            flex_reports_dir: var(ENV_REPORTS_DIR),
+            filename_template: var(ENV_FILENAME_TEMPLATE),
+            ledger_journal_file: var(ENV_JOURNAL_FILE),
+            flex_url: var(ENV_FLEX_URL),
+            profiles: BTreeMap::default(),
+            account_types: vec![],
+        })
+    }
+
+    /// Gets the query profile by name.
+    pub fn profile(&self, name: &str) -> Result<&QueryProfile> {
+        self.profiles.get(name).ok_or_else(|| {
+            Error::Config(format!("The profile {name:?} is not configured in {CONFIG_FILENAME}"))
+        })
+    }
+
+    /// Fills the missing values from the other configuration.
+    pub fn or(self, other: Config) -> Config {
+        Config {
+            flex_query_id: self.flex_query_id.or(other.flex_query_id),
+            ib_token: self.ib_token.or(other.ib_token),
+            symbols_path: self.symbols_path.or(other.symbols_path),
+            flex_report_path: self.flex_report_path.or(other.flex_report_path),
+            flex_reports_dir: self.flex_reports_dir.or(other.flex_reports_dir),
+            filename_template: self.filename_template.or(other.filename_template),
+            ledger_journal_file: self.ledger_journal_file.or(other.ledger_journal_file),
+            flex_url: self.flex_url.or(other.flex_url),
+            profiles: if self.profiles.is_empty() {
+                other.profiles
+            } else {
+                self.profiles
+            },
+            account_types: if self.account_types.is_empty() {
+                other.account_types
+            } else {
+                self.account_types
+            },
+        }
+    }
+}
+
+/**
+ * Finds the configuration file. The current directory is checked first, then the
+ * user's configuration directory.
+ */
+pub fn find_config_file() -> Option<PathBuf> {
+    let local = PathBuf::from(CONFIG_FILENAME);
+    if local.is_file() {
+        return Some(local);
+    }
+
+    let user_config = user_config_dir()?.join("ibflex").join(CONFIG_FILENAME);
+    if user_config.is_file() {
+        return Some(user_config);
+    }
+
+    None
+}
+
+/// The user's configuration directory, following the XDG convention.
+fn user_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        return Some(PathBuf::from(home).join(".config"));
+    }
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+/// The query id can be written as a number or as a string.
+fn deserialize_query_id<'de, D>(deserializer: D) -> std::result::Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum QueryId {
+        Number(u32),
+        Text(String),
+    }
+
+    match Option::<QueryId>::deserialize(deserializer)? {
+        Some(QueryId::Number(id)) => Ok(Some(id)),
+        Some(QueryId::Text(text)) => text
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use super::Config;
+
+    #[test]
+    fn test_read_config_file() {
+        let actual = Config::from_file(Path::new("sample_ibflex.toml")).unwrap();
+
+        assert_eq!(Some(0), actual.flex_query_id);
+        assert_eq!(Some("000000000000000000000000".into()), actual.ib_token);
+        assert_eq!(Some("./tests/symbols.csv".into()), actual.symbols_path);
+        assert_eq!(Some("./tests/".into()), actual.flex_reports_dir);
+        assert_eq!(None, actual.ledger_journal_file);
+    }
+
+    #[test]
+    fn test_read_profiles() {
+        let content = r#"
+ib_token = "main token"
+
+[profiles.cash-tx]
+flex_query_id = 111111
+
+[profiles.trades]
+flex_query_id = "222222"
+ib_token = "other account"
+"#;
+        let actual: Config = toml::from_str(content).unwrap();
+
+        assert_eq!(2, actual.profiles.len());
+        let cash = actual.profile("cash-tx").unwrap();
+        assert_eq!(Some(111111), cash.flex_query_id);
+        assert_eq!(None, cash.ib_token);
+        let trades = actual.profile("trades").unwrap();
+        assert_eq!(Some(222222), trades.flex_query_id);
+        assert_eq!(Some("other account".into()), trades.ib_token);
+        assert!(actual.profile("positions").is_err());
+    }
+
+    #[test]
+    fn test_read_account_types() {
+        let content = r#"
+[[account_types]]
+type = "Dividend"
+terms = ["revenue", "dividends"]
+
+[[account_types]]
+type = "WhTax"
+terms = ["taxes:foreign"]
+"#;
+        let actual: Config = toml::from_str(content).unwrap();
+
+        assert_eq!(2, actual.account_types.len());
+        assert_eq!("WhTax", actual.account_types[1].r#type);
+        assert_eq!(vec!["taxes:foreign".to_string()], actual.account_types[1].terms);
+    }
+
+    #[test]
+    fn test_numeric_query_id() {
+        let actual: Config = toml::from_str("flex_query_id = 123456").unwrap();
+
+        assert_eq!(Some(123456), actual.flex_query_id);
+    }
+
+    #[test]
+    fn test_read_env() {
+        let vars = HashMap::from([
+            ("IBFLEX_QUERYID", "654321"),
+            ("IBFLEX_TOKEN", "abc"),
+            ("IBFLEX_FLEX_URL", "http://localhost:8080/"),
+        ]);
+
+        let actual = Config::from_env(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(Some(654321), actual.flex_query_id);
+        assert_eq!(Some("abc".into()), actual.ib_token);
+        assert_eq!(None, actual.symbols_path);
+        assert_eq!(Some("http://localhost:8080/".into()), actual.flex_url);
+    }
+
+    /// The environment variables take precedence over the file.
+    #[test]
+    fn test_precedence() {
+        let env = Config {
+            ib_token: Some("env token".into()),
+            ..Default::default()
+        };
+        let file = Config {
+            flex_query_id: Some(1),
+            ib_token: Some("file token".into()),
+            ..Default::default()
+        };
+
+        let actual = env.or(file);
+
+        assert_eq!(Some(1), actual.flex_query_id);
+        assert_eq!(Some("env token".into()), actual.ib_token);
+    }
+}
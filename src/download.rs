@@ -4,143 +4,312 @@
  * <https://guides.interactivebrokers.com/reportingreference/reportguide/activity%20flex%20query%20reference.htm>
  */
 
-use chrono::Local;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use crate::flex_statement;
+use chrono::{Local, NaiveDate};
 
-const FLEX_URL: &str = "https://gdcdyn.interactivebrokers.com/Universal/servlet/";
+use crate::{
+    flex_statement::{self, StatementResponse, StatementStatus},
+    report_filename::{self, FilenameValues},
+    Error, Result,
+};
+
+pub const FLEX_URL: &str = "https://gdcdyn.interactivebrokers.com/Universal/servlet/";
+pub const USER_AGENT: &str = "Java";
 const REQUEST_ENDPOINT: &str = "FlexStatementService.SendRequest";
 const STMT_ENDPOINT: &str = "FlexStatementService.GetStatement";
 
 /**
  * Parameters for the download.
  */
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct DownloadParams {
     pub query_id: Option<u32>,
     pub token: Option<String>,
+    /// The name of the query profile. Used in the output filename.
+    pub profile: Option<String>,
+    /// The directory to save the report into. The current directory by default.
+    pub output_dir: Option<String>,
+    /// The output filename template. See [`report_filename`](crate::report_filename).
+    pub filename_template: Option<String>,
+    /// The address of the Flex Web Service, ending with a slash.
+    /// The endpoint names are appended to it.
+    pub base_url: String,
+    /// The User-Agent header for the requests.
+    pub user_agent: String,
+    pub retry: RetryPolicy,
+}
+
+impl Default for DownloadParams {
+    fn default() -> Self {
+        Self {
+            query_id: None,
+            token: None,
+            profile: None,
+            output_dir: None,
+            filename_template: None,
+            base_url: FLEX_URL.to_owned(),
+            user_agent: USER_AGENT.to_owned(),
+            retry: RetryPolicy::default(),
+        }
+    }
 }
 
 impl DownloadParams {
     pub fn new(query_id: Option<u32>, token: &Option<String>) -> Self {
         Self {
             query_id,
-            token: match token {
-                Some(tkn) => Some(tkn.to_owned()),
-                None => None,
-            },
+            token: token.as_ref().map(|tkn| tkn.to_owned()),
+            ..Default::default()
         }
     }
 }
 
 /**
- * Downloads the Flex Query Cash Transactions report into a file in the current directory.
+ * Polling settings for the statement download.
+ * IB needs time to prepare the statement. While it is being generated, or the
+ * service is busy, the request and the download are retried with an increasing
+ * delay, until the timeout is reached.
  */
-pub fn download(params: DownloadParams) -> String {
-    let query_id = match params.query_id {
-        Some(qid) => qid.to_string(),
-        None => panic!("The query id is mandatory for the report download!"),
-    };
-    let token = match params.token {
-        Some(tkn) => tkn,
-        None => panic!("The token is mandatory for the report download!"),
-    };
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The wait before the first download attempt.
+    pub initial_delay: Duration,
+    /// The delay between attempts is multiplied by this factor after each attempt.
+    pub backoff_factor: u32,
+    /// The upper limit for the delay between attempts.
+    pub max_delay: Duration,
+    /// The overall time allowed for the statement to become available.
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(5),
+            backoff_factor: 2,
+            max_delay: Duration::from_secs(60),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay to use after the given one.
+    fn next_delay(&self, delay: Duration) -> Duration {
+        (delay * self.backoff_factor).min(self.max_delay)
+    }
+}
 
-    let report = download_report(&query_id, &token);
+/**
+ * Downloads the Flex Query Cash Transactions report into a file in the output
+ * directory (the current directory by default).
+ * The filename is created from the filename template. Without a template,
+ * `{date}_cash-tx.xml` is used, or `{date}_{account_id}_{profile}.xml` for a profile.
+ * Returns the path to the saved report.
+ * The errors reported by the Flex Web Service are returned as
+ * [`FlexServiceError`](crate::flex_statement::FlexServiceError).
+ */
+pub fn download(params: DownloadParams) -> Result<String> {
+    let report = download_report(&params)?;
 
-    let today_date = Local::now().date_naive();
-    let today = today_date.format("%Y-%m-%d");
-    let output_filename = format!("{today}_cash-tx.xml");
+    let today = Local::now().date_naive();
+    let output_path = get_output_path(&params, &report, today);
 
-    std::fs::write(&output_filename, report).expect("successfully saved");
+    if let Some(dir) = output_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| Error::io(dir.display(), e))?;
+    }
+    std::fs::write(&output_path, report).map_err(|e| Error::io(output_path.display(), e))?;
 
-    output_filename
+    Ok(output_path.display().to_string())
+}
+
+/// The path for the downloaded report.
+fn get_output_path(params: &DownloadParams, report: &str, date: NaiveDate) -> PathBuf {
+    let template = report_filename::get_template(
+        params.filename_template.as_deref(),
+        params.profile.as_deref(),
+    );
+    let values = FilenameValues::from_report(report, params.profile.as_deref(), date);
+    let filename = report_filename::render(&template, &values);
+
+    match &params.output_dir {
+        Some(dir) => Path::new(dir).join(filename),
+        None => PathBuf::from(filename),
+    }
 }
 
 /**
  * FlexQueryReport is downloaded in a 2-step process.
  * You need to supply the token (Reports / Settings / FlexWeb Service),
  * and the query id (Reports / Flex Queries / Custom Flex Queries / Configure).
+ * Returns the contents of the report (xml).
  */
-fn download_report(query_id: &str, token: &str) -> String {
-    let resp = request_statement(query_id, token);
-    let stmt_resp = flex_statement::parse_response_text(&resp)
-        .unwrap_or_else(|e| panic!("Parse failed: {e}\nRaw response:\n{resp}"));
+pub fn download_report(params: &DownloadParams) -> Result<String> {
+    let Some(query_id) = params.query_id.map(|qid| qid.to_string()) else {
+        return Err(Error::Config(
+            "The query id is mandatory for the report download!".into(),
+        ));
+    };
+    let Some(token) = &params.token else {
+        return Err(Error::Config(
+            "The token is mandatory for the report download!".into(),
+        ));
+    };
+    let retry = &params.retry;
+
+    let started = Instant::now();
+
+    // The request is retried while the service is busy.
+    let mut delay = retry.initial_delay;
+    let stmt_resp = loop {
+        let resp = request_statement(params, &query_id, token)?;
+        log::debug!("statement response: {resp}");
+
+        match flex_statement::parse_response_text(&resp)? {
+            StatementResponse::Error(error) if error.code.is_in_progress() => {
+                if started.elapsed() + delay > retry.timeout {
+                    return Err(Error::StatementTimeout {
+                        seconds: retry.timeout.as_secs(),
+                        source: error,
+                    });
+                }
+                log::info!(
+                    "The statement request was not accepted ({}), retrying in {}s",
+                    error.message,
+                    delay.as_secs_f32()
+                );
+                std::thread::sleep(delay);
+                delay = retry.next_delay(delay);
+            }
+            response => break response.into_result()?,
+        }
+    };
 
     // Wait before requesting the actual report, as IB needs time to prepare it.
-    for i in (1..=5).rev() {
-        print!("\rDownloading report in {i}s...");
-        std::io::Write::flush(&mut std::io::stdout()).ok();
-        std::thread::sleep(std::time::Duration::from_secs(1));
-    }
-    println!();
+    let mut delay = retry.initial_delay;
+
+    loop {
+        log::info!("Downloading report in {}s...", delay.as_secs_f32());
+        std::thread::sleep(delay);
+
+        let report = download_statement_text(params, &stmt_resp.reference_code, token)?;
 
-    download_statement_text(&stmt_resp.reference_code, token)
+        let status = flex_statement::get_statement_status(&report)?;
+        match status {
+            StatementStatus::Ready => return Ok(report),
+            StatementStatus::InProgress(error) => {
+                log::debug!("statement not ready: {:?}", error);
+
+                delay = retry.next_delay(delay);
+                if started.elapsed() + delay > retry.timeout {
+                    return Err(Error::StatementTimeout {
+                        seconds: retry.timeout.as_secs(),
+                        source: error,
+                    });
+                }
+                log::info!("The statement is not ready yet ({})", error.message);
+            }
+            StatementStatus::Failed(error) => return Err(error.into()),
+        }
+    }
 }
 
 /**
  * Requests the statement. Receives the request id.
  * Returns the text of the response, the content is xml.
  */
-fn request_statement(query_id: &str, token: &str) -> String {
-    let url = format!("{FLEX_URL}{REQUEST_ENDPOINT}?v=3&t={token}&q={query_id}");
-    ureq::get(&url)
-        .set("User-Agent", "Java")
-        .call()
-        .expect("response received")
-        .into_string()
-        .expect("contents of the response")
+fn request_statement(params: &DownloadParams, query_id: &str, token: &str) -> Result<String> {
+    let url = format!("{}{REQUEST_ENDPOINT}?v=3&t={token}&q={query_id}", params.base_url);
+    get_text(params, &url)
 }
 
 /**
  * Downloads the actual report. 2nd step.
  * Requires the reference code received in the 1st step.
  */
-fn download_statement_text(ref_code: &str, token: &str) -> String {
-    let url = format!("{FLEX_URL}{STMT_ENDPOINT}?v=3&q={ref_code}&t={token}");
-    ureq::get(&url)
+fn download_statement_text(params: &DownloadParams, ref_code: &str, token: &str) -> Result<String> {
+    let url = format!("{}{STMT_ENDPOINT}?v=3&q={ref_code}&t={token}", params.base_url);
+    get_text(params, &url)
+}
+
+/// Sends the GET request and returns the response body.
+fn get_text(params: &DownloadParams, url: &str) -> Result<String> {
+    let endpoint = url.split('?').next().unwrap_or_default();
+    ureq::get(url)
+        .set("User-Agent", &params.user_agent)
         .call()
-        .expect("downloaded statement")
+        .map_err(Box::new)?
         .into_string()
-        .expect("text response (xml)")
+        .map_err(|e| Error::io(endpoint, e))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::download::{FLEX_URL, REQUEST_ENDPOINT};
+    use std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use chrono::NaiveDate;
 
+    use crate::download::{
+        get_output_path, DownloadParams, RetryPolicy, FLEX_URL, REQUEST_ENDPOINT,
+    };
+
+    /// The delay grows with each attempt, up to the maximum.
     #[test]
-    /// Test concatenating constants.
-    fn constants_test() {
-        let actual = format!("{FLEX_URL}{REQUEST_ENDPOINT}");
+    fn backoff_test() {
+        let retry = RetryPolicy {
+            initial_delay: Duration::from_secs(5),
+            backoff_factor: 2,
+            max_delay: Duration::from_secs(15),
+            timeout: Duration::from_secs(60),
+        };
+
+        let delay = retry.next_delay(retry.initial_delay);
+        assert_eq!(Duration::from_secs(10), delay);
+        let delay = retry.next_delay(delay);
+        assert_eq!(Duration::from_secs(15), delay);
+    }
+
+    /// The profile name and the account id are part of the default filename.
+    #[test]
+    fn output_path_test() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 8).unwrap();
+        let report = std::fs::read_to_string("tests/tcf.xml").unwrap();
+        let mut params = DownloadParams::default();
 
         assert_eq!(
-            "https://gdcdyn.interactivebrokers.com/Universal/servlet/FlexStatementService.SendRequest",
-            actual
+            PathBuf::from("2023-03-08_cash-tx.xml"),
+            get_output_path(&params, &report, date)
         );
-    }
 
-    /**
-     * Test sending the Flex request. This is step 1 of the 2-step process.
-     *
-     * To run the test, create ibflex.toml config and populate with valid parameters.
-     * Uncomment the [test] line below.
-     */
-    // #[test]
-    #[allow(unused)]
-    fn request_report_test() {
-        use super::{request_statement, DownloadParams};
-        let cfg = DownloadParams::default();
-        let actual = request_statement(
-            &cfg.query_id.unwrap().to_string(),
-            &cfg.token.unwrap(),
+        params.profile = Some("trades".into());
+        assert_eq!(
+            PathBuf::from("2023-03-08_U1234567_trades.xml"),
+            get_output_path(&params, &report, date)
         );
 
-        println!("received: {:?}", actual);
+        params.output_dir = Some("archive".into());
+        params.filename_template = Some("{account_id}_{to_date}.xml".into());
+        assert_eq!(
+            Path::new("archive").join("U1234567_2023-03-07.xml"),
+            get_output_path(&params, &report, date)
+        );
+    }
 
-        assert_ne!(String::default(), actual);
-        assert!(!actual.contains("ERROR"));
+    #[test]
+    /// Test concatenating constants.
+    fn constants_test() {
+        let actual = format!("{FLEX_URL}{REQUEST_ENDPOINT}");
 
-        assert!(false);
+        assert_eq!(
+            "https://gdcdyn.interactivebrokers.com/Universal/servlet/FlexStatementService.SendRequest",
+            actual
+        );
     }
 }
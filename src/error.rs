@@ -0,0 +1,64 @@
+/*!
+ * The library errors.
+ */
+
+use crate::flex_statement::FlexServiceError;
+
+/**
+ * The errors returned by the library functions.
+ */
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading or writing a file failed.
+    #[error("Could not access {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// The Flex report or the Flex Web Service response is not valid XML.
+    #[error("Invalid XML: {0}")]
+    Xml(#[from] quick_xml::DeError),
+    #[error("Invalid date: {0:?}")]
+    InvalidDate(String),
+    #[error("Invalid amount: {0:?}")]
+    InvalidAmount(String),
+    /// Running Ledger or hledger, or reading its output, failed.
+    #[error("Ledger: {0}")]
+    Ledger(String),
+    /// The symbols file is missing or invalid.
+    #[error("Symbols: {0}")]
+    Symbols(String),
+    /// No report file matches the filename template.
+    #[error("No report files found for pattern {0}")]
+    ReportNotFound(String),
+    /// A mandatory parameter is missing, or the configuration is invalid.
+    #[error("Configuration: {0}")]
+    Config(String),
+    /// The request to the Flex Web Service failed.
+    #[error("Request failed: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    /// The error reported by the Flex Web Service.
+    #[error(transparent)]
+    FlexService(#[from] FlexServiceError),
+    /// Writing the output failed.
+    #[error("Output: {0}")]
+    Output(String),
+    /// The statement was still being generated when the timeout was reached.
+    #[error("The statement was not ready in {seconds}s: {source}")]
+    StatementTimeout {
+        seconds: u64,
+        source: FlexServiceError,
+    },
+}
+
+impl Error {
+    /// Creates an I/O error for the given path.
+    pub fn io(path: impl std::fmt::Display, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
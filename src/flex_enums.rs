@@ -5,9 +5,15 @@
  * https://github.com/alensiljak/ibflex/blob/master/ibflex/enums.py
  */
 
-use std::fmt::Display;
+use std::{convert::Infallible, fmt::Display, str::FromStr};
 
-#[derive(Debug)]
+use serde::{Deserialize, Deserializer};
+
+/**
+ * The cash transaction type, `type` of the cash transaction.
+ * The variant names are used in the configuration and the CLI, i.e. `WhTax`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CashAction {
     DepositWithdraw,
     BrokerIntPaid,
@@ -15,46 +21,356 @@ pub enum CashAction {
     WhTax,
     BondIntRcvd,
     BondIntPaid,
+    /// The bond interest in the older reports, received or paid.
+    BondInt,
     Fees,
+    BrokerFees,
+    AdvisorFees,
     Dividend,
     PaymentInLieu,
-    CommAdj
+    CommAdj,
+    PriceAdj,
+    OtherIncome,
+    /// A type that is not known to this version, with the name as in the report.
+    Other(String),
+}
+
+/// The missing type.
+impl Default for CashAction {
+    fn default() -> Self {
+        CashAction::Other(String::new())
+    }
+}
+
+impl CashAction {
+    pub const ALL: [CashAction; 15] = [
+        CashAction::DepositWithdraw,
+        CashAction::BrokerIntPaid,
+        CashAction::BrokerIntRcvd,
+        CashAction::WhTax,
+        CashAction::BondIntRcvd,
+        CashAction::BondIntPaid,
+        CashAction::BondInt,
+        CashAction::Fees,
+        CashAction::BrokerFees,
+        CashAction::AdvisorFees,
+        CashAction::Dividend,
+        CashAction::PaymentInLieu,
+        CashAction::CommAdj,
+        CashAction::PriceAdj,
+        CashAction::OtherIncome,
+    ];
+
+    /// Finds the variant by its name, i.e. `WhTax`, ignoring the case.
+    pub fn from_name(name: &str) -> Option<CashAction> {
+        CashAction::ALL
+            .into_iter()
+            .find(|action| action.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// The type name in the Flex report, i.e. `Withholding Tax`.
+    pub fn ib_name(&self) -> &str {
+        match self {
+            CashAction::DepositWithdraw => "Deposits/Withdrawals",
+            CashAction::BrokerIntPaid => "Broker Interest Paid",
+            CashAction::BrokerIntRcvd => "Broker Interest Received",
+            CashAction::WhTax => "Withholding Tax",
+            CashAction::BondIntRcvd => "Bond Interest Received",
+            CashAction::BondIntPaid => "Bond Interest Paid",
+            CashAction::BondInt => "Bond Interest",
+            CashAction::Fees => "Other Fees",
+            CashAction::BrokerFees => "Broker Fees",
+            CashAction::AdvisorFees => "Advisor Fees",
+            CashAction::Dividend => "Dividends",
+            CashAction::PaymentInLieu => "Payment In Lieu Of Dividends",
+            CashAction::CommAdj => "Commission Adjustments",
+            CashAction::PriceAdj => "Price Adjustments",
+            CashAction::OtherIncome => "Other Income",
+            CashAction::Other(name) => name,
+        }
+    }
 }
 
 impl Display for CashAction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            CashAction::Other(name) => f.pad(name),
+            _ => f.pad(&format!("{:?}", self)),
+        }
+    }
+}
+
+/// Parses the type name in the Flex report, ignoring the case.
+/// The unknown names are the `Other` type.
+impl FromStr for CashAction {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        let name = name.trim();
+        // the names in the older reports
+        let name = match name {
+            "Deposits & Withdrawals" | "Deposits" | "Withdrawals" => CashAction::DepositWithdraw.ib_name(),
+            "Payment In Lieu Of Dividend" => CashAction::PaymentInLieu.ib_name(),
+            _ => name,
+        };
+
+        Ok(CashAction::ALL
+            .into_iter()
+            .find(|action| action.ib_name().eq_ignore_ascii_case(name))
+            .unwrap_or_else(|| CashAction::Other(name.to_owned())))
+    }
+}
+
+impl<'de> Deserialize<'de> for CashAction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name.parse().unwrap_or_else(|never| match never {}))
     }
 }
 
-/// Translates the IB Flex cash action name into the CashAction enum variant.
-/// Example:
-/// let type = cash_action("Deposits/Withdrawals");
-pub fn cash_action(action: &str) -> String {
-    match action {
-        "Deposits/Withdrawals" => CashAction::DepositWithdraw.to_string(),
-        "Broker Interest Paid" => CashAction::BrokerIntPaid.to_string(),
-        "Broker Interest Received" => CashAction::BrokerIntRcvd.to_string(),
-        "Withholding Tax" => CashAction::WhTax.to_string(),
-        "Bond Interest Received" => CashAction::BondIntRcvd.to_string(),
-        "Bond Interest Paid" => CashAction::BondIntPaid.to_string(),
-        "Other Fees" => CashAction::Fees.to_string(),
-        "Dividends" => CashAction::Dividend.to_string(),
-        "Payment In Lieu Of Dividends" => CashAction::PaymentInLieu.to_string(),
-        "Commission Adjustments" => CashAction::CommAdj.to_string(),
-        _ => panic!("Unrecognized cash action type: {}", action)
+/// Defines an enum of the IB codes, with the `Unknown` variant for the codes
+/// that are not (yet) listed here. The enum is parsed from and displayed as the code.
+macro_rules! codes {
+    ($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident = $code:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A code that is not known to this version.
+            Unknown(String),
+        }
+
+        impl $name {
+            /// The code, as in the Flex report.
+            pub fn code(&self) -> &str {
+                match self {
+                    $($name::$variant => $code,)*
+                    $name::Unknown(code) => code,
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Infallible;
+
+            fn from_str(code: &str) -> std::result::Result<Self, Self::Err> {
+                Ok(match code.trim() {
+                    $($code => $name::$variant,)*
+                    other => $name::Unknown(other.to_owned()),
+                })
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.pad(self.code())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                let code = String::deserialize(deserializer)?;
+                Ok(code.parse().unwrap_or_else(|never| match never {}))
+            }
+        }
+    };
+}
+
+codes! {
+    /// The asset category, `assetCategory`.
+    AssetCategory {
+        Stock = "STK",
+        Option = "OPT",
+        Future = "FUT",
+        FutureOption = "FOP",
+        Cash = "CASH",
+        Bond = "BOND",
+        Bill = "BILL",
+        Cfd = "CFD",
+        Warrant = "WAR",
+        StructuredProduct = "IOPT",
+        Fund = "FUND",
+        Commodity = "CMDTY",
+        Crypto = "CRYPTO",
+        FuturesStyleFutureOption = "FSFOP",
+        FuturesStyleOption = "FSOPT",
     }
 }
 
+codes! {
+    /// The trade side, `buySell`.
+    BuySell {
+        Buy = "BUY",
+        Sell = "SELL",
+        /// The cancelled buy.
+        CancelBuy = "BUY (Ca.)",
+        /// The cancelled sell.
+        CancelSell = "SELL (Ca.)",
+    }
+}
+
+codes! {
+    /// Whether the trade opens or closes the position, `openCloseIndicator`.
+    OpenClose {
+        Open = "O",
+        Close = "C",
+        /// Closes the position and opens the opposite one.
+        CloseOpen = "C;O",
+    }
+}
+
+codes! {
+    /// The option right, `putCall`.
+    PutCall {
+        Put = "P",
+        Call = "C",
+    }
+}
+
+codes! {
+    /// The trade type, `transactionType`.
+    TradeType {
+        ExchTrade = "ExchTrade",
+        BookTrade = "BookTrade",
+        DvpTrade = "DvpTrade",
+        FracShare = "FracShare",
+        FracShareCancel = "FracShareCancel",
+        TradeCorrect = "TradeCorrect",
+        TradeCancel = "TradeCancel",
+        FxTrade = "FXTrade",
+        Adjustment = "Adjustment",
+    }
+}
+
+codes! {
+    /// The corporate action type, `type` of the corporate action.
+    CorporateActionType {
+        BondConversion = "BC",
+        BondMaturity = "BM",
+        ContractConsolidation = "CC",
+        CashDividend = "CD",
+        ConvertibleIssue = "CI",
+        ContractSpinoff = "CO",
+        CouponPayment = "CP",
+        ContractSplit = "CS",
+        DelistWorthless = "DW",
+        ForwardSplit = "FS",
+        GenericVoluntary = "GV",
+        ChoiceDividendDelivery = "HD",
+        ChoiceDividendIssue = "HI",
+        IssueChange = "IC",
+        RightsIssue = "RI",
+        ReverseSplit = "RS",
+        StockDividend = "SD",
+        SpinOff = "SO",
+        SubscribeRights = "SR",
+        Merger = "TC",
+        TenderIssue = "TI",
+        Tender = "TO",
+    }
+}
+
+codes! {
+    /// A code of the `notes` attribute, i.e. `Ex;P;O`.
+    Code {
+        Assignment = "A",
+        AutoExercise = "AEx",
+        AutoFx = "AFx",
+        Allocation = "Al",
+        AwayTrade = "Aw",
+        BuyIn = "B",
+        DirectBorrow = "Bo",
+        Closing = "C",
+        Cancelled = "Ca",
+        Corrected = "Co",
+        Exercise = "Ex",
+        Expired = "Ep",
+        InternalTransfer = "I",
+        IbOrdered = "L",
+        LossDisallowed = "LD",
+        LongTerm = "LT",
+        DirectLoan = "Lo",
+        ManualEntry = "M",
+        ManualExercise = "MEx",
+        Opening = "O",
+        PartialExecution = "P",
+        PriceImprovement = "PI",
+        AccrualPosting = "Po",
+        DividendReinvestment = "R",
+        AccrualReversal = "Re",
+        Reimbursement = "Ri",
+        ShortTerm = "ST",
+        Transfer = "T",
+    }
+}
+
+/// Parses the `;`-separated codes, i.e. `Ex;P;O`.
+pub fn parse_codes(value: &str) -> Vec<Code> {
+    value
+        .split(';')
+        .filter(|code| !code.trim().is_empty())
+        .map(|code| code.parse().unwrap_or_else(|never| match never {}))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::cash_action;
+    use super::{parse_codes, BuySell, CashAction, Code, CorporateActionType, OpenClose};
 
     #[test]
     fn test_mapping() {
-        let ib_type = "Withholding Tax";
-        let actual = cash_action(ib_type);
+        let actual: CashAction = "Withholding Tax".parse().unwrap();
+
+        assert_eq!(CashAction::WhTax, actual);
+        assert_eq!("WhTax", actual.to_string());
+        assert_eq!(CashAction::PriceAdj, "Price Adjustments".parse().unwrap());
+        assert_eq!(CashAction::DepositWithdraw, "Deposits & Withdrawals".parse().unwrap());
+        assert_eq!(CashAction::PaymentInLieu, "Payment in Lieu of Dividends".parse().unwrap());
+    }
 
-        assert_eq!("WhTax", actual);
+    #[test]
+    fn test_names_round_trip() {
+        for action in CashAction::ALL {
+            assert_eq!(action, action.ib_name().parse().unwrap());
+            assert_eq!(Some(action.clone()), CashAction::from_name(&action.to_string()));
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Some(CashAction::BrokerIntRcvd), CashAction::from_name("brokerintrcvd"));
+        assert_eq!(None, CashAction::from_name("Broker Interest Received"));
+    }
+
+    #[test]
+    fn test_unknown_action() {
+        let actual: CashAction = "Lottery Winnings".parse().unwrap();
+
+        assert_eq!(CashAction::Other("Lottery Winnings".into()), actual);
+        assert_eq!("Lottery Winnings", actual.ib_name());
+        assert_eq!("Lottery Winnings", actual.to_string());
+    }
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(BuySell::CancelSell, "SELL (Ca.)".parse().unwrap());
+        assert_eq!(OpenClose::CloseOpen, "C;O".parse().unwrap());
+        assert_eq!(CorporateActionType::ReverseSplit, "RS".parse().unwrap());
+        assert_eq!("FS", CorporateActionType::ForwardSplit.to_string());
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        let actual: CorporateActionType = "XX".parse().unwrap();
+
+        assert_eq!(CorporateActionType::Unknown("XX".into()), actual);
+        assert_eq!("XX", actual.code());
+    }
+
+    #[test]
+    fn test_parse_codes() {
+        assert_eq!(vec![Code::Exercise, Code::PartialExecution, Code::Opening], parse_codes("Ex;P;O"));
+        assert_eq!(vec![Code::Unknown("New".into())], parse_codes("New;"));
+        assert!(parse_codes("").is_empty());
+    }
+}
@@ -2,32 +2,55 @@
  * Definitions for Flex Query report
  */
 
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
 use serde::Deserialize;
 
+use crate::{
+    flex_enums::{
+        AssetCategory, BuySell, CashAction, Code, CorporateActionType, OpenClose, PutCall, TradeType,
+    },
+    flex_serde, ISO_DATE_FORMAT,
+};
+
 /**
  * The structure of the IB Flex report.
  */
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct FlexQueryResponse {
+    #[serde(rename = "@queryName", default)]
+    pub query_name: String,
+    #[serde(rename = "@type", default)]
+    pub r#type: String,
+
     #[serde(rename = "FlexStatements", default)]
     pub flex_statements: FlexStatements,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 impl FlexQueryResponse {
     /// Parses the file contents (xml) into the [`FlexQueryResponse`] object.
-    pub fn from_xml(xml: &str) -> anyhow::Result<Self> {
-        Ok(quick_xml::de::from_str(xml)?)
+    /// The error names the path of the invalid element or attribute.
+    pub fn from_xml(xml: &str) -> crate::Result<Self> {
+        let mut deserializer = quick_xml::de::Deserializer::from_str(xml);
+        serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            quick_xml::DeError::Custom(format!("{path}: {}", e.into_inner())).into()
+        })
     }
 }
 
-impl From<String> for FlexQueryResponse {
+impl TryFrom<String> for FlexQueryResponse {
+    type Error = crate::Error;
+
     /**
      * Parses the file contents (xml) into the FlexQueryResponse object.
      */
-    fn from(value: String) -> Self {
-        quick_xml::de::from_str(&value).expect("parsed XML")
+    fn try_from(value: String) -> crate::Result<Self> {
+        Self::from_xml(&value)
     }
 }
 
@@ -38,20 +61,22 @@ pub struct FlexStatements {
 
     #[serde(rename = "FlexStatement", default)]
     pub flex_statement: Vec<FlexStatement>,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct FlexStatement {
     #[serde(rename = "@accountId", default)]
     pub account_id: String,
-    #[serde(rename = "@fromDate", default)]
-    pub from_date: String,
-    #[serde(rename = "@toDate", default)]
-    pub to_date: String,
+    #[serde(rename = "@fromDate", default, deserialize_with = "flex_serde::date")]
+    pub from_date: Option<NaiveDate>,
+    #[serde(rename = "@toDate", default, deserialize_with = "flex_serde::date")]
+    pub to_date: Option<NaiveDate>,
     #[serde(rename = "@period", default)]
     pub period: String,
-    #[serde(rename = "@whenGenerated", default)]
-    pub when_generated: String,
+    #[serde(rename = "@whenGenerated", default, deserialize_with = "flex_serde::date_time")]
+    pub when_generated: Option<NaiveDateTime>,
 
     #[serde(rename = "Trades", default)]
     pub trades: Trades,
@@ -63,12 +88,52 @@ pub struct FlexStatement {
     pub open_positions: OpenPositions,
     #[serde(rename = "CorporateActions", default)]
     pub corporate_actions: CorporateActions,
+    #[serde(rename = "AccountInformation", default)]
+    pub account_information: AccountInformation,
+    #[serde(rename = "EquitySummaryInBase", default)]
+    pub equity_summary_in_base: EquitySummaryInBase,
+    #[serde(rename = "ChangeInNAV", default)]
+    pub change_in_nav: ChangeInNav,
+    #[serde(rename = "ConversionRates", default)]
+    pub conversion_rates: ConversionRates,
+    #[serde(rename = "Transfers", default)]
+    pub transfers: Transfers,
+    #[serde(rename = "InterestAccruals", default)]
+    pub interest_accruals: InterestAccruals,
+    #[serde(rename = "ChangeInDividendAccruals", default)]
+    pub change_in_dividend_accruals: ChangeInDividendAccruals,
+    #[serde(rename = "OpenDividendAccruals", default)]
+    pub open_dividend_accruals: OpenDividendAccruals,
+    #[serde(rename = "SecuritiesInfo", default)]
+    pub securities_info: SecuritiesInfo,
+    #[serde(rename = "OptionEAE", default)]
+    pub option_eae: OptionEaes,
+    #[serde(rename = "FxTransactions", default)]
+    pub fx_transactions: FxTransactions,
+    #[serde(rename = "TransactionTaxes", default)]
+    pub transaction_taxes: TransactionTaxes,
+    #[serde(rename = "UnbundledCommissionDetails", default)]
+    pub unbundled_commission_details: UnbundledCommissionDetails,
+    #[serde(rename = "SLBActivities", default)]
+    pub slb_activities: SlbActivities,
+    #[serde(rename = "StmtFunds", default)]
+    pub stmt_funds: StmtFunds,
+    #[serde(rename = "MTMPerformanceSummaryInBase", default)]
+    pub mtm_performance_summary_in_base: MtmPerformanceSummaryInBase,
+    #[serde(rename = "FIFOPerformanceSummaryInBase", default)]
+    pub fifo_performance_summary_in_base: FifoPerformanceSummaryInBase,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub sections: Vec<RawElement>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct CashTransactions {
     #[serde(rename = "CashTransaction", default)]
     pub cash_transaction: Vec<CashTransaction>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
 }
 
 /**
@@ -77,12 +142,15 @@ pub struct CashTransactions {
  */
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct CashTransaction {
+    /// The account id. Filled from the statement, when not in the report.
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
     /// .report_date is the real date, when the transaction appears in the IB report.
-    #[serde(rename = "@reportDate", default)]
-    pub report_date: String,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
     /// .dateTime is the transaction effective date.
-    #[serde(rename = "@dateTime", default)]
-    pub date_time: String,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
     #[serde(rename = "@symbol", default)]
     pub symbol: String,
     #[serde(rename = "@isin", default)]
@@ -90,13 +158,21 @@ pub struct CashTransaction {
     #[serde(rename = "@listingExchange", default)]
     pub listing_exchange: Option<String>,
     #[serde(rename = "@type", default)]
-    pub r#type: String,
-    #[serde(rename = "@amount", default)]
-    pub amount: String,
+    pub r#type: CashAction,
+    #[serde(rename = "@amount", default, deserialize_with = "flex_serde::decimal")]
+    pub amount: Decimal,
     #[serde(rename = "@currency", default)]
     pub currency: String,
     #[serde(rename = "@description", default)]
     pub description: String,
+    /// The IB id of the record.
+    #[serde(rename = "@transactionID", default)]
+    pub transaction_id: String,
+    /// The id of the corporate action, shared by i.e. the dividend and its withholding tax.
+    #[serde(rename = "@actionID", default)]
+    pub action_id: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 impl Display for CashTransaction {
@@ -104,11 +180,11 @@ impl Display for CashTransaction {
         write!(
             f,
             "{}/{} {:7} {} {} {:>7} {}, {}",
-            self.report_date,
-            &self.date_time[..10],
+            format_date(self.report_date),
+            format_date(self.date_time.map(|date_time| date_time.date())),
             self.symbol,
             self.listing_exchange.as_deref().unwrap_or(""),
-            self.r#type,
+            self.r#type.ib_name(),
             self.amount,
             self.currency,
             self.description
@@ -125,52 +201,62 @@ pub struct Trades {
     pub items: Vec<TradeItem>,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+/// An element of the Trades section. The elements that are not modelled,
+/// like `<Order>` or `<SymbolSummary>`, are kept as raw elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
 pub enum TradeItem {
     Trade(Trade),
     Lot(Lot),
+    Unknown(RawElement),
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct Trade {
-    #[serde(rename = "@reportDate", default)]
-    pub report_date: String,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
     #[serde(rename = "@currency", default)]
     pub currency: String,
     #[serde(rename = "@symbol", default)]
     pub symbol: String,
     #[serde(rename = "@isin", default)]
     pub isin: String,
-    #[serde(rename = "@dateTime", default)]
-    pub date_time: String,
-    #[serde(rename = "@transactionType", default)]
-    pub transaction_type: String,
-    #[serde(rename = "@quantity", default)]
-    pub quantity: String,
-    #[serde(rename = "@tradePrice", default)]
-    pub trade_price: String,
-    #[serde(rename = "@tradeMoney", default)]
-    pub trade_money: String,
-    #[serde(rename = "@proceeds", default)]
-    pub proceeds: String,
-    #[serde(rename = "@ibCommission", default)]
-    pub ib_commission: String,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
+    #[serde(rename = "@transactionType", default, deserialize_with = "flex_serde::code")]
+    pub transaction_type: Option<TradeType>,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@tradePrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub trade_price: Option<Decimal>,
+    #[serde(rename = "@tradeMoney", default, deserialize_with = "flex_serde::decimal")]
+    pub trade_money: Decimal,
+    #[serde(rename = "@proceeds", default, deserialize_with = "flex_serde::decimal")]
+    pub proceeds: Decimal,
+    #[serde(rename = "@ibCommission", default, deserialize_with = "flex_serde::decimal")]
+    pub ib_commission: Decimal,
     #[serde(rename = "@ibCommissionCurrency", default)]
     pub ib_commission_currency: String,
-    #[serde(rename = "@netCash", default)]
-    pub net_cash: String,
-    #[serde(rename = "@cost", default)]
-    pub cost: String,
-    #[serde(rename = "@taxes", default)]
-    pub taxes: String,
-    #[serde(rename = "@buySell", default)]
-    pub buy_sell: String,
-    #[serde(rename = "@openCloseIndicator", default)]
-    pub open_close_indicator: String,
-    #[serde(rename = "@tradeDate", default)]
-    pub trade_date: String,
-    #[serde(rename = "@openDateTime", default)]
-    pub open_date_time: String,
+    #[serde(rename = "@netCash", default, deserialize_with = "flex_serde::decimal")]
+    pub net_cash: Decimal,
+    #[serde(rename = "@cost", default, deserialize_with = "flex_serde::decimal")]
+    pub cost: Decimal,
+    #[serde(rename = "@taxes", default, deserialize_with = "flex_serde::decimal")]
+    pub taxes: Decimal,
+    #[serde(rename = "@buySell", default, deserialize_with = "flex_serde::code")]
+    pub buy_sell: Option<BuySell>,
+    #[serde(rename = "@openCloseIndicator", default, deserialize_with = "flex_serde::code")]
+    pub open_close_indicator: Option<OpenClose>,
+    #[serde(rename = "@notes", default, deserialize_with = "flex_serde::codes")]
+    pub notes: Vec<Code>,
+    #[serde(rename = "@tradeDate", default, deserialize_with = "flex_serde::date")]
+    pub trade_date: Option<NaiveDate>,
+    #[serde(rename = "@openDateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub open_date_time: Option<NaiveDateTime>,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
@@ -181,82 +267,1383 @@ pub struct Lot {
     pub symbol: String,
     #[serde(rename = "@isin", default)]
     pub isin: String,
-    #[serde(rename = "@dateTime", default)]
-    pub date_time: String,
-    #[serde(rename = "@quantity", default)]
-    pub quantity: String,
-    #[serde(rename = "@cost", default)]
-    pub cost: String,
-    #[serde(rename = "@openDateTime", default)]
-    pub open_date_time: String,
-    #[serde(rename = "@reportDate", default)]
-    pub report_date: String,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@cost", default, deserialize_with = "flex_serde::decimal")]
+    pub cost: Decimal,
+    #[serde(rename = "@openDateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub open_date_time: Option<NaiveDateTime>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct CashReport {
     #[serde(rename = "CashReportCurrency", default)]
     pub cash_report_currency: Vec<CashReportCurrency>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct CashReportCurrency {
     #[serde(rename = "@currency", default)]
     pub currency: String,
-    #[serde(rename = "@toDate", default)]
-    pub to_date: String,
-    #[serde(rename = "@endingCash", default)]
-    pub ending_cash: String,
+    #[serde(rename = "@toDate", default, deserialize_with = "flex_serde::date")]
+    pub to_date: Option<NaiveDate>,
+    #[serde(rename = "@endingCash", default, deserialize_with = "flex_serde::decimal")]
+    pub ending_cash: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct OpenPositions {
     #[serde(rename = "OpenPosition", default)]
     pub open_position: Vec<OpenPosition>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct OpenPosition {
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
     #[serde(rename = "@symbol", default)]
     pub symbol: String,
     #[serde(rename = "@isin", default)]
     pub isin: String,
-    #[serde(rename = "@position", default)]
-    pub position: String,
+    #[serde(rename = "@position", default, deserialize_with = "flex_serde::decimal")]
+    pub position: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct CorporateActions {
     #[serde(rename = "CorporateAction", default)]
     pub corporate_action: Vec<CorporateAction>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
 pub struct CorporateAction {
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
     #[serde(rename = "@actionID", default)]
     pub action_id: String,
-    #[serde(rename = "@reportDate", default)]
-    pub report_date: String,
-    #[serde(rename = "@dateTime", default)]
-    pub date_time: String,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
     #[serde(rename = "@currency", default)]
     pub currency: String,
     #[serde(rename = "@symbol", default)]
     pub symbol: String,
     #[serde(rename = "@isin", default)]
     pub isin: String,
+    #[serde(rename = "@type", default, deserialize_with = "flex_serde::code")]
+    pub r#type: Option<CorporateActionType>,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@amount", default, deserialize_with = "flex_serde::decimal")]
+    pub amount: Decimal,
+    #[serde(rename = "@proceeds", default, deserialize_with = "flex_serde::decimal")]
+    pub proceeds: Decimal,
+    #[serde(rename = "@value", default, deserialize_with = "flex_serde::decimal")]
+    pub value: Decimal,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// The account details.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct AccountInformation {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@name", default)]
+    pub name: String,
+    #[serde(rename = "@accountType", default)]
+    pub account_type: String,
+    #[serde(rename = "@customerType", default)]
+    pub customer_type: String,
+    #[serde(rename = "@accountCapabilities", default)]
+    pub account_capabilities: String,
+    #[serde(rename = "@tradingPermissions", default)]
+    pub trading_permissions: String,
+    #[serde(rename = "@registeredRepName", default)]
+    pub registered_rep_name: String,
+    #[serde(rename = "@registeredRepPhone", default)]
+    pub registered_rep_phone: String,
+    #[serde(rename = "@dateOpened", default, deserialize_with = "flex_serde::date")]
+    pub date_opened: Option<NaiveDate>,
+    #[serde(rename = "@dateFunded", default, deserialize_with = "flex_serde::date")]
+    pub date_funded: Option<NaiveDate>,
+    #[serde(rename = "@dateClosed", default, deserialize_with = "flex_serde::date")]
+    pub date_closed: Option<NaiveDate>,
+    #[serde(rename = "@lastTradedDate", default, deserialize_with = "flex_serde::date")]
+    pub last_traded_date: Option<NaiveDate>,
+    #[serde(rename = "@street", default)]
+    pub street: String,
+    #[serde(rename = "@street2", default)]
+    pub street2: String,
+    #[serde(rename = "@city", default)]
+    pub city: String,
+    #[serde(rename = "@state", default)]
+    pub state: String,
+    #[serde(rename = "@country", default)]
+    pub country: String,
+    #[serde(rename = "@postalCode", default)]
+    pub postal_code: String,
+    #[serde(rename = "@streetResidentialAddress", default)]
+    pub street_residential_address: String,
+    #[serde(rename = "@street2ResidentialAddress", default)]
+    pub street2_residential_address: String,
+    #[serde(rename = "@cityResidentialAddress", default)]
+    pub city_residential_address: String,
+    #[serde(rename = "@stateResidentialAddress", default)]
+    pub state_residential_address: String,
+    #[serde(rename = "@countryResidentialAddress", default)]
+    pub country_residential_address: String,
+    #[serde(rename = "@postalCodeResidentialAddress", default)]
+    pub postal_code_residential_address: String,
+    #[serde(rename = "@masterName", default)]
+    pub master_name: String,
+    #[serde(rename = "@ibEntity", default)]
+    pub ib_entity: String,
+    #[serde(rename = "@primaryEmail", default)]
+    pub primary_email: String,
+    #[serde(rename = "@accountRepName", default)]
+    pub account_rep_name: String,
+    #[serde(rename = "@accountRepPhone", default)]
+    pub account_rep_phone: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct EquitySummaryInBase {
+    #[serde(rename = "EquitySummaryByReportDateInBase", default)]
+    pub equity_summary_by_report_date_in_base: Vec<EquitySummaryByReportDateInBase>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The net asset value by asset class on a report date, in the base currency.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct EquitySummaryByReportDateInBase {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@cash", default, deserialize_with = "flex_serde::decimal")]
+    pub cash: Decimal,
+    #[serde(rename = "@cashLong", default, deserialize_with = "flex_serde::decimal")]
+    pub cash_long: Decimal,
+    #[serde(rename = "@cashShort", default, deserialize_with = "flex_serde::decimal")]
+    pub cash_short: Decimal,
+    #[serde(rename = "@slbCashCollateral", default, deserialize_with = "flex_serde::decimal")]
+    pub slb_cash_collateral: Decimal,
+    #[serde(rename = "@stock", default, deserialize_with = "flex_serde::decimal")]
+    pub stock: Decimal,
+    #[serde(rename = "@stockLong", default, deserialize_with = "flex_serde::decimal")]
+    pub stock_long: Decimal,
+    #[serde(rename = "@stockShort", default, deserialize_with = "flex_serde::decimal")]
+    pub stock_short: Decimal,
+    #[serde(rename = "@slbDirectSecuritiesBorrowed", default, deserialize_with = "flex_serde::decimal")]
+    pub slb_direct_securities_borrowed: Decimal,
+    #[serde(rename = "@slbDirectSecuritiesLent", default, deserialize_with = "flex_serde::decimal")]
+    pub slb_direct_securities_lent: Decimal,
+    #[serde(rename = "@options", default, deserialize_with = "flex_serde::decimal")]
+    pub options: Decimal,
+    #[serde(rename = "@optionsLong", default, deserialize_with = "flex_serde::decimal")]
+    pub options_long: Decimal,
+    #[serde(rename = "@optionsShort", default, deserialize_with = "flex_serde::decimal")]
+    pub options_short: Decimal,
+    #[serde(rename = "@bonds", default, deserialize_with = "flex_serde::decimal")]
+    pub bonds: Decimal,
+    #[serde(rename = "@bondsLong", default, deserialize_with = "flex_serde::decimal")]
+    pub bonds_long: Decimal,
+    #[serde(rename = "@bondsShort", default, deserialize_with = "flex_serde::decimal")]
+    pub bonds_short: Decimal,
+    #[serde(rename = "@commodities", default, deserialize_with = "flex_serde::decimal")]
+    pub commodities: Decimal,
+    #[serde(rename = "@commoditiesLong", default, deserialize_with = "flex_serde::decimal")]
+    pub commodities_long: Decimal,
+    #[serde(rename = "@commoditiesShort", default, deserialize_with = "flex_serde::decimal")]
+    pub commodities_short: Decimal,
+    #[serde(rename = "@funds", default, deserialize_with = "flex_serde::decimal")]
+    pub funds: Decimal,
+    #[serde(rename = "@fundsLong", default, deserialize_with = "flex_serde::decimal")]
+    pub funds_long: Decimal,
+    #[serde(rename = "@fundsShort", default, deserialize_with = "flex_serde::decimal")]
+    pub funds_short: Decimal,
+    #[serde(rename = "@notes", default, deserialize_with = "flex_serde::decimal")]
+    pub notes: Decimal,
+    #[serde(rename = "@notesLong", default, deserialize_with = "flex_serde::decimal")]
+    pub notes_long: Decimal,
+    #[serde(rename = "@notesShort", default, deserialize_with = "flex_serde::decimal")]
+    pub notes_short: Decimal,
+    #[serde(rename = "@interestAccruals", default, deserialize_with = "flex_serde::decimal")]
+    pub interest_accruals: Decimal,
+    #[serde(rename = "@interestAccrualsLong", default, deserialize_with = "flex_serde::decimal")]
+    pub interest_accruals_long: Decimal,
+    #[serde(rename = "@interestAccrualsShort", default, deserialize_with = "flex_serde::decimal")]
+    pub interest_accruals_short: Decimal,
+    #[serde(rename = "@dividendAccruals", default, deserialize_with = "flex_serde::decimal")]
+    pub dividend_accruals: Decimal,
+    #[serde(rename = "@dividendAccrualsLong", default, deserialize_with = "flex_serde::decimal")]
+    pub dividend_accruals_long: Decimal,
+    #[serde(rename = "@dividendAccrualsShort", default, deserialize_with = "flex_serde::decimal")]
+    pub dividend_accruals_short: Decimal,
+    #[serde(rename = "@softDollars", default, deserialize_with = "flex_serde::decimal")]
+    pub soft_dollars: Decimal,
+    #[serde(rename = "@crypto", default, deserialize_with = "flex_serde::decimal")]
+    pub crypto: Decimal,
+    #[serde(rename = "@forexCfdUnrealizedPl", default, deserialize_with = "flex_serde::decimal")]
+    pub forex_cfd_unrealized_pl: Decimal,
+    #[serde(rename = "@cfdUnrealizedPl", default, deserialize_with = "flex_serde::decimal")]
+    pub cfd_unrealized_pl: Decimal,
+    #[serde(rename = "@brokerFeesAccrualsComponent", default, deserialize_with = "flex_serde::decimal")]
+    pub broker_fees_accruals_component: Decimal,
+    #[serde(rename = "@total", default, deserialize_with = "flex_serde::decimal")]
+    pub total: Decimal,
+    #[serde(rename = "@totalLong", default, deserialize_with = "flex_serde::decimal")]
+    pub total_long: Decimal,
+    #[serde(rename = "@totalShort", default, deserialize_with = "flex_serde::decimal")]
+    pub total_short: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// The change in the net asset value over the period.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChangeInNav {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fromDate", default, deserialize_with = "flex_serde::date")]
+    pub from_date: Option<NaiveDate>,
+    #[serde(rename = "@toDate", default, deserialize_with = "flex_serde::date")]
+    pub to_date: Option<NaiveDate>,
+    #[serde(rename = "@startingValue", default, deserialize_with = "flex_serde::decimal")]
+    pub starting_value: Decimal,
+    #[serde(rename = "@mtm", default, deserialize_with = "flex_serde::decimal")]
+    pub mtm: Decimal,
+    #[serde(rename = "@realized", default, deserialize_with = "flex_serde::decimal")]
+    pub realized: Decimal,
+    #[serde(rename = "@changeInUnrealized", default, deserialize_with = "flex_serde::decimal")]
+    pub change_in_unrealized: Decimal,
+    #[serde(rename = "@costAdjustments", default, deserialize_with = "flex_serde::decimal")]
+    pub cost_adjustments: Decimal,
+    #[serde(rename = "@transferredPnlAdjustments", default, deserialize_with = "flex_serde::decimal")]
+    pub transferred_pnl_adjustments: Decimal,
+    #[serde(rename = "@depositsWithdrawals", default, deserialize_with = "flex_serde::decimal")]
+    pub deposits_withdrawals: Decimal,
+    #[serde(rename = "@internalCashTransfers", default, deserialize_with = "flex_serde::decimal")]
+    pub internal_cash_transfers: Decimal,
+    #[serde(rename = "@assetTransfers", default, deserialize_with = "flex_serde::decimal")]
+    pub asset_transfers: Decimal,
+    #[serde(rename = "@debitCardActivity", default, deserialize_with = "flex_serde::decimal")]
+    pub debit_card_activity: Decimal,
+    #[serde(rename = "@billPay", default, deserialize_with = "flex_serde::decimal")]
+    pub bill_pay: Decimal,
+    #[serde(rename = "@dividends", default, deserialize_with = "flex_serde::decimal")]
+    pub dividends: Decimal,
+    #[serde(rename = "@withholdingTax", default, deserialize_with = "flex_serde::decimal")]
+    pub withholding_tax: Decimal,
+    #[serde(rename = "@withholding871m", default, deserialize_with = "flex_serde::decimal")]
+    pub withholding871m: Decimal,
+    #[serde(rename = "@withholdingTaxCollected", default, deserialize_with = "flex_serde::decimal")]
+    pub withholding_tax_collected: Decimal,
+    #[serde(rename = "@changeInDividendAccruals", default, deserialize_with = "flex_serde::decimal")]
+    pub change_in_dividend_accruals: Decimal,
+    #[serde(rename = "@interest", default, deserialize_with = "flex_serde::decimal")]
+    pub interest: Decimal,
+    #[serde(rename = "@changeInInterestAccruals", default, deserialize_with = "flex_serde::decimal")]
+    pub change_in_interest_accruals: Decimal,
+    #[serde(rename = "@advisorFees", default, deserialize_with = "flex_serde::decimal")]
+    pub advisor_fees: Decimal,
+    #[serde(rename = "@brokerFees", default, deserialize_with = "flex_serde::decimal")]
+    pub broker_fees: Decimal,
+    #[serde(rename = "@changeInBrokerFees", default, deserialize_with = "flex_serde::decimal")]
+    pub change_in_broker_fees: Decimal,
+    #[serde(rename = "@clientFees", default, deserialize_with = "flex_serde::decimal")]
+    pub client_fees: Decimal,
+    #[serde(rename = "@otherFees", default, deserialize_with = "flex_serde::decimal")]
+    pub other_fees: Decimal,
+    #[serde(rename = "@feesReceivables", default, deserialize_with = "flex_serde::decimal")]
+    pub fees_receivables: Decimal,
+    #[serde(rename = "@commissions", default, deserialize_with = "flex_serde::decimal")]
+    pub commissions: Decimal,
+    #[serde(rename = "@commissionReceivables", default, deserialize_with = "flex_serde::decimal")]
+    pub commission_receivables: Decimal,
+    #[serde(rename = "@forexCommissions", default, deserialize_with = "flex_serde::decimal")]
+    pub forex_commissions: Decimal,
+    #[serde(rename = "@transactionTax", default, deserialize_with = "flex_serde::decimal")]
+    pub transaction_tax: Decimal,
+    #[serde(rename = "@taxReceivables", default, deserialize_with = "flex_serde::decimal")]
+    pub tax_receivables: Decimal,
+    #[serde(rename = "@salesTax", default, deserialize_with = "flex_serde::decimal")]
+    pub sales_tax: Decimal,
+    #[serde(rename = "@billableSalesTax", default, deserialize_with = "flex_serde::decimal")]
+    pub billable_sales_tax: Decimal,
+    #[serde(rename = "@softDollars", default, deserialize_with = "flex_serde::decimal")]
+    pub soft_dollars: Decimal,
+    #[serde(rename = "@netFxTrading", default, deserialize_with = "flex_serde::decimal")]
+    pub net_fx_trading: Decimal,
+    #[serde(rename = "@fxTranslation", default, deserialize_with = "flex_serde::decimal")]
+    pub fx_translation: Decimal,
+    #[serde(rename = "@linkingAdjustments", default, deserialize_with = "flex_serde::decimal")]
+    pub linking_adjustments: Decimal,
+    #[serde(rename = "@other", default, deserialize_with = "flex_serde::decimal")]
+    pub other: Decimal,
+    #[serde(rename = "@endingValue", default, deserialize_with = "flex_serde::decimal")]
+    pub ending_value: Decimal,
+    #[serde(rename = "@twr", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub twr: Option<Decimal>,
+    #[serde(rename = "@corporateActionProceeds", default, deserialize_with = "flex_serde::decimal")]
+    pub corporate_action_proceeds: Decimal,
+    #[serde(rename = "@commissionCreditsRedemption", default, deserialize_with = "flex_serde::decimal")]
+    pub commission_credits_redemption: Decimal,
+    #[serde(rename = "@grantActivity", default, deserialize_with = "flex_serde::decimal")]
+    pub grant_activity: Decimal,
+    #[serde(rename = "@excessFundSweep", default, deserialize_with = "flex_serde::decimal")]
+    pub excess_fund_sweep: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct ConversionRates {
+    #[serde(rename = "ConversionRate", default)]
+    pub conversion_rate: Vec<ConversionRate>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The exchange rate on the report date.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct ConversionRate {
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@fromCurrency", default)]
+    pub from_currency: String,
+    #[serde(rename = "@toCurrency", default)]
+    pub to_currency: String,
+    #[serde(rename = "@rate", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub rate: Option<Decimal>,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct Transfers {
+    #[serde(rename = "Transfer", default)]
+    pub transfer: Vec<Transfer>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// A position or cash transfer, in or out of the account.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@date", default, deserialize_with = "flex_serde::date")]
+    pub date: Option<NaiveDate>,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
     #[serde(rename = "@type", default)]
     pub r#type: String,
+    #[serde(rename = "@direction", default)]
+    pub direction: String,
+    #[serde(rename = "@company", default)]
+    pub company: String,
+    #[serde(rename = "@account", default)]
+    pub account: String,
+    #[serde(rename = "@accountName", default)]
+    pub account_name: String,
+    #[serde(rename = "@deliveringBroker", default)]
+    pub delivering_broker: String,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@transferPrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub transfer_price: Option<Decimal>,
+    #[serde(rename = "@positionAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub position_amount: Decimal,
+    #[serde(rename = "@positionAmountInBase", default, deserialize_with = "flex_serde::decimal")]
+    pub position_amount_in_base: Decimal,
+    #[serde(rename = "@pnlAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub pnl_amount: Decimal,
+    #[serde(rename = "@pnlAmountInBase", default, deserialize_with = "flex_serde::decimal")]
+    pub pnl_amount_in_base: Decimal,
+    #[serde(rename = "@fxPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub fx_pnl: Decimal,
+    #[serde(rename = "@cashTransfer", default, deserialize_with = "flex_serde::decimal")]
+    pub cash_transfer: Decimal,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(rename = "@clientReference", default)]
+    pub client_reference: String,
+    #[serde(rename = "@transactionID", default)]
+    pub transaction_id: String,
+    #[serde(rename = "@levelOfDetail", default)]
+    pub level_of_detail: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct InterestAccruals {
+    #[serde(rename = "InterestAccrualsCurrency", default)]
+    pub interest_accruals_currency: Vec<InterestAccrualsCurrency>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The interest accruals in one currency.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct InterestAccrualsCurrency {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fromDate", default, deserialize_with = "flex_serde::date")]
+    pub from_date: Option<NaiveDate>,
+    #[serde(rename = "@toDate", default, deserialize_with = "flex_serde::date")]
+    pub to_date: Option<NaiveDate>,
+    #[serde(rename = "@startingAccrualBalance", default, deserialize_with = "flex_serde::decimal")]
+    pub starting_accrual_balance: Decimal,
+    #[serde(rename = "@interestAccrued", default, deserialize_with = "flex_serde::decimal")]
+    pub interest_accrued: Decimal,
+    #[serde(rename = "@accrualReversal", default, deserialize_with = "flex_serde::decimal")]
+    pub accrual_reversal: Decimal,
+    #[serde(rename = "@fxTranslation", default, deserialize_with = "flex_serde::decimal")]
+    pub fx_translation: Decimal,
+    #[serde(rename = "@endingAccrualBalance", default, deserialize_with = "flex_serde::decimal")]
+    pub ending_accrual_balance: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChangeInDividendAccruals {
+    #[serde(rename = "ChangeInDividendAccrual", default)]
+    pub change_in_dividend_accrual: Vec<ChangeInDividendAccrual>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// A posting or reversal of the dividend accrual.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChangeInDividendAccrual {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
     #[serde(rename = "@description", default)]
     pub description: String,
-    #[serde(rename = "@amount", default)]
-    pub amount: String,
-    #[serde(rename = "@proceeds", default)]
-    pub proceeds: String,
-    #[serde(rename = "@value", default)]
-    pub value: String,
-    #[serde(rename = "@quantity", default)]
-    pub quantity: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@date", default, deserialize_with = "flex_serde::date")]
+    pub date: Option<NaiveDate>,
+    #[serde(rename = "@exDate", default, deserialize_with = "flex_serde::date")]
+    pub ex_date: Option<NaiveDate>,
+    #[serde(rename = "@payDate", default, deserialize_with = "flex_serde::date")]
+    pub pay_date: Option<NaiveDate>,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@tax", default, deserialize_with = "flex_serde::decimal")]
+    pub tax: Decimal,
+    #[serde(rename = "@fee", default, deserialize_with = "flex_serde::decimal")]
+    pub fee: Decimal,
+    #[serde(rename = "@grossRate", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub gross_rate: Option<Decimal>,
+    #[serde(rename = "@grossAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub gross_amount: Decimal,
+    #[serde(rename = "@netAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub net_amount: Decimal,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(rename = "@fromAcct", default)]
+    pub from_acct: String,
+    #[serde(rename = "@toAcct", default)]
+    pub to_acct: String,
+    #[serde(rename = "@actionID", default)]
+    pub action_id: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct OpenDividendAccruals {
+    #[serde(rename = "OpenDividendAccrual", default)]
+    pub open_dividend_accrual: Vec<OpenDividendAccrual>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The dividend accrual that is not paid yet.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct OpenDividendAccrual {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@exDate", default, deserialize_with = "flex_serde::date")]
+    pub ex_date: Option<NaiveDate>,
+    #[serde(rename = "@payDate", default, deserialize_with = "flex_serde::date")]
+    pub pay_date: Option<NaiveDate>,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@tax", default, deserialize_with = "flex_serde::decimal")]
+    pub tax: Decimal,
+    #[serde(rename = "@fee", default, deserialize_with = "flex_serde::decimal")]
+    pub fee: Decimal,
+    #[serde(rename = "@grossRate", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub gross_rate: Option<Decimal>,
+    #[serde(rename = "@grossAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub gross_amount: Decimal,
+    #[serde(rename = "@netAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub net_amount: Decimal,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(rename = "@fromAcct", default)]
+    pub from_acct: String,
+    #[serde(rename = "@toAcct", default)]
+    pub to_acct: String,
+    #[serde(rename = "@actionID", default)]
+    pub action_id: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct SecuritiesInfo {
+    #[serde(rename = "SecurityInfo", default)]
+    pub security_info: Vec<SecurityInfo>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The details of a security in the statement.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct SecurityInfo {
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@figi", default)]
+    pub figi: String,
+    #[serde(rename = "@underlyingSecurityID", default)]
+    pub underlying_security_id: String,
+    #[serde(rename = "@underlyingListingExchange", default)]
+    pub underlying_listing_exchange: String,
+    #[serde(rename = "@strike", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub strike: Option<Decimal>,
+    #[serde(rename = "@expiry", default, deserialize_with = "flex_serde::date")]
+    pub expiry: Option<NaiveDate>,
+    #[serde(rename = "@putCall", default, deserialize_with = "flex_serde::code")]
+    pub put_call: Option<PutCall>,
+    #[serde(rename = "@principalAdjustFactor", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub principal_adjust_factor: Option<Decimal>,
+    #[serde(rename = "@maturity", default, deserialize_with = "flex_serde::date")]
+    pub maturity: Option<NaiveDate>,
+    #[serde(rename = "@issueDate", default, deserialize_with = "flex_serde::date")]
+    pub issue_date: Option<NaiveDate>,
+    #[serde(rename = "@type", default)]
+    pub r#type: String,
+    #[serde(rename = "@underlyingCategory", default)]
+    pub underlying_category: String,
+    #[serde(rename = "@subCategory", default)]
+    pub sub_category: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@settlementPolicyMethod", default)]
+    pub settlement_policy_method: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct OptionEaes {
+    #[serde(rename = "OptionEAE", default)]
+    pub option_eae: Vec<OptionEae>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// An option exercise, assignment, or expiration.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct OptionEae {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@underlyingSecurityID", default)]
+    pub underlying_security_id: String,
+    #[serde(rename = "@underlyingListingExchange", default)]
+    pub underlying_listing_exchange: String,
+    #[serde(rename = "@strike", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub strike: Option<Decimal>,
+    #[serde(rename = "@expiry", default, deserialize_with = "flex_serde::date")]
+    pub expiry: Option<NaiveDate>,
+    #[serde(rename = "@putCall", default, deserialize_with = "flex_serde::code")]
+    pub put_call: Option<PutCall>,
+    #[serde(rename = "@date", default, deserialize_with = "flex_serde::date")]
+    pub date: Option<NaiveDate>,
+    #[serde(rename = "@transactionType", default)]
+    pub transaction_type: String,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@tradePrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub trade_price: Option<Decimal>,
+    #[serde(rename = "@markPrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub mark_price: Option<Decimal>,
+    #[serde(rename = "@proceeds", default, deserialize_with = "flex_serde::decimal")]
+    pub proceeds: Decimal,
+    #[serde(rename = "@commisionsAndTax", default, deserialize_with = "flex_serde::decimal")]
+    pub commisions_and_tax: Decimal,
+    #[serde(rename = "@costBasis", default, deserialize_with = "flex_serde::decimal")]
+    pub cost_basis: Decimal,
+    #[serde(rename = "@realizedPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub realized_pnl: Decimal,
+    #[serde(rename = "@fxPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub fx_pnl: Decimal,
+    #[serde(rename = "@mtmPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub mtm_pnl: Decimal,
+    #[serde(rename = "@tradeID", default)]
+    pub trade_id: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct FxTransactions {
+    #[serde(rename = "FxTransaction", default)]
+    pub fx_transaction: Vec<FxTransaction>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// A change in the foreign currency position, with its realized P/L.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct FxTransaction {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@functionalCurrency", default)]
+    pub functional_currency: String,
+    #[serde(rename = "@fxCurrency", default)]
+    pub fx_currency: String,
+    #[serde(rename = "@activityDescription", default)]
+    pub activity_description: String,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@proceeds", default, deserialize_with = "flex_serde::decimal")]
+    pub proceeds: Decimal,
+    #[serde(rename = "@cost", default, deserialize_with = "flex_serde::decimal")]
+    pub cost: Decimal,
+    #[serde(rename = "@realizedPL", default, deserialize_with = "flex_serde::decimal")]
+    pub realized_pl: Decimal,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(rename = "@levelOfDetail", default)]
+    pub level_of_detail: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct TransactionTaxes {
+    #[serde(rename = "TransactionTax", default)]
+    pub transaction_tax: Vec<TransactionTax>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// A tax on a trade, i.e. the financial transaction tax.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct TransactionTax {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@date", default, deserialize_with = "flex_serde::date")]
+    pub date: Option<NaiveDate>,
+    #[serde(rename = "@taxDescription", default)]
+    pub tax_description: String,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@taxAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub tax_amount: Decimal,
+    #[serde(rename = "@tradeId", default)]
+    pub trade_id: String,
+    #[serde(rename = "@tradePrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub trade_price: Option<Decimal>,
+    #[serde(rename = "@source", default)]
+    pub source: String,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(rename = "@levelOfDetail", default)]
+    pub level_of_detail: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct UnbundledCommissionDetails {
+    #[serde(rename = "UnbundledCommissionDetail", default)]
+    pub unbundled_commission_detail: Vec<UnbundledCommissionDetail>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The commission of a trade, split by the charges.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct UnbundledCommissionDetail {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@dateTime", default, deserialize_with = "flex_serde::date_time")]
+    pub date_time: Option<NaiveDateTime>,
+    #[serde(rename = "@exchange", default)]
+    pub exchange: String,
+    #[serde(rename = "@buySell", default, deserialize_with = "flex_serde::code")]
+    pub buy_sell: Option<BuySell>,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@price", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub price: Option<Decimal>,
+    #[serde(rename = "@amount", default, deserialize_with = "flex_serde::decimal")]
+    pub amount: Decimal,
+    #[serde(rename = "@totalCommission", default, deserialize_with = "flex_serde::decimal")]
+    pub total_commission: Decimal,
+    #[serde(rename = "@brokerExecutionCharge", default, deserialize_with = "flex_serde::decimal")]
+    pub broker_execution_charge: Decimal,
+    #[serde(rename = "@brokerClearingCharge", default, deserialize_with = "flex_serde::decimal")]
+    pub broker_clearing_charge: Decimal,
+    #[serde(rename = "@thirdPartyExecutionCharge", default, deserialize_with = "flex_serde::decimal")]
+    pub third_party_execution_charge: Decimal,
+    #[serde(rename = "@thirdPartyClearingCharge", default, deserialize_with = "flex_serde::decimal")]
+    pub third_party_clearing_charge: Decimal,
+    #[serde(rename = "@thirdPartyRegulatoryCharge", default, deserialize_with = "flex_serde::decimal")]
+    pub third_party_regulatory_charge: Decimal,
+    #[serde(rename = "@regFINRATradingActivityFee", default, deserialize_with = "flex_serde::decimal")]
+    pub reg_finra_trading_activity_fee: Decimal,
+    #[serde(rename = "@regSection31TransactionFee", default, deserialize_with = "flex_serde::decimal")]
+    pub reg_section31_transaction_fee: Decimal,
+    #[serde(rename = "@regOther", default, deserialize_with = "flex_serde::decimal")]
+    pub reg_other: Decimal,
+    #[serde(rename = "@other", default, deserialize_with = "flex_serde::decimal")]
+    pub other: Decimal,
+    #[serde(rename = "@orderReference", default)]
+    pub order_reference: String,
+    #[serde(rename = "@tradeID", default)]
+    pub trade_id: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct SlbActivities {
+    #[serde(rename = "SLBActivity", default)]
+    pub slb_activity: Vec<SlbActivity>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// A securities lending or borrowing activity.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct SlbActivity {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@date", default, deserialize_with = "flex_serde::date")]
+    pub date: Option<NaiveDate>,
+    #[serde(rename = "@slbTransactionId", default)]
+    pub slb_transaction_id: String,
+    #[serde(rename = "@activityDescription", default)]
+    pub activity_description: String,
+    #[serde(rename = "@type", default)]
+    pub r#type: String,
+    #[serde(rename = "@exchange", default)]
+    pub exchange: String,
+    #[serde(rename = "@quantity", default, deserialize_with = "flex_serde::decimal")]
+    pub quantity: Decimal,
+    #[serde(rename = "@feeRate", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fee_rate: Option<Decimal>,
+    #[serde(rename = "@collateralAmount", default, deserialize_with = "flex_serde::decimal")]
+    pub collateral_amount: Decimal,
+    #[serde(rename = "@markQuantity", default, deserialize_with = "flex_serde::decimal")]
+    pub mark_quantity: Decimal,
+    #[serde(rename = "@markPriorPrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub mark_prior_price: Option<Decimal>,
+    #[serde(rename = "@markCurrentPrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub mark_current_price: Option<Decimal>,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct StmtFunds {
+    #[serde(rename = "StatementOfFundsLine", default)]
+    pub statement_of_funds_line: Vec<StatementOfFundsLine>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// A line of the statement of funds, with the running balance.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct StatementOfFundsLine {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@currency", default)]
+    pub currency: String,
+    #[serde(rename = "@fxRateToBase", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub fx_rate_to_base: Option<Decimal>,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@date", default, deserialize_with = "flex_serde::date")]
+    pub date: Option<NaiveDate>,
+    #[serde(rename = "@settleDate", default, deserialize_with = "flex_serde::date")]
+    pub settle_date: Option<NaiveDate>,
+    #[serde(rename = "@activityCode", default)]
+    pub activity_code: String,
+    #[serde(rename = "@activityDescription", default)]
+    pub activity_description: String,
+    #[serde(rename = "@tradeID", default)]
+    pub trade_id: String,
+    #[serde(rename = "@orderID", default)]
+    pub order_id: String,
+    #[serde(rename = "@buySell", default, deserialize_with = "flex_serde::code")]
+    pub buy_sell: Option<BuySell>,
+    #[serde(rename = "@tradeQuantity", default, deserialize_with = "flex_serde::decimal")]
+    pub trade_quantity: Decimal,
+    #[serde(rename = "@tradePrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub trade_price: Option<Decimal>,
+    #[serde(rename = "@tradeGross", default, deserialize_with = "flex_serde::decimal")]
+    pub trade_gross: Decimal,
+    #[serde(rename = "@tradeCommission", default, deserialize_with = "flex_serde::decimal")]
+    pub trade_commission: Decimal,
+    #[serde(rename = "@tradeTax", default, deserialize_with = "flex_serde::decimal")]
+    pub trade_tax: Decimal,
+    #[serde(rename = "@debit", default, deserialize_with = "flex_serde::decimal")]
+    pub debit: Decimal,
+    #[serde(rename = "@credit", default, deserialize_with = "flex_serde::decimal")]
+    pub credit: Decimal,
+    #[serde(rename = "@amount", default, deserialize_with = "flex_serde::decimal")]
+    pub amount: Decimal,
+    #[serde(rename = "@balance", default, deserialize_with = "flex_serde::decimal")]
+    pub balance: Decimal,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@levelOfDetail", default)]
+    pub level_of_detail: String,
+    #[serde(rename = "@transactionID", default)]
+    pub transaction_id: String,
+    #[serde(rename = "@actionID", default)]
+    pub action_id: String,
+    #[serde(rename = "@tradeCode", default)]
+    pub trade_code: String,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct MtmPerformanceSummaryInBase {
+    #[serde(rename = "MTMPerformanceSummaryUnderlying", default)]
+    pub mtm_performance_summary_underlying: Vec<MtmPerformanceSummaryUnderlying>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The mark-to-market P/L of a security, in the base currency.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct MtmPerformanceSummaryUnderlying {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@prevCloseQuantity", default, deserialize_with = "flex_serde::decimal")]
+    pub prev_close_quantity: Decimal,
+    #[serde(rename = "@prevClosePrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub prev_close_price: Option<Decimal>,
+    #[serde(rename = "@closeQuantity", default, deserialize_with = "flex_serde::decimal")]
+    pub close_quantity: Decimal,
+    #[serde(rename = "@closePrice", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub close_price: Option<Decimal>,
+    #[serde(rename = "@transactionMtm", default, deserialize_with = "flex_serde::decimal")]
+    pub transaction_mtm: Decimal,
+    #[serde(rename = "@priorOpenMtm", default, deserialize_with = "flex_serde::decimal")]
+    pub prior_open_mtm: Decimal,
+    #[serde(rename = "@commissions", default, deserialize_with = "flex_serde::decimal")]
+    pub commissions: Decimal,
+    #[serde(rename = "@other", default, deserialize_with = "flex_serde::decimal")]
+    pub other: Decimal,
+    #[serde(rename = "@total", default, deserialize_with = "flex_serde::decimal")]
+    pub total: Decimal,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(rename = "@corpActionMtm", default, deserialize_with = "flex_serde::decimal")]
+    pub corp_action_mtm: Decimal,
+    #[serde(rename = "@dividends", default, deserialize_with = "flex_serde::decimal")]
+    pub dividends: Decimal,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct FifoPerformanceSummaryInBase {
+    #[serde(rename = "FIFOPerformanceSummaryUnderlying", default)]
+    pub fifo_performance_summary_underlying: Vec<FifoPerformanceSummaryUnderlying>,
+    #[serde(flatten, deserialize_with = "flex_serde::sections")]
+    pub elements: Vec<RawElement>,
+}
+
+/// The realized and unrealized FIFO P/L of a security, in the base currency.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct FifoPerformanceSummaryUnderlying {
+    #[serde(rename = "@accountId", default)]
+    pub account_id: String,
+    #[serde(rename = "@acctAlias", default)]
+    pub acct_alias: String,
+    #[serde(rename = "@model", default)]
+    pub model: String,
+    #[serde(rename = "@assetCategory", default, deserialize_with = "flex_serde::code")]
+    pub asset_category: Option<AssetCategory>,
+    #[serde(rename = "@symbol", default)]
+    pub symbol: String,
+    #[serde(rename = "@description", default)]
+    pub description: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: String,
+    #[serde(rename = "@securityID", default)]
+    pub security_id: String,
+    #[serde(rename = "@securityIDType", default)]
+    pub security_id_type: String,
+    #[serde(rename = "@cusip", default)]
+    pub cusip: String,
+    #[serde(rename = "@isin", default)]
+    pub isin: String,
+    #[serde(rename = "@listingExchange", default)]
+    pub listing_exchange: String,
+    #[serde(rename = "@underlyingConid", default)]
+    pub underlying_conid: String,
+    #[serde(rename = "@underlyingSymbol", default)]
+    pub underlying_symbol: String,
+    #[serde(rename = "@issuer", default)]
+    pub issuer: String,
+    #[serde(rename = "@multiplier", default, deserialize_with = "flex_serde::optional_decimal")]
+    pub multiplier: Option<Decimal>,
+    #[serde(rename = "@reportDate", default, deserialize_with = "flex_serde::date")]
+    pub report_date: Option<NaiveDate>,
+    #[serde(rename = "@realizedSTProfit", default, deserialize_with = "flex_serde::decimal")]
+    pub realized_st_profit: Decimal,
+    #[serde(rename = "@realizedSTLoss", default, deserialize_with = "flex_serde::decimal")]
+    pub realized_st_loss: Decimal,
+    #[serde(rename = "@realizedLTProfit", default, deserialize_with = "flex_serde::decimal")]
+    pub realized_lt_profit: Decimal,
+    #[serde(rename = "@realizedLTLoss", default, deserialize_with = "flex_serde::decimal")]
+    pub realized_lt_loss: Decimal,
+    #[serde(rename = "@totalRealizedPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_realized_pnl: Decimal,
+    #[serde(rename = "@unrealizedProfit", default, deserialize_with = "flex_serde::decimal")]
+    pub unrealized_profit: Decimal,
+    #[serde(rename = "@unrealizedLoss", default, deserialize_with = "flex_serde::decimal")]
+    pub unrealized_loss: Decimal,
+    #[serde(rename = "@unrealizedSTProfit", default, deserialize_with = "flex_serde::decimal")]
+    pub unrealized_st_profit: Decimal,
+    #[serde(rename = "@unrealizedSTLoss", default, deserialize_with = "flex_serde::decimal")]
+    pub unrealized_st_loss: Decimal,
+    #[serde(rename = "@unrealizedLTProfit", default, deserialize_with = "flex_serde::decimal")]
+    pub unrealized_lt_profit: Decimal,
+    #[serde(rename = "@unrealizedLTLoss", default, deserialize_with = "flex_serde::decimal")]
+    pub unrealized_lt_loss: Decimal,
+    #[serde(rename = "@totalUnrealizedPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_unrealized_pnl: Decimal,
+    #[serde(rename = "@totalFifoPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_fifo_pnl: Decimal,
+    #[serde(rename = "@totalRealizedCapitalGainsPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_realized_capital_gains_pnl: Decimal,
+    #[serde(rename = "@totalRealizedFxPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_realized_fx_pnl: Decimal,
+    #[serde(rename = "@totalUnrealizedCapitalGainsPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_unrealized_capital_gains_pnl: Decimal,
+    #[serde(rename = "@totalUnrealizedFxPnl", default, deserialize_with = "flex_serde::decimal")]
+    pub total_unrealized_fx_pnl: Decimal,
+    #[serde(rename = "@costAdj", default, deserialize_with = "flex_serde::decimal")]
+    pub cost_adj: Decimal,
+    #[serde(rename = "@code", default, deserialize_with = "flex_serde::codes")]
+    pub code: Vec<Code>,
+    #[serde(flatten, deserialize_with = "flex_serde::extra")]
+    pub extra: BTreeMap<String, String>,
+}
+
+/**
+ * An element of the report that is not modelled, i.e. a new statement section.
+ * The attributes are by the name, and the children in the order of the report.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RawElement {
+    pub name: String,
+    pub attributes: BTreeMap<String, String>,
+    pub children: Vec<RawElement>,
+    pub text: String,
+}
+
+/// Formats the optional date as ISO, or empty.
+pub fn format_date(date: Option<NaiveDate>) -> String {
+    date.map(|date| date.format(ISO_DATE_FORMAT).to_string())
+        .unwrap_or_default()
 }
 
 // pub enum TxType {
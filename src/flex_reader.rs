@@ -3,85 +3,134 @@
  * The logic for choosing a file.
  */
 
-const FILE_SUFFIX: &str = "_cash-tx.xml";
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::NaiveDate;
+
+use crate::{report_filename, Error, Result, ISO_DATE_FORMAT};
 
 /**
  * Loads the Flex report.
  * If the direct path to the report is given, then the report is loaded. This
  * parameter takes precedence over path.
  * If the path to the directory is given, the latest report from that directory
- * will be loaded.
+ * will be loaded. The reports are recognized by the filename template, which
+ * defaults to [`DEFAULT_TEMPLATE`](report_filename::DEFAULT_TEMPLATE).
  */
-pub fn load_report(flex_report_path: Option<String>, flex_reports_dir: Option<String>) -> String {
-    log::debug!("load_report with: {:?}, {:?}", flex_report_path, flex_reports_dir);
+pub fn load_report(
+    flex_report_path: Option<String>,
+    flex_reports_dir: Option<String>,
+    filename_template: Option<&str>,
+) -> Result<String> {
+    let report_path = get_report_path(flex_report_path, flex_reports_dir, filename_template)?;
+
+    std::fs::read_to_string(&report_path).map_err(|e| Error::io(report_path, e))
+}
+
+/**
+ * Chooses the report file to load. See [`load_report`].
+ */
+pub fn get_report_path(
+    flex_report_path: Option<String>,
+    flex_reports_dir: Option<String>,
+    filename_template: Option<&str>,
+) -> Result<String> {
+    log::debug!("get_report_path with: {:?}, {:?}", flex_report_path, flex_reports_dir);
 
     let report_path = match flex_report_path {
         Some(file_path) => file_path.to_owned(),
-        None => get_latest_report_path(flex_reports_dir),
+        None => get_latest_report_path(
+            flex_reports_dir,
+            filename_template.unwrap_or(report_filename::DEFAULT_TEMPLATE),
+        )?,
     };
+    log::info!("Using {}", report_path);
 
-    println!("Using {}", report_path);
-
-    std::fs::read_to_string(report_path).expect("xml file read")
+    Ok(report_path)
 }
 
 /**
  * Gets the path to the latest report file in the given directory or the 
  * current directory, if None received.
+ * The report files are the ones matching the filename template. The latest is
+ * the one with the latest `{date}`, or `{to_date}`, in the filename. If the
+ * template has no dates, the last modified file is used.
  */
-pub fn get_latest_report_path(report_dir: Option<String>) -> String {
+pub fn get_latest_report_path(report_dir: Option<String>, filename_template: &str) -> Result<String> {
     // Load the latest report file.
-    let mut pattern = format!("*{}", FILE_SUFFIX);
+    let report_dir = PathBuf::from(report_dir.unwrap_or_default());
+    let pattern = report_dir
+        .join(report_filename::to_glob(filename_template))
+        .display()
+        .to_string();
 
-    if let Some(dir_path) = report_dir {
-        pattern = dir_path + pattern.as_str();
-    }
-
-    get_latest_filename(&pattern)
+    get_latest_filename(&pattern, &report_dir, filename_template)
 }
 
-/// Get the latest of the filest matching the given pattern.
+/// Get the latest of the files matching the given pattern.
 /// Pattern example: *.xml
-fn get_latest_filename(file_pattern: &str) -> String {
+fn get_latest_filename(
+    file_pattern: &str,
+    report_dir: &Path,
+    filename_template: &str,
+) -> Result<String> {
     log::debug!("file pattern: {:?}", file_pattern);
 
-    let mut filenames: Vec<String> = glob::glob(file_pattern)
-        .expect("directory list")
-        .filter_map(|entry| {
-            let path_buf = entry.unwrap();
-            if path_buf.is_file() {
-                let val = path_buf.to_str().unwrap().to_string();
-                Some(val)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    if filenames.is_empty() {
-        panic!("Aborting. No XML files found for pattern {}.", file_pattern);
+    let entries = glob::glob(file_pattern)
+        .map_err(|e| Error::Config(format!("Invalid file pattern {file_pattern}: {e}")))?;
+
+    let mut filenames: Vec<PathBuf> = vec![];
+    for entry in entries {
+        let path_buf = entry.map_err(|e| {
+            let path = e.path().display().to_string();
+            Error::io(path, e.into())
+        })?;
+        if path_buf.is_file() {
+            filenames.push(path_buf);
+        }
     }
 
-    filenames.sort();
-
-    let result = filenames.last().unwrap().to_owned();
+    let dated = filename_template.contains("date}");
+    filenames
+        .into_iter()
+        .max_by_key(|path| {
+            let relative = path.strip_prefix(report_dir).unwrap_or(path);
+            let date = get_report_date(filename_template, &relative.display().to_string());
+            let modified = if dated { None } else { get_modified(path) };
+            (date, modified, path.to_owned())
+        })
+        .map(|path| path.display().to_string())
+        .ok_or_else(|| Error::ReportNotFound(file_pattern.to_owned()))
+}
 
-    result
+/// The download date, or the end of the report period, from the filename.
+fn get_report_date(filename_template: &str, filename: &str) -> Option<NaiveDate> {
+    let values = report_filename::parse(filename_template, filename)?;
+    [values.date, values.to_date]
+        .iter()
+        .find_map(|date| NaiveDate::parse_from_str(date, ISO_DATE_FORMAT).ok())
 }
 
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
 
 #[cfg(test)]
 mod tests {
-    use std::path::MAIN_SEPARATOR;
+    use std::path::{Path, MAIN_SEPARATOR};
 
     use crate::{flex_query::FlexQueryResponse, flex_reader::load_report, 
-        test_fixtures::*, compare::CompareParams};
+        test_fixtures::*, compare::CompareParams, report_filename::DEFAULT_TEMPLATE};
 
-    use super::get_latest_filename;
+    use super::{get_latest_filename, get_latest_report_path};
+    use crate::Error;
 
     #[test_log::test]
     fn test_dir_list() {
-        let actual = get_latest_filename("tests/*.xml");
+        let actual = get_latest_filename("tests/*.xml", Path::new("tests"), DEFAULT_TEMPLATE).unwrap();
 
         assert!(!actual.is_empty());
         //let path = canonicalize("tests/report_1.xml").unwrap();
@@ -94,10 +143,45 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    /// The directory is joined with the pattern from the filename template.
+    #[test]
+    fn test_latest_report_w_template() {
+        let actual = get_latest_report_path(Some("tests".into()), "report_{account_id}.xml").unwrap();
+
+        let mut expected = "tests".to_string();
+        expected.push(MAIN_SEPARATOR);
+        expected.push_str("report_1.xml");
+        assert_eq!(expected, actual);
+    }
+
+    /// The latest report is chosen by the date in the filename, not by the name.
+    #[test]
+    fn test_latest_report_by_date() {
+        let dir = std::env::temp_dir().join(format!("ibflex-latest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for filename in ["U1111111_2023-03-09.xml", "U2222222_2023-03-08.xml", "U3333333_latest.xml"] {
+            std::fs::write(dir.join(filename), "").unwrap();
+        }
+
+        let actual =
+            get_latest_report_path(Some(dir.display().to_string()), "{account_id}_{date}.xml")
+                .unwrap();
+
+        assert_eq!(dir.join("U1111111_2023-03-09.xml").display().to_string(), actual);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_report_found() {
+        let actual = get_latest_report_path(Some("tests".into()), "{date}_missing.xml");
+
+        assert!(matches!(actual, Err(Error::ReportNotFound(_))));
+    }
+
     #[rstest::rstest]
-    fn test_parse_file(cmp_params: CompareParams) -> anyhow::Result<()> {
-        let report = load_report(cmp_params.flex_report_path, cmp_params.flex_reports_dir);
-        let actual = FlexQueryResponse::from(report);
+    fn test_parse_file(cmp_params: CompareParams) -> crate::Result<()> {
+        let report = load_report(cmp_params.flex_report_path, cmp_params.flex_reports_dir, None)?;
+        let actual = FlexQueryResponse::try_from(report)?;
 
         let total_txs: usize = actual
             .flex_statements
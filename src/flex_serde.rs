@@ -0,0 +1,361 @@
+/*!
+ * Deserializers for the typed attributes of the Flex report.
+ *
+ * Depending on the Flex query settings, IB writes the dates as `yyyy-MM-dd`,
+ * `yyyyMMdd`, or `dd-MMM-yy`, and the times as `HH:mm:ss` or `HHmmss`,
+ * separated from the date with `;`, `,`, or a space.
+ * The empty values are the missing dates (None) and the zero amounts. The
+ * prices, rates and factors are None when empty or missing, not zero.
+ * The codes are parsed into the `flex_enums`, and the empty codes are None.
+ *
+ * The errors do not name the attribute; [`FlexQueryResponse::from_xml`](crate::flex_query::FlexQueryResponse::from_xml)
+ * adds the path of the attribute.
+ *
+ * The attributes and the elements that are not modelled are collected with
+ * `#[serde(flatten)]` into `extra` and, for the statement, `sections`, and for
+ * the other sections, `elements`. The unknown elements of the Trades section
+ * are `TradeItem::Unknown`.
+ */
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use serde::{
+    de::{EnumAccess, Error, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    flex_enums::{self, Code},
+    flex_query::{Lot, RawElement, Trade, TradeItem},
+};
+
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y%m%d", "%d-%b-%y"];
+const TIME_FORMATS: [&str; 3] = ["%H:%M:%S", "%H%M%S", "%H:%M"];
+
+/// Parses the Flex date. The date/time values give their date.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .or_else(|| parse_date_time(value).map(|date_time| date_time.date()))
+}
+
+/// Parses the Flex date/time. The dates without the time are at midnight.
+pub fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    let (date, time) = match value.split_once([';', ',', ' ']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (value, None),
+    };
+
+    let date = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())?;
+    let time = match time {
+        Some(time) => TIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveTime::parse_from_str(time, format).ok())?,
+        None => NaiveTime::MIN,
+    };
+
+    Some(date.and_time(time))
+}
+
+/// Deserializes the number. The empty value is zero.
+pub(crate) fn decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    Ok(optional_decimal(deserializer)?.unwrap_or_default())
+}
+
+/// Deserializes the number, or None if empty. Used for the prices, rates and
+/// factors, for which zero is not a neutral value.
+pub(crate) fn optional_decimal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Decimal>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .map(Some)
+        .map_err(|_| D::Error::custom(format!("Invalid number: {value:?}")))
+}
+
+/// Deserializes the date, or None if empty.
+pub(crate) fn date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+
+    parse_date(&value)
+        .map(Some)
+        .ok_or_else(|| D::Error::custom(format!("Invalid date: {value:?}")))
+}
+
+/// Deserializes the date/time, or None if empty.
+pub(crate) fn date_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDateTime>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+
+    parse_date_time(&value)
+        .map(Some)
+        .ok_or_else(|| D::Error::custom(format!("Invalid date/time: {value:?}")))
+}
+
+/// Deserializes the code into its enum, or None if empty.
+pub(crate) fn code<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = std::convert::Infallible>,
+{
+    let value = String::deserialize(deserializer)?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(value.parse().ok())
+}
+
+/// Deserializes the `;`-separated codes of the `notes` attribute.
+pub(crate) fn codes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Code>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Ok(flex_enums::parse_codes(&value))
+}
+
+/// An XML value: the attribute or the text, or the element with its
+/// attributes (`@name`), text (`$text`), and children, in order.
+enum Node {
+    Text(String),
+    Element(Vec<(String, Node)>),
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an XML attribute or element")
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Node, E> {
+        Ok(Node::Text(value.to_owned()))
+    }
+
+    fn visit_bool<E: Error>(self, value: bool) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Node, E> {
+        Ok(Node::Element(vec![]))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        // The repeated elements are separate entries of the parent, so there
+        // are no sequences in the reports.
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(Node::Element(vec![]))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut entries = vec![];
+        while let Some(entry) = map.next_entry::<String, Node>()? {
+            entries.push(entry);
+        }
+        Ok(Node::Element(entries))
+    }
+}
+
+/// The entries of the element that are not deserialized into the fields.
+fn remaining_entries<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(String, Node)>, D::Error> {
+    match deserializer.deserialize_map(NodeVisitor)? {
+        Node::Element(entries) => Ok(entries),
+        Node::Text(_) => Ok(vec![]),
+    }
+}
+
+fn to_raw_element(name: String, node: Node) -> RawElement {
+    let mut element = RawElement {
+        name,
+        ..Default::default()
+    };
+    match node {
+        Node::Text(text) => element.text = text,
+        Node::Element(entries) => {
+            for (key, value) in entries {
+                match (key.strip_prefix('@'), value) {
+                    (Some(attribute), Node::Text(value)) => {
+                        element.attributes.insert(attribute.to_owned(), value);
+                    }
+                    (None, Node::Text(text)) if key.starts_with('$') => element.text = text,
+                    (_, value) => element.children.push(to_raw_element(key, value)),
+                }
+            }
+        }
+    }
+    element
+}
+
+/// Collects the attributes that are not modelled, by the name without `@`.
+pub(crate) fn extra<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error> {
+    Ok(remaining_entries(deserializer)?
+        .into_iter()
+        .filter_map(|(key, value)| match (key.strip_prefix('@'), value) {
+            (Some(attribute), Node::Text(value)) => Some((attribute.to_owned(), value)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Collects the child elements that are not modelled, as raw elements.
+pub(crate) fn sections<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<RawElement>, D::Error> {
+    Ok(remaining_entries(deserializer)?
+        .into_iter()
+        .filter(|(key, _)| !key.starts_with(['@', '$']))
+        .map(|(key, value)| to_raw_element(key, value))
+        .collect())
+}
+
+/// Deserializes the element of the Trades section by its name.
+impl<'de> Deserialize<'de> for TradeItem {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_enum("TradeItem", &["Trade", "Lot"], TradeItemVisitor)
+    }
+}
+
+struct TradeItemVisitor;
+
+impl<'de> Visitor<'de> for TradeItemVisitor {
+    type Value = TradeItem;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an element of the Trades section")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<TradeItem, A::Error> {
+        let (name, variant) = data.variant::<String>()?;
+        match name.as_str() {
+            "Trade" => variant.newtype_variant::<Trade>().map(TradeItem::Trade),
+            "Lot" => variant.newtype_variant::<Lot>().map(TradeItem::Lot),
+            _ => {
+                let node = variant.newtype_variant::<Node>()?;
+                Ok(TradeItem::Unknown(to_raw_element(name, node)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use serde::Deserialize;
+
+    use super::{parse_date, parse_date_time};
+    use crate::flex_query::FlexQueryResponse;
+
+    #[derive(Debug, Deserialize)]
+    struct Record {
+        #[serde(rename = "@amount", default, deserialize_with = "super::decimal")]
+        amount: Decimal,
+        #[serde(rename = "@fxRateToBase", default, deserialize_with = "super::optional_decimal")]
+        fx_rate_to_base: Option<Decimal>,
+        #[serde(rename = "@reportDate", default, deserialize_with = "super::date")]
+        report_date: Option<NaiveDate>,
+    }
+
+    #[test]
+    fn test_date_formats() {
+        let expected = NaiveDate::from_ymd_opt(2022, 12, 15);
+
+        assert_eq!(expected, parse_date("2022-12-15"));
+        assert_eq!(expected, parse_date("20221215"));
+        assert_eq!(expected, parse_date("15-Dec-22"));
+        assert_eq!(expected, parse_date("2022-12-15;12:20:00"));
+        assert_eq!(None, parse_date("15.12.2022"));
+    }
+
+    #[test]
+    fn test_date_time_formats() {
+        let expected = NaiveDate::from_ymd_opt(2022, 12, 15).unwrap().and_hms_opt(12, 20, 0);
+
+        assert_eq!(expected, parse_date_time("2022-12-15;12:20:00"));
+        assert_eq!(expected, parse_date_time("20221215;122000"));
+        assert_eq!(expected, parse_date_time("2022-12-15 12:20:00"));
+        assert_eq!(expected, parse_date_time("20221215, 12:20"));
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2022, 12, 15).unwrap().and_hms_opt(0, 0, 0),
+            parse_date_time("2022-12-15")
+        );
+        assert_eq!(None, parse_date_time("2022-12-15;noon"));
+    }
+
+    #[test]
+    fn test_empty_values() {
+        let actual: Record =
+            quick_xml::de::from_str(r#"<Record amount="" fxRateToBase="" reportDate="" />"#).unwrap();
+
+        assert_eq!(Decimal::ZERO, actual.amount);
+        assert_eq!(None, actual.fx_rate_to_base);
+        assert_eq!(None, actual.report_date);
+    }
+
+    /// The missing rate is None, not zero.
+    #[test]
+    fn test_missing_values() {
+        let actual: Record = quick_xml::de::from_str(r#"<Record />"#).unwrap();
+
+        assert_eq!(Decimal::ZERO, actual.amount);
+        assert_eq!(None, actual.fx_rate_to_base);
+    }
+
+    /// The error gives the path of the invalid attribute.
+    #[test]
+    fn test_error_names_attribute() {
+        let content = r#"<FlexQueryResponse>
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567">
+<CashTransactions>
+<CashTransaction amount="1" reportDate="2022-12-15" />
+<CashTransaction amount="1" reportDate="15.12.2022" />
+</CashTransactions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+        let actual = FlexQueryResponse::from_xml(content).unwrap_err().to_string();
+
+        assert!(actual.contains("CashTransaction"), "{actual}");
+        assert!(actual.contains("@reportDate"), "{actual}");
+        assert!(actual.contains("15.12.2022"), "{actual}");
+    }
+}
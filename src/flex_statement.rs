@@ -4,6 +4,8 @@
  * https://www.interactivebrokers.com.au/en/software/etmug/employeetrack/flex%20web%20service%20version%203.htm
  */
 
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 
 /**
@@ -24,13 +26,267 @@ pub struct FlexStatementResponse {
     pub url: String,
 }
 
-pub fn parse_response_text(text: &str) -> Result<FlexStatementResponse, quick_xml::DeError> {
-    quick_xml::de::from_str(text)
+/**
+The response of the Flex Web Service. Either of the steps may return an error
+instead of the expected content.
+ */
+#[derive(Debug)]
+pub enum StatementResponse {
+    Success(FlexStatementResponse),
+    Error(FlexServiceError),
+}
+
+impl StatementResponse {
+    pub fn into_result(self) -> crate::Result<FlexStatementResponse> {
+        match self {
+            StatementResponse::Success(response) => Ok(response),
+            StatementResponse::Error(error) => Err(error.into()),
+        }
+    }
+}
+
+/// All the elements that may appear in the Flex Web Service response.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseXml {
+    #[serde(rename = "@timestamp", default)]
+    timestamp: String,
+
+    #[serde(default)]
+    status: String,
+    reference_code: Option<String>,
+    url: Option<String>,
+    error_code: Option<u32>,
+    error_message: Option<String>,
+}
+
+/// Parses the response to the statement request (1st step).
+pub fn parse_response_text(text: &str) -> crate::Result<StatementResponse> {
+    let response: ResponseXml = quick_xml::de::from_str(text)?;
+
+    if let Some(code) = response.error_code {
+        return Ok(StatementResponse::Error(FlexServiceError {
+            code: code.into(),
+            message: response.error_message.unwrap_or_default(),
+        }));
+    }
+
+    match (response.reference_code, response.url) {
+        (Some(reference_code), Some(url)) => {
+            Ok(StatementResponse::Success(FlexStatementResponse {
+                timestamp: response.timestamp,
+                status: response.status,
+                reference_code,
+                url,
+            }))
+        }
+        _ => Err(crate::Error::Xml(serde::de::Error::custom(
+            "missing ReferenceCode or Url in the statement response",
+        ))),
+    }
+}
+
+/// The state of the statement download.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StatementStatus {
+    /// The response contains the Flex Query report.
+    Ready,
+    /// The statement is not ready yet. Retry later.
+    InProgress(FlexServiceError),
+    /// The statement can not be retrieved.
+    Failed(FlexServiceError),
+}
+
+/// Checks the response of the statement download (2nd step).
+/// The error codes of the request (1st step) are the same.
+pub fn get_statement_status(text: &str) -> crate::Result<StatementStatus> {
+    if text.contains("<FlexQueryResponse") {
+        return Ok(StatementStatus::Ready);
+    }
+
+    let status = match parse_response_text(text)? {
+        StatementResponse::Error(error) if error.code.is_in_progress() => {
+            StatementStatus::InProgress(error)
+        }
+        StatementResponse::Error(error) => StatementStatus::Failed(error),
+        StatementResponse::Success(_) => {
+            return Err(crate::Error::Xml(serde::de::Error::custom(
+                "expected the statement but received a reference code",
+            )))
+        }
+    };
+    Ok(status)
+}
+
+/**
+The error reported by the Flex Web Service.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlexServiceError {
+    pub code: FlexErrorCode,
+    pub message: String,
+}
+
+impl Display for FlexServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IB Flex Web Service error {}: {}", self.code.code(), self.message)?;
+        if let Some(hint) = self.code.hint() {
+            write!(f, " {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FlexServiceError {}
+
+/**
+The error codes documented for the Flex Web Service.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlexErrorCode {
+    /// 1001: Statement could not be generated at this time.
+    CannotGenerate,
+    /// 1003: Statement is not available.
+    NotAvailable,
+    /// 1004: Statement is incomplete at this time.
+    Incomplete,
+    /// 1005: Settlement data is not ready at this time.
+    SettlementNotReady,
+    /// 1006: FIFO P/L data is not ready at this time.
+    FifoNotReady,
+    /// 1007: MTM P/L data is not ready at this time.
+    MtmNotReady,
+    /// 1008: MTM and FIFO P/L data is not ready at this time.
+    MtmAndFifoNotReady,
+    /// 1009: The server is under heavy load.
+    ServerBusy,
+    /// 1010: Legacy Flex Queries are no longer supported.
+    LegacyQuery,
+    /// 1011: Service account is inactive.
+    AccountInactive,
+    /// 1012: Token has expired.
+    TokenExpired,
+    /// 1013: IP restriction.
+    IpRestriction,
+    /// 1014: Query is invalid.
+    InvalidQuery,
+    /// 1015: Token is invalid.
+    InvalidToken,
+    /// 1016: Account is invalid.
+    InvalidAccount,
+    /// 1017: Reference code is invalid.
+    InvalidReferenceCode,
+    /// 1018: Too many requests have been made from this token.
+    TooManyRequests,
+    /// 1019: Statement generation in progress.
+    GenerationInProgress,
+    /// 1020: Invalid request or unable to validate request.
+    InvalidRequest,
+    /// 1021: Statement could not be retrieved at this time.
+    CannotRetrieve,
+    /// Any code not documented at the time of writing.
+    Other(u32),
+}
+
+impl From<u32> for FlexErrorCode {
+    fn from(value: u32) -> Self {
+        match value {
+            1001 => FlexErrorCode::CannotGenerate,
+            1003 => FlexErrorCode::NotAvailable,
+            1004 => FlexErrorCode::Incomplete,
+            1005 => FlexErrorCode::SettlementNotReady,
+            1006 => FlexErrorCode::FifoNotReady,
+            1007 => FlexErrorCode::MtmNotReady,
+            1008 => FlexErrorCode::MtmAndFifoNotReady,
+            1009 => FlexErrorCode::ServerBusy,
+            1010 => FlexErrorCode::LegacyQuery,
+            1011 => FlexErrorCode::AccountInactive,
+            1012 => FlexErrorCode::TokenExpired,
+            1013 => FlexErrorCode::IpRestriction,
+            1014 => FlexErrorCode::InvalidQuery,
+            1015 => FlexErrorCode::InvalidToken,
+            1016 => FlexErrorCode::InvalidAccount,
+            1017 => FlexErrorCode::InvalidReferenceCode,
+            1018 => FlexErrorCode::TooManyRequests,
+            1019 => FlexErrorCode::GenerationInProgress,
+            1020 => FlexErrorCode::InvalidRequest,
+            1021 => FlexErrorCode::CannotRetrieve,
+            other => FlexErrorCode::Other(other),
+        }
+    }
+}
+
+impl FlexErrorCode {
+    /// The numeric code, as used by IB.
+    pub fn code(&self) -> u32 {
+        match self {
+            FlexErrorCode::CannotGenerate => 1001,
+            FlexErrorCode::NotAvailable => 1003,
+            FlexErrorCode::Incomplete => 1004,
+            FlexErrorCode::SettlementNotReady => 1005,
+            FlexErrorCode::FifoNotReady => 1006,
+            FlexErrorCode::MtmNotReady => 1007,
+            FlexErrorCode::MtmAndFifoNotReady => 1008,
+            FlexErrorCode::ServerBusy => 1009,
+            FlexErrorCode::LegacyQuery => 1010,
+            FlexErrorCode::AccountInactive => 1011,
+            FlexErrorCode::TokenExpired => 1012,
+            FlexErrorCode::IpRestriction => 1013,
+            FlexErrorCode::InvalidQuery => 1014,
+            FlexErrorCode::InvalidToken => 1015,
+            FlexErrorCode::InvalidAccount => 1016,
+            FlexErrorCode::InvalidReferenceCode => 1017,
+            FlexErrorCode::TooManyRequests => 1018,
+            FlexErrorCode::GenerationInProgress => 1019,
+            FlexErrorCode::InvalidRequest => 1020,
+            FlexErrorCode::CannotRetrieve => 1021,
+            FlexErrorCode::Other(code) => *code,
+        }
+    }
+
+    /// The statement is not ready yet and the request should be retried.
+    pub fn is_in_progress(&self) -> bool {
+        matches!(
+            self,
+            FlexErrorCode::CannotGenerate
+                | FlexErrorCode::Incomplete
+                | FlexErrorCode::SettlementNotReady
+                | FlexErrorCode::FifoNotReady
+                | FlexErrorCode::MtmNotReady
+                | FlexErrorCode::MtmAndFifoNotReady
+                | FlexErrorCode::ServerBusy
+                | FlexErrorCode::TooManyRequests
+                | FlexErrorCode::GenerationInProgress
+                | FlexErrorCode::CannotRetrieve
+        )
+    }
+
+    /// What the user can do about the error.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            FlexErrorCode::TokenExpired | FlexErrorCode::InvalidToken => Some(
+                "Generate a new token in Reports / Settings / Flex Web Service and pass it with --token.",
+            ),
+            FlexErrorCode::IpRestriction => {
+                Some("Check the IP restriction in Reports / Settings / Flex Web Service.")
+            }
+            FlexErrorCode::AccountInactive => {
+                Some("Enable the Flex Web Service in Reports / Settings.")
+            }
+            FlexErrorCode::InvalidQuery | FlexErrorCode::NotAvailable => {
+                Some("Check the query id in Reports / Flex Queries.")
+            }
+            FlexErrorCode::LegacyQuery => Some("Recreate the query as an Activity Flex Query."),
+            FlexErrorCode::InvalidRequest => Some("Check the token and the query id."),
+            code if code.is_in_progress() => Some("Try again shortly."),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::flex_statement;
+    use crate::flex_statement::{self, FlexErrorCode, StatementResponse, StatementStatus};
 
     /// Test parsing the XML response.
     /// The tags are PascalCase, attributes are camelCase, while the Rust struct members are
@@ -43,7 +299,10 @@ mod tests {
 <Url>https://gdcdyn.interactivebrokers.com/Universal/servlet/FlexStatementService.GetStatement</Url>
 </FlexStatementResponse>
 ";
-        let actual = flex_statement::parse_response_text(reqresp).expect("parsed statement");
+        let actual = flex_statement::parse_response_text(reqresp)
+            .expect("parsed statement")
+            .into_result()
+            .expect("successful response");
 
         println!("parsed: {:?}", actual);
 
@@ -53,4 +312,72 @@ mod tests {
         assert_eq!("https://gdcdyn.interactivebrokers.com/Universal/servlet/FlexStatementService.GetStatement",
             actual.url);
     }
+
+    /// The failed request contains the error code and the message.
+    #[test]
+    fn test_parsing_error_response() {
+        let reqresp = r"<FlexStatementResponse timestamp='17 January, 2023 12:51 PM EST'>
+<Status>Fail</Status>
+<ErrorCode>1015</ErrorCode>
+<ErrorMessage>Token is invalid.</ErrorMessage>
+</FlexStatementResponse>
+";
+        let actual = flex_statement::parse_response_text(reqresp).expect("parsed statement");
+
+        let StatementResponse::Error(error) = actual else {
+            panic!("expected an error, got {:?}", actual)
+        };
+        assert_eq!(FlexErrorCode::InvalidToken, error.code);
+        assert_eq!("Token is invalid.", error.message);
+        assert!(error.to_string().contains("1015"));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(FlexErrorCode::TokenExpired, FlexErrorCode::from(1012));
+        assert_eq!(1020, FlexErrorCode::InvalidRequest.code());
+        assert_eq!(FlexErrorCode::Other(1099), FlexErrorCode::from(1099));
+        assert!(FlexErrorCode::from(1019).is_in_progress());
+        assert!(!FlexErrorCode::from(1003).is_in_progress());
+    }
+
+    /// The statement is still being generated.
+    #[test]
+    fn test_statement_in_progress() {
+        let response = r"<FlexStatementResponse timestamp='17 January, 2023 12:51 PM EST'>
+<Status>Warn</Status>
+<ErrorCode>1019</ErrorCode>
+<ErrorMessage>Statement generation in progress. Please try again shortly.</ErrorMessage>
+</FlexStatementResponse>
+";
+        let actual = flex_statement::get_statement_status(response).unwrap();
+
+        let StatementStatus::InProgress(error) = actual else {
+            panic!("expected in-progress status, got {:?}", actual)
+        };
+        assert_eq!(FlexErrorCode::GenerationInProgress, error.code);
+        assert_eq!("Statement generation in progress. Please try again shortly.", error.message);
+    }
+
+    #[test]
+    fn test_statement_ready() {
+        let response = r#"<FlexQueryResponse queryName="cash tx" type="AF">
+<FlexStatements count="0" />
+</FlexQueryResponse>"#;
+
+        assert_eq!(StatementStatus::Ready, flex_statement::get_statement_status(response).unwrap());
+    }
+
+    #[test]
+    fn test_statement_failed() {
+        let response = r"<FlexStatementResponse timestamp='17 January, 2023 12:51 PM EST'>
+<Status>Fail</Status>
+<ErrorCode>1012</ErrorCode>
+<ErrorMessage>Token has expired.</ErrorMessage>
+</FlexStatementResponse>
+";
+        let actual = flex_statement::get_statement_status(response).unwrap();
+
+        assert!(matches!(actual, StatementStatus::Failed(error) if error.code == FlexErrorCode::TokenExpired));
+    }
 }
@@ -0,0 +1,231 @@
+/*!
+ * Runs hledger to retrieve the journal postings.
+ * Uses the JSON output of `hledger print`, which contains all the postings
+ * with the amounts, so no column positions are involved.
+ */
+
+use std::process::Command;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    account_types::AccountRules,
+    journal_backend::{JournalBackend, JournalQuery},
+    model::CommonTransaction,
+    Error, Result, ISO_DATE_FORMAT,
+};
+
+/// Runs hledger, which must be on the path.
+#[derive(Debug, Default)]
+pub struct HledgerBackend;
+
+impl JournalBackend for HledgerBackend {
+    fn get_transactions(&self, query: &JournalQuery) -> Result<Vec<CommonTransaction>> {
+        let args = get_hledger_args(query);
+        log::debug!("running: hledger {:?}", args);
+
+        let output = Command::new("hledger")
+            .args(&args)
+            .output()
+            .map_err(|e| Error::Ledger(format!("Could not run hledger: {e}")))?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Ledger(format!("Error running hledger: {}", err.trim())));
+        }
+        let out = String::from_utf8(output.stdout)
+            .map_err(|e| Error::Ledger(format!("Invalid output: {e}")))?;
+
+        parse_print_json(&out, query.effective_dates, query.account_rules)
+    }
+}
+
+/// The transaction in the `hledger print -O json` output.
+#[derive(Debug, Deserialize)]
+struct HledgerTransaction {
+    tdate: String,
+    tdate2: Option<String>,
+    tdescription: String,
+    tpostings: Vec<HledgerPosting>,
+    /// The metadata, as name-value pairs.
+    #[serde(default)]
+    ttags: Vec<(String, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HledgerPosting {
+    paccount: String,
+    pamount: Vec<HledgerAmount>,
+    /// The posting dates override the transaction dates.
+    pdate: Option<String>,
+    pdate2: Option<String>,
+    #[serde(default)]
+    ptags: Vec<(String, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HledgerAmount {
+    acommodity: String,
+    aquantity: HledgerQuantity,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HledgerQuantity {
+    decimal_mantissa: i128,
+    decimal_places: u32,
+}
+
+/// Assembles the arguments for `hledger print`.
+fn get_hledger_args(query: &JournalQuery) -> Vec<String> {
+    let mut args: Vec<String> = ["print", "-O", "json", "--explicit", "-b", &query.start_date]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+
+    if query.effective_dates {
+        // The secondary dates are the effective dates.
+        args.push("--date2".into());
+    }
+
+    if let Some(journal_file) = &query.journal_file {
+        args.push("-f".into());
+        args.push(journal_file.to_owned());
+    }
+
+    args
+}
+
+/**
+ * Parses the JSON output of `hledger print`.
+ * Returns one record per posting amount, for the accounts matching the rules.
+ */
+fn parse_print_json(
+    json: &str,
+    effective_dates: bool,
+    account_rules: &AccountRules,
+) -> Result<Vec<CommonTransaction>> {
+    let hledger_txs: Vec<HledgerTransaction> = serde_json::from_str(json)
+        .map_err(|e| Error::Ledger(format!("Invalid hledger output: {e}")))?;
+
+    let mut txs = vec![];
+    for hledger_tx in hledger_txs {
+        // The symbol is the first word of the description.
+        let symbol = hledger_tx
+            .tdescription
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+
+        for posting in &hledger_tx.tpostings {
+            let Some(tx_type) = account_rules.get_type(&posting.paccount) else {
+                continue;
+            };
+
+            let date_str = if effective_dates {
+                posting
+                    .pdate2
+                    .as_ref()
+                    .or(hledger_tx.tdate2.as_ref())
+                    .or(posting.pdate.as_ref())
+                    .unwrap_or(&hledger_tx.tdate)
+            } else {
+                posting.pdate.as_ref().unwrap_or(&hledger_tx.tdate)
+            };
+            let date = NaiveDate::parse_from_str(date_str, ISO_DATE_FORMAT)
+                .map_err(|_| Error::InvalidDate(date_str.to_owned()))?
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+
+            for amount in &posting.pamount {
+                let quantity = &amount.aquantity;
+                let value =
+                    Decimal::try_from_i128_with_scale(quantity.decimal_mantissa, quantity.decimal_places)
+                        .map_err(|_| Error::InvalidAmount(format!("{:?}", quantity)))?;
+
+                let mut tx = CommonTransaction {
+                    date,
+                    report_date: date.format(ISO_DATE_FORMAT).to_string(),
+                    payee: hledger_tx.tdescription.to_owned(),
+                    account: posting.paccount.to_owned(),
+                    amount: value,
+                    currency: amount.acommodity.to_owned(),
+                    symbol: symbol.to_owned(),
+                    r#type: tx_type.to_owned(),
+                    ..Default::default()
+                };
+                // The posting tags override the transaction tags.
+                for (name, value) in hledger_tx.ttags.iter().chain(&posting.ptags) {
+                    tx.set_metadata(name, value);
+                }
+                txs.push(tx);
+            }
+        }
+    }
+
+    Ok(txs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rust_decimal::Decimal;
+
+    use super::parse_print_json;
+    use crate::{account_types::AccountRules, flex_enums::CashAction};
+
+    /// Hand-written in the format of `hledger print -O json`, with the fields
+    /// that are read and the postings of `tests/journal.ledger`. hledger is not
+    /// needed to run the tests.
+    const PRINT_JSON: &str = r#"[
+  {
+    "tcode": "", "tcomment": "", "tdate": "2022-12-01", "tdate2": null,
+    "tdescription": "Supermarket", "tindex": 1, "tstatus": "Unmarked", "ttags": [],
+    "tpostings": [
+      { "paccount": "Expenses:Food", "pdate": null, "pdate2": null, "pcomment": "", "ptags": [],
+        "pamount": [ { "acommodity": "EUR", "aprice": null,
+          "aquantity": { "decimalMantissa": 1500, "decimalPlaces": 2, "floatingPoint": 15 } } ] }
+    ]
+  },
+  {
+    "tcode": "", "tcomment": "", "tdate": "2022-12-15", "tdate2": "2022-12-16",
+    "tdescription": "TRET_AS Distribution", "tindex": 2, "tstatus": "Unmarked", "ttags": [["isin", "NL0009690239"]],
+    "tpostings": [
+      { "paccount": "Income:Investment:IB:TRET_AS", "pdate": null, "pdate2": null, "pcomment": "", "ptags": [],
+        "pamount": [ { "acommodity": "EUR", "aprice": null,
+          "aquantity": { "decimalMantissa": -3840, "decimalPlaces": 2, "floatingPoint": -38.4 } } ] },
+      { "paccount": "Expenses:Investment:IB:Withholding Tax", "pdate": null, "pdate2": null, "pcomment": "ib_tx: 1234\n", "ptags": [["ib_tx", "1234"], ["isin", "NL0009690239"]],
+        "pamount": [ { "acommodity": "EUR", "aprice": null,
+          "aquantity": { "decimalMantissa": 577, "decimalPlaces": 2, "floatingPoint": 5.77 } } ] },
+      { "paccount": "Assets:Investment:IB:Cash", "pdate": null, "pdate2": null, "pcomment": "", "ptags": [],
+        "pamount": [ { "acommodity": "EUR", "aprice": null,
+          "aquantity": { "decimalMantissa": 3263, "decimalPlaces": 2, "floatingPoint": 32.63 } } ] }
+    ]
+  }
+]"#;
+
+    #[test]
+    fn test_parse_print_json() {
+        let actual = parse_print_json(PRINT_JSON, false, &AccountRules::default()).unwrap();
+
+        assert_eq!(2, actual.len());
+        assert_eq!("TRET_AS", actual[0].symbol);
+        assert_eq!(CashAction::Dividend, actual[0].r#type);
+        assert_eq!("2022-12-15", actual[0].report_date);
+        assert_eq!(Decimal::from_str("-38.40").unwrap(), actual[0].amount);
+        assert_eq!(CashAction::WhTax, actual[1].r#type);
+        assert_eq!("EUR", actual[1].currency);
+        assert_eq!("NL0009690239", actual[0].isin);
+        assert_eq!("1234", actual[1].transaction_id);
+    }
+
+    #[test]
+    fn test_parse_effective_dates() {
+        let actual = parse_print_json(PRINT_JSON, true, &AccountRules::default()).unwrap();
+
+        assert_eq!("2022-12-16", actual[0].report_date);
+    }
+}
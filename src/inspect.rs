@@ -0,0 +1,292 @@
+/*!
+ * Lists the parts of the Flex report that are not modelled, so that a Flex
+ * query with additional columns or sections does not lose the data unnoticed.
+ * The values themselves are kept in the `extra` attributes of the records and
+ * in the `sections` of the statement.
+ */
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use crate::{
+    flex_query::{FlexQueryResponse, RawElement, TradeItem},
+    Error, Result,
+};
+
+/// The attributes, the statement sections, and the elements that are not modelled.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Unmodelled {
+    /// The attribute names, by the element name.
+    pub attributes: BTreeMap<String, BTreeSet<String>>,
+    /// The names of the statement sections.
+    pub sections: BTreeSet<String>,
+    /// The elements inside the modelled sections, as `Section/Element`.
+    pub elements: BTreeSet<String>,
+}
+
+impl Unmodelled {
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.sections.is_empty() && self.elements.is_empty()
+    }
+
+    fn add_elements(&mut self, section: &str, elements: &[RawElement]) {
+        self.elements
+            .extend(elements.iter().map(|element| format!("{section}/{}", element.name)));
+    }
+
+    fn add<'a>(&mut self, element: &str, records: impl IntoIterator<Item = &'a BTreeMap<String, String>>) {
+        for extra in records.into_iter().filter(|extra| !extra.is_empty()) {
+            self.attributes
+                .entry(element.to_owned())
+                .or_default()
+                .extend(extra.keys().cloned());
+        }
+    }
+}
+
+impl Display for Unmodelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "All the attributes and sections are modelled.");
+        }
+
+        for (element, attributes) in &self.attributes {
+            let names: Vec<&str> = attributes.iter().map(String::as_str).collect();
+            writeln!(f, "Attributes of {element}: {}", names.join(", "))?;
+        }
+        for section in &self.sections {
+            writeln!(f, "Section: {section}")?;
+        }
+        for element in &self.elements {
+            writeln!(f, "Element: {element}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the report file and lists what is not modelled.
+pub fn inspect_report(report_path: &str) -> Result<Unmodelled> {
+    let content = std::fs::read_to_string(report_path).map_err(|e| Error::io(report_path, e))?;
+    let response = FlexQueryResponse::from_xml(&content)?;
+
+    Ok(find_unmodelled(&response))
+}
+
+/// Lists the attributes and the statement sections that are not modelled.
+pub fn find_unmodelled(response: &FlexQueryResponse) -> Unmodelled {
+    let mut result = Unmodelled::default();
+
+    result.add("FlexQueryResponse", [&response.extra]);
+    result.add("FlexStatements", [&response.flex_statements.extra]);
+
+    for stmt in &response.flex_statements.flex_statement {
+        result.add("FlexStatement", [&stmt.extra]);
+        result.sections.extend(stmt.sections.iter().map(|section| section.name.to_owned()));
+
+        for item in &stmt.trades.items {
+            match item {
+                TradeItem::Trade(trade) => result.add("Trade", [&trade.extra]),
+                TradeItem::Lot(lot) => result.add("Lot", [&lot.extra]),
+                TradeItem::Unknown(element) => {
+                    result.add_elements("Trades", std::slice::from_ref(element))
+                }
+            }
+        }
+        result.add_elements("CashTransactions", &stmt.cash_transactions.elements);
+        result.add(
+            "CashTransaction",
+            stmt.cash_transactions.cash_transaction.iter().map(|r| &r.extra),
+        );
+        result.add_elements("CashReport", &stmt.cash_report.elements);
+        result.add(
+            "CashReportCurrency",
+            stmt.cash_report.cash_report_currency.iter().map(|r| &r.extra),
+        );
+        result.add_elements("OpenPositions", &stmt.open_positions.elements);
+        result.add("OpenPosition", stmt.open_positions.open_position.iter().map(|r| &r.extra));
+        result.add_elements("CorporateActions", &stmt.corporate_actions.elements);
+        result.add(
+            "CorporateAction",
+            stmt.corporate_actions.corporate_action.iter().map(|r| &r.extra),
+        );
+        result.add("AccountInformation", [&stmt.account_information.extra]);
+        result.add_elements("EquitySummaryInBase", &stmt.equity_summary_in_base.elements);
+        result.add(
+            "EquitySummaryByReportDateInBase",
+            stmt.equity_summary_in_base
+                .equity_summary_by_report_date_in_base
+                .iter()
+                .map(|r| &r.extra),
+        );
+        result.add("ChangeInNAV", [&stmt.change_in_nav.extra]);
+        result.add_elements("ConversionRates", &stmt.conversion_rates.elements);
+        result.add("ConversionRate", stmt.conversion_rates.conversion_rate.iter().map(|r| &r.extra));
+        result.add_elements("Transfers", &stmt.transfers.elements);
+        result.add("Transfer", stmt.transfers.transfer.iter().map(|r| &r.extra));
+        result.add_elements("InterestAccruals", &stmt.interest_accruals.elements);
+        result.add(
+            "InterestAccrualsCurrency",
+            stmt.interest_accruals.interest_accruals_currency.iter().map(|r| &r.extra),
+        );
+        result.add_elements("ChangeInDividendAccruals", &stmt.change_in_dividend_accruals.elements);
+        result.add(
+            "ChangeInDividendAccrual",
+            stmt.change_in_dividend_accruals
+                .change_in_dividend_accrual
+                .iter()
+                .map(|r| &r.extra),
+        );
+        result.add_elements("OpenDividendAccruals", &stmt.open_dividend_accruals.elements);
+        result.add(
+            "OpenDividendAccrual",
+            stmt.open_dividend_accruals.open_dividend_accrual.iter().map(|r| &r.extra),
+        );
+        result.add_elements("SecuritiesInfo", &stmt.securities_info.elements);
+        result.add("SecurityInfo", stmt.securities_info.security_info.iter().map(|r| &r.extra));
+        result.add_elements("OptionEAE", &stmt.option_eae.elements);
+        result.add("OptionEAE", stmt.option_eae.option_eae.iter().map(|r| &r.extra));
+        result.add_elements("FxTransactions", &stmt.fx_transactions.elements);
+        result.add("FxTransaction", stmt.fx_transactions.fx_transaction.iter().map(|r| &r.extra));
+        result.add_elements("TransactionTaxes", &stmt.transaction_taxes.elements);
+        result.add(
+            "TransactionTax",
+            stmt.transaction_taxes.transaction_tax.iter().map(|r| &r.extra),
+        );
+        result.add_elements("UnbundledCommissionDetails", &stmt.unbundled_commission_details.elements);
+        result.add(
+            "UnbundledCommissionDetail",
+            stmt.unbundled_commission_details
+                .unbundled_commission_detail
+                .iter()
+                .map(|r| &r.extra),
+        );
+        result.add_elements("SLBActivities", &stmt.slb_activities.elements);
+        result.add("SLBActivity", stmt.slb_activities.slb_activity.iter().map(|r| &r.extra));
+        result.add_elements("StmtFunds", &stmt.stmt_funds.elements);
+        result.add(
+            "StatementOfFundsLine",
+            stmt.stmt_funds.statement_of_funds_line.iter().map(|r| &r.extra),
+        );
+        result.add_elements("MTMPerformanceSummaryInBase", &stmt.mtm_performance_summary_in_base.elements);
+        result.add(
+            "MTMPerformanceSummaryUnderlying",
+            stmt.mtm_performance_summary_in_base
+                .mtm_performance_summary_underlying
+                .iter()
+                .map(|r| &r.extra),
+        );
+        result.add_elements("FIFOPerformanceSummaryInBase", &stmt.fifo_performance_summary_in_base.elements);
+        result.add(
+            "FIFOPerformanceSummaryUnderlying",
+            stmt.fifo_performance_summary_in_base
+                .fifo_performance_summary_underlying
+                .iter()
+                .map(|r| &r.extra),
+        );
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{find_unmodelled, inspect_report};
+    use crate::flex_query::FlexQueryResponse;
+
+    #[test]
+    fn test_find_unmodelled() {
+        let content = r#"
+<FlexQueryResponse queryName="extra" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="20230301" toDate="20230331" period="LastMonth" whenGenerated="20230401;083015">
+<Trades>
+<Order symbol="AIR" quantity="4" />
+<Trade symbol="AIR" quantity="4" />
+<SymbolSummary symbol="AIR" quantity="4" />
+</Trades>
+<CashTransactions>
+<CashTransaction reportDate="20230315" type="Dividends" amount="38.4" fxRateToBase="1" levelOfDetail="DETAIL" />
+<CashTransaction reportDate="20230315" type="Withholding Tax" amount="-5.77" clientReference="" />
+<CashTransactionSummary amount="32.63" />
+</CashTransactions>
+<NetStockPositionSummary>
+<NetStockPosition symbol="AIR" netShares="4" />
+</NetStockPositionSummary>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>
+"#;
+        let response = FlexQueryResponse::from_xml(content).unwrap();
+
+        let actual = find_unmodelled(&response);
+
+        assert_eq!(
+            vec!["clientReference", "fxRateToBase", "levelOfDetail"],
+            actual.attributes["CashTransaction"].iter().collect::<Vec<_>>()
+        );
+        assert_eq!(1, actual.attributes.len());
+        assert!(actual.sections.contains("NetStockPositionSummary"));
+        assert_eq!(
+            vec!["CashTransactions/CashTransactionSummary", "Trades/Order", "Trades/SymbolSummary"],
+            actual.elements.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(2, response.flex_statements.flex_statement[0].cash_transactions.cash_transaction.len());
+        assert_eq!(
+            "Attributes of CashTransaction: clientReference, fxRateToBase, levelOfDetail\nSection: NetStockPositionSummary\n\
+             Element: CashTransactions/CashTransactionSummary\nElement: Trades/Order\nElement: Trades/SymbolSummary\n",
+            actual.to_string()
+        );
+    }
+
+    /// Each record of `tests/sections.xml`, which has all the modelled sections, gets
+    /// an unmodelled attribute, and each section an unmodelled child. All are listed,
+    /// so the sections that are missing from `find_unmodelled` fail the test.
+    #[test]
+    fn test_find_unmodelled_complete() {
+        let content = std::fs::read_to_string("tests/sections.xml").unwrap();
+        let mut xml = String::default();
+        let mut elements = BTreeSet::new();
+        let mut sections = BTreeSet::new();
+        for (i, tag) in content.split('<').enumerate() {
+            if i > 0 {
+                xml.push('<');
+            }
+            let name_end = tag.find([' ', '/', '>']).unwrap_or(tag.len());
+            let name = &tag[..name_end];
+            if i == 0 || name.is_empty() || tag.starts_with(['/', '?']) {
+                xml.push_str(tag);
+                continue;
+            }
+            xml.push_str(name);
+            let is_section = !tag.trim_end().ends_with("/>") && !name.starts_with("Flex");
+            if !is_section {
+                elements.insert(name.to_owned());
+                xml.push_str(r#" unmodelled="1""#);
+            }
+            xml.push_str(&tag[name_end..]);
+            if is_section {
+                sections.insert(name.to_owned());
+                xml.push_str("<Unmodelled />\n");
+            }
+        }
+        let response = FlexQueryResponse::from_xml(&xml).unwrap();
+
+        let actual = find_unmodelled(&response);
+
+        assert_eq!(elements, actual.attributes.keys().cloned().collect());
+        let expected: BTreeSet<String> = sections.iter().map(|name| format!("{name}/Unmodelled")).collect();
+        assert_eq!(expected, actual.elements);
+        assert!(actual.sections.is_empty(), "{actual}");
+    }
+
+    #[test]
+    fn test_all_modelled() {
+        let actual = inspect_report("tests/sections.xml").unwrap();
+
+        assert!(actual.is_empty(), "{actual}");
+    }
+}
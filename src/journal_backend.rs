@@ -0,0 +1,113 @@
+/*!
+ * The journal backends, which read the postings to compare from the books.
+ *
+ * Each backend produces the same list of [`CommonTransaction`]s, one per
+ * posting, with the type given by the account rules.
+ */
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    account_types::AccountRules, beancount_reader::BeancountBackend, flex_enums::CashAction,
+    hledger_runner::HledgerBackend, ledger_reader::LedgerReaderBackend, ledger_runner, model::CommonTransaction, Error, Result,
+};
+
+/// The postings to fetch from the journal.
+/// The backends classify the postings with all the account rules and may return
+/// the postings of other types, which are dropped by the comparison.
+#[derive(Debug, Clone)]
+pub struct JournalQuery<'a> {
+    /// The journal file. The tool's default journal is used if not set.
+    pub journal_file: Option<String>,
+    /// The first date, in ISO format.
+    pub start_date: String,
+    pub effective_dates: bool,
+    /// Give the posting types. The first matching rule wins.
+    pub account_rules: &'a AccountRules,
+    /// The posting types to compare. Only used to narrow the Ledger query.
+    pub types: &'a [CashAction],
+}
+
+/// Reads the postings from the journal.
+pub trait JournalBackend {
+    fn get_transactions(&self, query: &JournalQuery) -> Result<Vec<CommonTransaction>>;
+}
+
+/// Runs Ledger-cli. See [`ledger_runner`].
+#[derive(Debug, Default)]
+pub struct LedgerBackend;
+
+impl JournalBackend for LedgerBackend {
+    fn get_transactions(&self, query: &JournalQuery) -> Result<Vec<CommonTransaction>> {
+        ledger_runner::get_ledger_tx(
+            query.journal_file.to_owned(),
+            query.start_date.to_owned(),
+            query.effective_dates,
+            query.account_rules,
+            query.types,
+        )
+    }
+}
+
+/// The available journal backends.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Ledger,
+    /// Reads the Ledger journal without running Ledger. See [`ledger_reader`](crate::ledger_reader).
+    LedgerNative,
+    Hledger,
+    Beancount,
+}
+
+impl Backend {
+    pub fn create(&self) -> Box<dyn JournalBackend> {
+        match self {
+            Backend::Ledger => Box::new(LedgerBackend),
+            Backend::LedgerNative => Box::new(LedgerReaderBackend),
+            Backend::Hledger => Box::new(HledgerBackend),
+            Backend::Beancount => Box::new(BeancountBackend),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ledger" => Ok(Backend::Ledger),
+            "ledger-native" => Ok(Backend::LedgerNative),
+            "hledger" => Ok(Backend::Hledger),
+            "beancount" => Ok(Backend::Beancount),
+            _ => Err(Error::Config(format!(
+                "Unknown journal backend {s:?}. Use ledger, ledger-native, hledger, or beancount."
+            ))),
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Backend::Ledger => "ledger",
+            Backend::LedgerNative => "ledger-native",
+            Backend::Hledger => "hledger",
+            Backend::Beancount => "beancount",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Backend;
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(Backend::Hledger, "hledger".parse().unwrap());
+        assert_eq!(Backend::LedgerNative, "Ledger-Native".parse().unwrap());
+        assert_eq!("ledger", Backend::default().to_string());
+        assert!("gnucash".parse::<Backend>().is_err());
+    }
+}
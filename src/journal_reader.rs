@@ -0,0 +1,140 @@
+/*!
+ * The parts shared by the native journal readers, [`ledger_reader`](crate::ledger_reader)
+ * and [`beancount_reader`](crate::beancount_reader): reading the journal files
+ * with the includes, the comments, and the conversion of the postings into the
+ * transactions to compare.
+ */
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::{
+    journal_backend::JournalQuery, model::CommonTransaction, Error, Result, ISO_DATE_FORMAT,
+};
+
+/// A posting of the journal, with the values from its transaction.
+pub(crate) struct JournalPosting<'a> {
+    /// The date to compare, the posting date or the effective date.
+    pub date: NaiveDate,
+    pub payee: &'a str,
+    pub symbol: &'a str,
+    pub description: &'a str,
+    pub account: &'a str,
+    /// The quantity and the commodity. None if the amount could not be inferred.
+    pub amount: Option<(Decimal, &'a str)>,
+    pub tx_metadata: &'a BTreeMap<String, String>,
+    pub metadata: &'a BTreeMap<String, String>,
+}
+
+/**
+ * Converts the postings into the transactions to compare. The postings before
+ * the start date and the ones without a type are skipped.
+ * The postings inherit the transaction metadata.
+ */
+pub(crate) fn get_transactions<'a>(
+    postings: impl IntoIterator<Item = JournalPosting<'a>>,
+    query: &JournalQuery,
+) -> Result<Vec<CommonTransaction>> {
+    let start_date = NaiveDate::parse_from_str(&query.start_date, ISO_DATE_FORMAT)
+        .map_err(|_| Error::InvalidDate(query.start_date.to_owned()))?;
+
+    let mut txs = vec![];
+    for posting in postings {
+        if posting.date < start_date {
+            continue;
+        }
+        let Some(tx_type) = query.account_rules.get_type(posting.account) else {
+            continue;
+        };
+        let Some((amount, currency)) = posting.amount else {
+            log::warn!("Could not infer the amount of {} on {}", posting.account, posting.date);
+            continue;
+        };
+
+        let date = posting.date.and_hms_opt(0, 0, 0).unwrap();
+        let mut tx = CommonTransaction {
+            date,
+            report_date: date.format(ISO_DATE_FORMAT).to_string(),
+            payee: posting.payee.to_owned(),
+            account: posting.account.to_owned(),
+            amount,
+            currency: currency.to_owned(),
+            symbol: posting.symbol.to_owned(),
+            r#type: tx_type,
+            description: posting.description.to_owned(),
+            ..Default::default()
+        };
+        for (key, value) in posting.tx_metadata.iter().chain(posting.metadata) {
+            tx.set_metadata(key, value);
+        }
+        txs.push(tx);
+    }
+
+    Ok(txs)
+}
+
+/// Reads the journal file. Returns the content and the directory for the includes.
+pub(crate) fn read_file(path: &Path) -> Result<(String, PathBuf)> {
+    log::debug!("reading journal {:?}", path);
+
+    let content = std::fs::read_to_string(path).map_err(|e| Error::io(path.display(), e))?;
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    Ok((content, dir))
+}
+
+/// The included files, relative to the directory. The path can be a glob pattern.
+pub(crate) fn get_include_paths(pattern: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    if pattern.is_empty() {
+        return Err(Error::Ledger("Invalid include without a file".into()));
+    }
+    let path = dir.join(PathBuf::from(pattern));
+
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![path]);
+    }
+
+    let paths = glob::glob(&path.to_string_lossy())
+        .map_err(|e| Error::Ledger(format!("Invalid include {pattern:?}: {e}")))?;
+    Ok(paths.flatten().collect())
+}
+
+/// Finds the first of the characters that is not inside the quotes.
+pub(crate) fn find_unquoted(text: &str, chars: &[char]) -> Option<usize> {
+    let mut in_quotes = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if !in_quotes && chars.contains(&c) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits the line at the comment, `;`, which is not inside the quotes.
+pub(crate) fn strip_comment(line: &str) -> (&str, Option<&str>) {
+    match find_unquoted(line, &[';']) {
+        Some(i) => (line[..i].trim_end(), Some(&line[i + 1..])),
+        None => (line.trim_end(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_comment;
+
+    #[test]
+    fn test_strip_comment() {
+        assert_eq!(
+            (r#"Assets:Cash  10 "A;B""#, Some(" note")),
+            strip_comment(r#"Assets:Cash  10 "A;B"  ; note"#)
+        );
+        assert_eq!(("Assets:Cash", None), strip_comment("Assets:Cash  "));
+    }
+}
@@ -0,0 +1,177 @@
+/*!
+ * Parser for Ledger's output of the `csv` command.
+ *
+ * Each posting is a separate record, with the columns
+ * date, code, payee, account, commodity, amount, cleared state, the posting note,
+ * and the transaction note. The output of the default format, without the last
+ * column, is also read.
+ * Unlike the register, the values do not depend on the column widths, so the
+ * long payees and the multi-byte characters are read correctly.
+ */
+
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::{
+    account_types::AccountRules, flex_enums::CashAction, ledger_reader::parse_note_metadata,
+    model::CommonTransaction, Error, Result, ISO_DATE_FORMAT,
+};
+
+const DATE_COLUMN: usize = 0;
+const PAYEE_COLUMN: usize = 2;
+const ACCOUNT_COLUMN: usize = 3;
+const COMMODITY_COLUMN: usize = 4;
+const AMOUNT_COLUMN: usize = 5;
+/// The posting note, with the metadata. In the default format, the transaction
+/// note if the posting has none.
+const NOTE_COLUMN: usize = 7;
+const TX_NOTE_COLUMN: usize = 8;
+
+/**
+ * Parses the output of `ledger csv`.
+ * The account rules give the transaction type of each posting.
+ */
+pub fn parse_csv_output(output: &str, account_rules: &AccountRules) -> Result<Vec<CommonTransaction>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        // Ledger escapes the quotes in the values with a backslash.
+        .escape(Some(b'\\'))
+        .from_reader(output.as_bytes());
+
+    let mut txs = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| Error::Ledger(format!("Invalid csv output: {e}")))?;
+        txs.push(parse_record(&record, account_rules)?);
+    }
+
+    Ok(txs)
+}
+
+/// Parses one posting record.
+fn parse_record(record: &csv::StringRecord, account_rules: &AccountRules) -> Result<CommonTransaction> {
+    log::debug!("parsing: {:?}", record);
+
+    let column = |index: usize| {
+        record
+            .get(index)
+            .map(str::trim)
+            .ok_or_else(|| Error::Ledger(format!("Unexpected csv record: {record:?}")))
+    };
+
+    let date_str = column(DATE_COLUMN)?;
+    let date = NaiveDate::parse_from_str(date_str, ISO_DATE_FORMAT)
+        .map_err(|_| Error::InvalidDate(date_str.to_owned()))?
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+
+    let payee = column(PAYEE_COLUMN)?;
+    let account = column(ACCOUNT_COLUMN)?;
+
+    let r#type = match account_rules.get_type(account) {
+        Some(tx_type) => tx_type,
+        None => {
+            log::warn!("Could not parse type {:?}", account);
+
+            CashAction::Other("Error!".to_string())
+        }
+    };
+
+    // remove the thousand separators, which the Decimal parser doesn't handle.
+    let amount_str = column(AMOUNT_COLUMN)?.replace(',', "");
+    let amount = Decimal::from_str(&amount_str).map_err(|_| Error::InvalidAmount(amount_str.to_owned()))?;
+
+    let mut tx = CommonTransaction {
+        date,
+        report_date: date.format(ISO_DATE_FORMAT).to_string(),
+        payee: payee.to_owned(),
+        account: account.to_owned(),
+        amount,
+        currency: column(COMMODITY_COLUMN)?.to_owned(),
+        // The symbol is the first word of the payee.
+        symbol: payee.split_whitespace().next().unwrap_or_default().to_owned(),
+        r#type,
+        ..Default::default()
+    };
+
+    // The posting metadata overrides the transaction metadata.
+    for column in [TX_NOTE_COLUMN, NOTE_COLUMN] {
+        let note = record.get(column).unwrap_or_default();
+        for (key, value) in parse_note_metadata(note) {
+            tx.set_metadata(&key, &value);
+        }
+    }
+
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::parse_csv_output;
+    use crate::{account_types::AccountRules, flex_enums::CashAction};
+
+    /// `ledger csv -f tests/journal.ledger --date-format %Y-%m-%d`
+    #[test_log::test]
+    fn test_parse_distribution() {
+        let output = r#""2022-12-15","","TRET_AS Distribution","Income:Investment:IB:TRET_AS","EUR","-38.40","",""
+"2022-12-15","","TRET_AS Distribution","Expenses:Investment:IB:Withholding Tax","EUR","5.77",""," ib_tx: 1234\\n isin: NL0009690239"
+"#;
+
+        let actual = parse_csv_output(output, &AccountRules::default()).unwrap();
+
+        assert_eq!(2, actual.len());
+        assert_eq!("TRET_AS", actual[0].symbol);
+        assert_eq!("2022-12-15", actual[0].report_date);
+        assert_eq!(CashAction::Dividend, actual[0].r#type);
+        assert_eq!(Decimal::from_str_exact("-38.40").unwrap(), actual[0].amount);
+        assert_eq!("EUR", actual[0].currency);
+        assert_eq!("Expenses:Investment:IB:Withholding Tax", actual[1].account);
+        assert_eq!(CashAction::WhTax, actual[1].r#type);
+        assert_eq!("1234", actual[1].transaction_id);
+        assert_eq!("NL0009690239", actual[1].isin);
+    }
+
+    /// The transaction metadata applies to all the postings.
+    /// `ledger csv -d <query> -f tests/tagged.ledger --csv-format <CSV_FORMAT> --date-format %Y-%m-%d`
+    #[test_log::test]
+    fn test_parse_tagged() {
+        let output = r#""2022-12-20","","TCBT_AS Distribution","Income:Investment:IB:TCBT_AS","EUR","-6.05",""," ib_tx: 1002"," ib_action: 9001"
+"2022-12-20","","TCBT_AS Distribution","Expenses:Investment:IB:Withholding Tax","EUR","0.91","",""," ib_action: 9001"
+"#;
+
+        let actual = parse_csv_output(output, &AccountRules::default()).unwrap();
+
+        assert_eq!(2, actual.len());
+        assert_eq!("1002", actual[0].transaction_id);
+        assert_eq!("9001", actual[0].action_id);
+        assert_eq!("", actual[1].transaction_id);
+        assert_eq!("9001", actual[1].action_id);
+    }
+
+    /// The long payees, the multi-byte characters, and the quotes do not shift the values.
+    #[test_log::test]
+    fn test_parse_long_payee() {
+        let output = r#""2022-12-15","","VHYL_AS Ausschüttung für das vierte Quartal, \"Überweisung\" – sehr lang","Income:Investment:IB:VHYL_AS","EUR","-1,038.40","*","note"
+"#;
+
+        let actual = parse_csv_output(output, &AccountRules::default()).unwrap();
+
+        assert_eq!(1, actual.len());
+        assert_eq!("VHYL_AS", actual[0].symbol);
+        assert!(actual[0].payee.ends_with("\"Überweisung\" – sehr lang"));
+        assert_eq!("Income:Investment:IB:VHYL_AS", actual[0].account);
+        assert_eq!(Decimal::from_str_exact("-1038.40").unwrap(), actual[0].amount);
+    }
+
+    #[test_log::test]
+    fn test_invalid_amount() {
+        let output = r#""2022-12-15","","TRET_AS","Income:Investment:IB:TRET_AS","EUR","n/a","",""
+"#;
+
+        assert!(parse_csv_output(output, &AccountRules::default()).is_err());
+    }
+}
@@ -3,13 +3,19 @@ use std::str::FromStr;
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
 
-use crate::{flex_enums::CashAction, model::CommonTransaction, ISO_DATE_FORMAT};
+use crate::{
+    account_types::AccountRules, flex_enums::CashAction, model::CommonTransaction, Error, Result,
+    ISO_DATE_FORMAT,
+};
 
 /**
 Parses the output of the `ledger print` command.
 This is the regular Ledger syntax.
 */
-pub(crate) fn parse_print_output(lines: Vec<&str>) -> Vec<CommonTransaction> {
+pub fn parse_print_output(
+    lines: Vec<&str>,
+    account_rules: &AccountRules,
+) -> Result<Vec<CommonTransaction>> {
     let mut tx = CommonTransaction::default();
     let mut result: Vec<CommonTransaction> = vec![];
     // Postings for the current transaction. Used to add amount to the postings without it.
@@ -22,35 +28,33 @@ pub(crate) fn parse_print_output(lines: Vec<&str>) -> Vec<CommonTransaction> {
         if trimmed.starts_with(';') {
             continue;
         }
-        if trimmed == "" {
+        if trimmed.is_empty() {
             continue;
         }
 
         // data lines
-        let have_tx = parse_tx_row(trimmed);
-
-        if have_tx.is_some() {
+        if let Some(new_tx) = parse_tx_row(trimmed) {
             // got the (new) transaction header.
 
             // If we already have a transaction header, process the posting records.
-            if tx.payee != "" {
-                postings = close_transaction(postings, &tx.currency);
+            if !tx.payee.is_empty() {
+                postings = close_transaction(postings, &tx.currency)?;
                 result.append(&mut postings);
                 // clear the postings vector
                 postings = vec![];
             }
 
             // set the transaction record.
-            tx = have_tx.unwrap();
+            tx = new_tx;
 
             continue;
         }
 
         // otherwise parse the postings.
-        let mut posting = parse_posting_row(trimmed);
+        let mut posting = parse_posting_row(trimmed, account_rules)?;
 
         // get the currency
-        if posting.currency != "" {
+        if !posting.currency.is_empty() {
             tx.currency = posting.currency.to_owned();
         }
 
@@ -65,19 +69,19 @@ pub(crate) fn parse_print_output(lines: Vec<&str>) -> Vec<CommonTransaction> {
         postings.push(posting);
     }
     // process the last transaction
-    postings = close_transaction(postings, &tx.currency);
+    postings = close_transaction(postings, &tx.currency)?;
     result.append(&mut postings);
 
-    result
+    Ok(result)
 }
 
 /// Makes final adjustments to the postings, like adding the amount to the records
 /// that don't have it.
-fn close_transaction(postings: Vec<CommonTransaction>, currency: &str) -> Vec<CommonTransaction> {
+fn close_transaction(postings: Vec<CommonTransaction>, currency: &str) -> Result<Vec<CommonTransaction>> {
     let mut result: Vec<CommonTransaction> = vec![];
 
     // Add amount to any postings missing it.
-    let amount = get_amount_from_postings(&postings);
+    let amount = get_amount_from_postings(&postings)?;
     for mut tx_posting in postings {
         log::debug!("posting amount: {:0} to be set", tx_posting.amount);
         if tx_posting.amount == Decimal::ZERO {
@@ -95,10 +99,10 @@ fn close_transaction(postings: Vec<CommonTransaction>, currency: &str) -> Vec<Co
         result.push(tx_posting);
     }
 
-    result
+    Ok(result)
 }
 
-fn get_amount_from_postings(postings: &Vec<CommonTransaction>) -> Decimal {
+fn get_amount_from_postings(postings: &[CommonTransaction]) -> Result<Decimal> {
     let mut amount = Decimal::ZERO;
 
     for posting in postings {
@@ -110,19 +114,19 @@ fn get_amount_from_postings(postings: &Vec<CommonTransaction>) -> Decimal {
             // we don't have an amount yet.
             amount = posting.amount;
         } else {
-            panic!("multiple amounts found!");
+            return Err(Error::Ledger(format!(
+                "multiple amounts found in the transaction {}!",
+                posting.payee
+            )));
         }
     }
-    amount
+    Ok(amount)
 }
 
 fn parse_tx_row(line: &str) -> Option<CommonTransaction> {
     // date
     // always use ISO dates, sorry
-    let date = get_date_from_line(line);
-    if date.is_none() {
-        return None;
-    }
+    let date = get_date_from_line(line)?;
 
     let payee_start_index = 11;
     let comment_index = line.find(';');
@@ -136,7 +140,7 @@ fn parse_tx_row(line: &str) -> Option<CommonTransaction> {
 
     let mut tx = CommonTransaction::default();
 
-    tx.date = date.unwrap().and_hms_opt(0, 0, 0).unwrap();
+    tx.date = date.and_hms_opt(0, 0, 0).unwrap();
     tx.report_date = tx.date.format(ISO_DATE_FORMAT).to_string();
     
     tx.payee = payee.to_owned();
@@ -152,15 +156,11 @@ fn parse_tx_row(line: &str) -> Option<CommonTransaction> {
 }
 
 fn get_date_from_line(line: &str) -> Option<NaiveDate> {
-    let date_string = &line[0..10];
-    let result = NaiveDate::parse_from_str(&date_string, ISO_DATE_FORMAT);
-    match result {
-        Ok(date) => Some(date),
-        Err(_) => None,
-    }
+    let date_string = line.get(0..10)?;
+    NaiveDate::parse_from_str(date_string, ISO_DATE_FORMAT).ok()
 }
 
-fn parse_posting_row(line: &str) -> CommonTransaction {
+fn parse_posting_row(line: &str, account_rules: &AccountRules) -> Result<CommonTransaction> {
     let mut tx = CommonTransaction::default();
 
     let have_amount = line.find("  ");
@@ -172,8 +172,7 @@ fn parse_posting_row(line: &str) -> CommonTransaction {
     };
 
     // is there an amount?
-    if have_amount.is_some() {
-        let amount_index = have_amount.unwrap();
+    if let Some(amount_index) = have_amount {
         let mut amount_str = &line[amount_index..];
         // log::debug!("starting amount parse from '{:0}'", amount_str);
         amount_str = amount_str.trim();
@@ -187,31 +186,31 @@ fn parse_posting_row(line: &str) -> CommonTransaction {
         // split the currency
         let amount_parts: Vec<&str> = amount_str.split(' ').collect();
 
-        tx.amount = Decimal::from_str(&amount_parts[0]).expect("amount value");
-        tx.currency = amount_parts[1].to_owned();
+        let [amount, currency] = amount_parts[..] else {
+            return Err(Error::InvalidAmount(amount_str));
+        };
+        tx.amount = Decimal::from_str(amount).map_err(|_| Error::InvalidAmount(amount_str.to_owned()))?;
+        tx.currency = currency.to_owned();
     }
 
     // Type
-    // Get just the first 2 characters.
-    let account = &tx.account[0..2];
-    tx.r#type = if account == "In" {
-        CashAction::Dividend.to_string()
-    } else if account == "Ex" {
-        CashAction::WhTax.to_string()
-    } else {
-        log::warn!("Could not parse type {:?}", account);
-
-        "Error!".to_string()
+    tx.r#type = match account_rules.get_type(&tx.account) {
+        Some(tx_type) => tx_type,
+        None => {
+            log::warn!("Could not parse type {:?}", tx.account);
+
+            CashAction::Other("Error!".to_string())
+        }
     };
 
-    tx
+    Ok(tx)
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs;
 
-    use crate::ledger_print_output_parser::parse_print_output;
+    use crate::{account_types::AccountRules, ledger_print_output_parser::parse_print_output};
 
     #[test_log::test]
     fn parse_print() {
@@ -222,7 +221,7 @@ mod tests {
         let journal = fs::read_to_string("tests/tcf.ledger").expect("test file read");
         let lines = journal.lines().collect();
 
-        let actual = parse_print_output(lines);
+        let actual = parse_print_output(lines, &AccountRules::default()).unwrap();
 
         assert!(!actual.is_empty());
         // 7 transaction records / postings.
@@ -0,0 +1,1073 @@
+/*!
+ * Reads the postings from a Ledger journal, without running Ledger.
+ *
+ * Supports the syntax used in the investment books: the transactions with any
+ * number of postings (one elided amount per balancing group is inferred), the
+ * `date=effective` dates and the posting dates in the notes (`; [=2022-12-16]`),
+ * the comments and the metadata (`; key: value` and `; :tag:`), the commodity annotations (lot prices, lot
+ * dates, and the `@`/`@@` prices), the virtual postings, the `include`
+ * directives, and the automated transactions.
+ * The automated transaction predicates are account patterns, which can be
+ * combined with `and`, `or`, and `not`. Only the `^` and `$` anchors and `.*`
+ * are recognized in the patterns; the other predicates are errors.
+ * `alias` and `apply account` are errors, as they rename the accounts. The
+ * other directives are skipped.
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    str::FromStr,
+};
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+
+use crate::{
+    journal_backend::{JournalBackend, JournalQuery},
+    journal_reader::{self, find_unquoted, strip_comment, JournalPosting},
+    model::CommonTransaction,
+    Error, Result,
+};
+
+/// The environment variable with the default journal, as in Ledger.
+const LEDGER_FILE_VAR: &str = "LEDGER_FILE";
+
+/// Reads the Ledger journal file natively.
+#[derive(Debug, Default)]
+pub struct LedgerReaderBackend;
+
+impl JournalBackend for LedgerReaderBackend {
+    fn get_transactions(&self, query: &JournalQuery) -> Result<Vec<CommonTransaction>> {
+        let Some(journal_file) = query
+            .journal_file
+            .to_owned()
+            .or_else(|| std::env::var(LEDGER_FILE_VAR).ok())
+        else {
+            return Err(Error::Config(format!(
+                "The journal file is mandatory. Use --ledger-journal-file or set {LEDGER_FILE_VAR}."
+            )));
+        };
+        let entries = read_journal(Path::new(&journal_file))?;
+
+        let postings = entries.iter().flat_map(|entry| {
+            entry.postings.iter().map(|posting| JournalPosting {
+                date: posting.get_date(entry, query.effective_dates),
+                payee: &entry.payee,
+                // The symbol is the first word of the payee.
+                symbol: entry.payee.split_whitespace().next().unwrap_or_default(),
+                description: "",
+                account: &posting.account,
+                amount: posting
+                    .amount
+                    .as_ref()
+                    .map(|amount| (amount.quantity, amount.commodity.as_str())),
+                tx_metadata: &entry.metadata,
+                metadata: &posting.metadata,
+            })
+        });
+
+        journal_reader::get_transactions(postings, query)
+    }
+}
+
+/// Reads the transactions from the journal file and the included files.
+pub fn read_journal(path: &Path) -> Result<Vec<Entry>> {
+    let mut parser = JournalParser::default();
+    parser.read_file(path)?;
+    Ok(parser.entries)
+}
+
+/// Parses the journal content. The includes are relative to the directory.
+pub fn parse_journal(content: &str, dir: &Path) -> Result<Vec<Entry>> {
+    let mut parser = JournalParser::default();
+    parser.parse(content, dir)?;
+    Ok(parser.entries)
+}
+
+/// A Ledger transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub date: NaiveDate,
+    pub effective_date: Option<NaiveDate>,
+    pub payee: String,
+    pub postings: Vec<Posting>,
+    /// The metadata values. The tags have empty values.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A posting, with the brackets removed from the virtual account names.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Posting {
+    pub account: String,
+    pub kind: PostingKind,
+    /// The amount, if not elided or inferred.
+    pub amount: Option<Amount>,
+    /// The posting date, from the note.
+    pub date: Option<NaiveDate>,
+    pub effective_date: Option<NaiveDate>,
+    pub metadata: BTreeMap<String, String>,
+    /// The amount used for balancing the transaction, if different (price or cost).
+    cost: Option<Amount>,
+}
+
+impl Posting {
+    /// The date of the posting. The posting dates override the transaction dates.
+    pub fn get_date(&self, entry: &Entry, effective_dates: bool) -> NaiveDate {
+        let date = self.date.unwrap_or(entry.date);
+        if effective_dates {
+            self.effective_date.or(entry.effective_date).unwrap_or(date)
+        } else {
+            date
+        }
+    }
+
+    /// The value that balances the transaction.
+    fn balance_amount(&self) -> Option<&Amount> {
+        self.cost.as_ref().or(self.amount.as_ref())
+    }
+}
+
+/// The postings of each kind balance separately, except the virtual ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostingKind {
+    #[default]
+    Real,
+    /// `(Account)`, does not have to balance.
+    Virtual,
+    /// `[Account]`, balances with the other balanced virtual postings.
+    BalancedVirtual,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Amount {
+    pub quantity: Decimal,
+    /// The commodity, without the quotes. Empty for the plain numbers.
+    pub commodity: String,
+}
+
+impl Amount {
+    fn new(quantity: Decimal, commodity: &str) -> Amount {
+        Amount {
+            quantity,
+            commodity: commodity.to_owned(),
+        }
+    }
+}
+
+/// `= predicate` with the postings added to the matching postings' transactions.
+#[derive(Debug, Default, Clone)]
+struct AutomatedTransaction {
+    predicate: Predicate,
+    postings: Vec<AutomatedPosting>,
+}
+
+#[derive(Debug, Clone)]
+struct AutomatedPosting {
+    account: String,
+    kind: PostingKind,
+    amount: AutomatedAmount,
+}
+
+#[derive(Debug, Clone)]
+enum AutomatedAmount {
+    /// A number without a commodity multiplies the matching posting's amount.
+    Multiplier(Decimal),
+    Fixed(Amount),
+}
+
+/// Account patterns, in the disjunctive form: any of the alternatives,
+/// with all of their terms matching.
+#[derive(Debug, Default, Clone)]
+struct Predicate {
+    alternatives: Vec<Vec<PredicateTerm>>,
+}
+
+#[derive(Debug, Clone)]
+struct PredicateTerm {
+    negated: bool,
+    pattern: String,
+}
+
+impl Predicate {
+    /// Parses i.e. `/^Income:.*:IB/ and not /Interest/` or `expr 'account =~ /IB/'`.
+    /// The other terms (payee, tag, ...), the parentheses, and the other regular
+    /// expression syntax are errors, as they would select other postings.
+    fn parse(text: &str) -> Result<Predicate> {
+        let text = text.trim();
+        let text = text.strip_prefix("expr ").unwrap_or(text).trim();
+        let text = text.trim_matches(|c| c == '\'' || c == '"');
+        let unsupported = |reason: &str| {
+            Error::Ledger(format!(
+                "Unsupported automated transaction predicate {text:?}: {reason}. Use the ledger backend."
+            ))
+        };
+
+        let tokens = tokenize_predicate(text);
+        let mut alternatives = vec![vec![]];
+        let mut negated = false;
+        for (i, token) in tokens.iter().enumerate() {
+            match token.as_str() {
+                "or" | "|" => alternatives.push(vec![]),
+                "and" | "&" | "account" => {}
+                "=~" if i > 0 && tokens[i - 1] == "account" => {}
+                "=~" => return Err(unsupported("only the account can be matched")),
+                "not" | "!" => negated = !negated,
+                _ => {
+                    if token.starts_with(['@', '%', '#', '=']) || token.contains(['(', ')']) {
+                        return Err(unsupported(&format!("{token:?} is not an account pattern")));
+                    }
+                    let pattern = token.trim_matches('/').to_owned();
+                    if !is_supported_pattern(&pattern) {
+                        return Err(unsupported(&format!(
+                            "only ^, $, and .* are supported in the pattern {pattern:?}"
+                        )));
+                    }
+                    alternatives.last_mut().unwrap().push(PredicateTerm { negated, pattern });
+                    negated = false;
+                }
+            }
+        }
+        alternatives.retain(|terms| !terms.is_empty());
+
+        Ok(Predicate { alternatives })
+    }
+
+    fn matches(&self, account: &str) -> bool {
+        self.alternatives.iter().any(|terms| {
+            terms
+                .iter()
+                .all(|term| pattern_matches(&term.pattern, account) != term.negated)
+        })
+    }
+}
+
+/// Splits the predicate into the words and the `/patterns/`, which can contain spaces.
+fn tokenize_predicate(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::default();
+    let mut in_pattern = false;
+
+    for c in text.chars() {
+        match c {
+            '/' => {
+                current.push(c);
+                if in_pattern {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                in_pattern = !in_pattern;
+            }
+            c if c.is_whitespace() && !in_pattern => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// Checks that the pattern has no other regular expression syntax than
+/// the anchors and `.*`, which [`pattern_matches`] recognizes.
+fn is_supported_pattern(pattern: &str) -> bool {
+    let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+    let pattern = pattern.strip_suffix('$').unwrap_or(pattern);
+    !pattern
+        .replace(".*", "")
+        .contains(|c| r"\|()[]{}+?*.^$".contains(c))
+}
+
+/// Matches the account with the pattern, case-insensitive.
+/// Recognizes the `^` and `$` anchors and the `.*` wildcards.
+fn pattern_matches(pattern: &str, account: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let account = account.to_lowercase();
+
+    let (anchored_start, pattern) = match pattern.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, pattern.as_str()),
+    };
+    let (anchored_end, pattern) = match pattern.strip_suffix('$') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+
+    let parts: Vec<&str> = pattern.split(".*").collect();
+    let mut rest = account.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        let is_first = i == 0;
+        let is_last = i == parts.len() - 1;
+
+        if is_first && anchored_start {
+            let Some(remaining) = rest.strip_prefix(part) else {
+                return false;
+            };
+            rest = remaining;
+        } else if is_last && anchored_end {
+            return rest.ends_with(part);
+        } else {
+            let Some(index) = rest.find(part) else {
+                return false;
+            };
+            rest = &rest[index + part.len()..];
+        }
+    }
+
+    !anchored_end || rest.is_empty()
+}
+
+/// The block being parsed, which the indented lines belong to.
+enum Block {
+    None,
+    Transaction(Entry),
+    Automated(AutomatedTransaction),
+    /// A directive with the sub-directives, or a periodic transaction.
+    Skipped,
+    /// `comment` or `test` until `end <name>`.
+    Comment(String),
+}
+
+#[derive(Default)]
+struct JournalParser {
+    entries: Vec<Entry>,
+    automated: Vec<AutomatedTransaction>,
+}
+
+impl JournalParser {
+    fn read_file(&mut self, path: &Path) -> Result<()> {
+        let (content, dir) = journal_reader::read_file(path)?;
+
+        self.parse(&content, &dir)
+    }
+
+    fn parse(&mut self, content: &str, dir: &Path) -> Result<()> {
+        let mut block = Block::None;
+
+        for line in content.lines() {
+            if let Block::Comment(name) = &block {
+                if line.trim_end() == format!("end {name}") {
+                    block = Block::None;
+                }
+                continue;
+            }
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let indented = line.starts_with([' ', '\t']);
+            if indented {
+                match &mut block {
+                    Block::Transaction(entry) => parse_entry_line(entry, trimmed)?,
+                    Block::Automated(automated) => {
+                        if let Some(posting) = parse_automated_posting(trimmed)? {
+                            automated.postings.push(posting);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // A new directive closes the current block.
+            self.close(std::mem::replace(&mut block, Block::None));
+
+            block = self.parse_directive(trimmed, dir)?;
+        }
+
+        self.close(block);
+
+        Ok(())
+    }
+
+    /// Parses the unindented line, which starts a new block.
+    fn parse_directive(&mut self, line: &str, dir: &Path) -> Result<Block> {
+        if line.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(Block::Transaction(parse_header(line)?));
+        }
+        if line.starts_with([';', '#', '%', '|', '*']) {
+            return Ok(Block::None);
+        }
+        if let Some(predicate) = line.strip_prefix('=') {
+            return Ok(Block::Automated(AutomatedTransaction {
+                predicate: Predicate::parse(strip_comment(predicate).0)?,
+                postings: vec![],
+            }));
+        }
+
+        let line = line.strip_prefix('!').unwrap_or(line);
+        let (directive, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match directive {
+            "include" => {
+                self.include(argument.trim(), dir)?;
+                Ok(Block::None)
+            }
+            "comment" | "test" => Ok(Block::Comment(directive.to_owned())),
+            // These would change the account names, and so the posting types.
+            "alias" => Err(unsupported_directive(line)),
+            "apply" if argument.trim_start().starts_with("account") => {
+                Err(unsupported_directive(line))
+            }
+            "apply" => {
+                log::warn!("Skipping the unsupported directive {line:?}");
+                Ok(Block::None)
+            }
+            _ => Ok(Block::Skipped),
+        }
+    }
+
+    /// Reads the included file(s). The paths can be glob patterns.
+    fn include(&mut self, pattern: &str, dir: &Path) -> Result<()> {
+        for included in journal_reader::get_include_paths(pattern.trim_matches('"'), dir)? {
+            self.read_file(&included)?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, block: Block) {
+        match block {
+            Block::Transaction(entry) => {
+                let entry = self.apply_automated(close_transaction(entry));
+                self.entries.push(entry);
+            }
+            Block::Automated(automated) => self.automated.push(automated),
+            _ => {}
+        }
+    }
+
+    /// Adds the postings of the automated transactions for the matching postings.
+    fn apply_automated(&self, mut entry: Entry) -> Entry {
+        let mut generated = vec![];
+
+        for posting in &entry.postings {
+            for automated in &self.automated {
+                if !automated.predicate.matches(&posting.account) {
+                    continue;
+                }
+
+                for automated_posting in &automated.postings {
+                    let amount = match (&automated_posting.amount, &posting.amount) {
+                        (AutomatedAmount::Fixed(amount), _) => Some(amount.clone()),
+                        (AutomatedAmount::Multiplier(factor), Some(amount)) => Some(Amount::new(
+                            amount.quantity * factor,
+                            &amount.commodity,
+                        )),
+                        (AutomatedAmount::Multiplier(_), None) => None,
+                    };
+
+                    generated.push(Posting {
+                        account: automated_posting.account.to_owned(),
+                        kind: automated_posting.kind,
+                        amount,
+                        date: posting.date,
+                        effective_date: posting.effective_date,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        entry.postings.append(&mut generated);
+        entry
+    }
+}
+
+fn unsupported_directive(line: &str) -> Error {
+    Error::Ledger(format!(
+        "The directive {line:?} is not supported by the native reader. Use the ledger backend."
+    ))
+}
+
+/// Parses the transaction header line, i.e. `2022-12-15=2022-12-16 * (123) TRET_AS Distribution ; note`.
+fn parse_header(line: &str) -> Result<Entry> {
+    let (line, note) = strip_comment(line);
+    let (dates, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+    let (date_str, effective_str) = match dates.split_once('=') {
+        Some((date, effective)) => (date, Some(effective)),
+        None => (dates, None),
+    };
+    let date = parse_date(date_str, None)?;
+    let effective_date = effective_str
+        .map(|effective| parse_date(effective, Some(date.year())))
+        .transpose()?;
+
+    // The state flag and the code.
+    let mut payee = rest.trim_start();
+    payee = payee
+        .strip_prefix(['*', '!'])
+        .map(str::trim_start)
+        .unwrap_or(payee);
+    if payee.starts_with('(') {
+        if let Some(end) = payee.find(')') {
+            payee = payee[end + 1..].trim_start();
+        }
+    }
+
+    Ok(Entry {
+        date,
+        effective_date,
+        payee: payee.trim_end().to_owned(),
+        postings: vec![],
+        metadata: note.map(parse_note_metadata).unwrap_or_default(),
+    })
+}
+
+/// Parses the posting or the note line of the transaction.
+fn parse_entry_line(entry: &mut Entry, line: &str) -> Result<()> {
+    if let Some(note) = line.strip_prefix(';') {
+        // The note applies to the last posting, or to the transaction.
+        match entry.postings.last_mut() {
+            Some(posting) => apply_posting_note(posting, note, entry.date),
+            None => entry.metadata.extend(parse_note_metadata(note)),
+        }
+        return Ok(());
+    }
+
+    let (line, note) = strip_comment(line);
+    let Some(mut posting) = parse_posting(line)? else {
+        return Ok(());
+    };
+    if let Some(note) = note {
+        apply_posting_note(&mut posting, note, entry.date);
+    }
+    entry.postings.push(posting);
+
+    Ok(())
+}
+
+/// Reads the metadata and the posting dates, `[2022-12-15]`, `[=2022-12-16]`, or
+/// `[2022-12-15=2022-12-16]`, from the note. The other bracketed text is skipped.
+fn apply_posting_note(posting: &mut Posting, note: &str, tx_date: NaiveDate) {
+    posting.metadata.extend(parse_note_metadata(note));
+
+    let Some(start) = note.find('[') else {
+        return;
+    };
+    let Some(length) = note[start..].find(']') else {
+        return;
+    };
+    let dates = &note[start + 1..start + length];
+
+    let (date_str, effective_str) = dates.split_once('=').unwrap_or((dates, ""));
+    let date = (!date_str.is_empty()).then(|| parse_date(date_str, Some(tx_date.year())));
+    let effective_date = (!effective_str.is_empty()).then(|| parse_date(effective_str, Some(tx_date.year())));
+
+    match (date.transpose(), effective_date.transpose()) {
+        (Ok(date), Ok(effective_date)) => {
+            posting.date = date.or(posting.date);
+            posting.effective_date = effective_date.or(posting.effective_date);
+        }
+        _ => log::debug!("not a posting date: {dates:?}"),
+    }
+}
+
+/**
+ * Gets the metadata from the note: `key: value` (also `key:: value`) at the start
+ * of a line, and the tags, `:tag1:tag2:`, with empty values.
+ * The lines of the note can also be separated by the escaped `\n`, as in the
+ * `ledger csv` output.
+ */
+pub(crate) fn parse_note_metadata(note: &str) -> BTreeMap<String, String> {
+    let mut metadata = BTreeMap::new();
+
+    for line in note.split('\n').flat_map(|line| line.split("\\n")) {
+        let line = line.trim().trim_start_matches(';').trim();
+
+        if let Some(tags) = line.strip_prefix(':') {
+            let tags = tags.split_whitespace().next().unwrap_or_default();
+            for tag in tags.split(':').filter(|tag| !tag.is_empty()) {
+                metadata.insert(tag.to_owned(), String::default());
+            }
+            continue;
+        }
+
+        let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let Some(key) = first.strip_suffix(':') else {
+            continue;
+        };
+        let key = key.strip_suffix(':').unwrap_or(key);
+        if !key.is_empty() && !key.contains(':') {
+            metadata.insert(key.to_owned(), rest.trim().to_owned());
+        }
+    }
+
+    metadata
+}
+
+/// Parses the account and the amount of the posting line, without the note.
+/// Returns None for the postings that cannot be used, like the balance assignments.
+fn parse_posting(line: &str) -> Result<Option<Posting>> {
+    // The state flag.
+    let line = line
+        .strip_prefix(['*', '!'])
+        .map(str::trim_start)
+        .unwrap_or(line);
+
+    let (account, rest) = split_account(line);
+    let (account, kind) = get_account_kind(account);
+
+    // The balance assertion, after the amount.
+    let (amount_str, assertion) = match rest.split_once('=') {
+        Some((amount, assertion)) => (amount.trim(), Some(assertion)),
+        None => (rest, None),
+    };
+
+    if amount_str.is_empty() {
+        if assertion.is_some() {
+            log::warn!("Balance assignments are not supported, skipping {line:?}");
+            return Ok(None);
+        }
+        return Ok(Some(Posting {
+            account,
+            kind,
+            ..Default::default()
+        }));
+    }
+
+    let (amount, cost) = parse_posting_amount(amount_str)
+        .ok_or_else(|| Error::InvalidAmount(amount_str.to_owned()))?;
+
+    Ok(Some(Posting {
+        account,
+        kind,
+        amount: Some(amount),
+        cost,
+        ..Default::default()
+    }))
+}
+
+/// The postings of an automated transaction.
+fn parse_automated_posting(line: &str) -> Result<Option<AutomatedPosting>> {
+    if line.starts_with(';') {
+        return Ok(None);
+    }
+    let (line, _note) = strip_comment(line);
+    let (account, amount_str) = split_account(line);
+    let (account, kind) = get_account_kind(account);
+
+    let amount = if let Ok(factor) = Decimal::from_str(amount_str) {
+        AutomatedAmount::Multiplier(factor)
+    } else {
+        let amount = parse_amount(amount_str).ok_or_else(|| Error::InvalidAmount(amount_str.to_owned()))?;
+        AutomatedAmount::Fixed(amount)
+    };
+
+    Ok(Some(AutomatedPosting { account, kind, amount }))
+}
+
+/// The account ends at two spaces or a tab.
+fn split_account(line: &str) -> (&str, &str) {
+    let end = [line.find("  "), line.find('\t')]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(line.len());
+    (line[..end].trim(), line[end..].trim())
+}
+
+/// Removes the brackets of the virtual accounts.
+fn get_account_kind(account: &str) -> (String, PostingKind) {
+    if let Some(name) = account.strip_prefix('(').and_then(|a| a.strip_suffix(')')) {
+        (name.to_owned(), PostingKind::Virtual)
+    } else if let Some(name) = account.strip_prefix('[').and_then(|a| a.strip_suffix(']')) {
+        (name.to_owned(), PostingKind::BalancedVirtual)
+    } else {
+        (account.to_owned(), PostingKind::Real)
+    }
+}
+
+/**
+ * Parses the amount with the annotations and the price, i.e.
+ * `10 VHYL {50.00 EUR} [2022-01-01] (lot) @ 52.00 EUR`.
+ * Returns the amount and, with a price or a lot price, the value for balancing.
+ */
+fn parse_posting_amount(text: &str) -> Option<(Amount, Option<Amount>)> {
+    let (text, price) = match text.split_once('@') {
+        Some((amount, price)) => (amount.trim(), Some(price)),
+        None => (text, None),
+    };
+
+    // The lot price.
+    let annotation_start = find_unquoted(text, &['{', '[', '(']).unwrap_or(text.len());
+    let annotations = &text[annotation_start..];
+    let amount = parse_amount(&text[..annotation_start])?;
+
+    let lot_price = annotations
+        .strip_prefix('{')
+        .and_then(|a| a.split_once('}'))
+        .map(|(price, _)| price);
+
+    let cost = match (price, lot_price) {
+        (Some(price), _) => match price.strip_prefix('@') {
+            // `@@`, the total price
+            Some(total) => Some((parse_amount(total)?, true)),
+            None => Some((parse_amount(price)?, false)),
+        },
+        (None, Some(lot_price)) => match lot_price.strip_prefix('{') {
+            // `{{...}}`, the total cost
+            Some(total) => Some((parse_amount(total)?, true)),
+            None => Some((parse_amount(lot_price)?, false)),
+        },
+        (None, None) => None,
+    };
+
+    let cost = cost.map(|(price, is_total)| {
+        let quantity = if is_total {
+            let mut total = price.quantity.abs();
+            total.set_sign_negative(amount.quantity.is_sign_negative());
+            total
+        } else {
+            price.quantity * amount.quantity
+        };
+        Amount::new(quantity, &price.commodity)
+    });
+
+    Some((amount, cost))
+}
+
+/// Parses the amount, with the commodity before or after the number,
+/// i.e. `-38.40 EUR`, `EUR -38.40`, `$-10`, `-$10`, or `10 "VHYL 1"`.
+fn parse_amount(text: &str) -> Option<Amount> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text),
+    };
+
+    let is_number_start = |c: char| c.is_ascii_digit() || c == '.';
+    let (number, commodity) = if text.starts_with(is_number_start) {
+        let end = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+            .unwrap_or(text.len());
+        (&text[..end], text[end..].trim())
+    } else {
+        let end = if let Some(quoted) = text.strip_prefix('"') {
+            quoted.find('"')? + 2
+        } else {
+            text.find(|c: char| is_number_start(c) || c == '-' || c.is_whitespace())?
+        };
+        (text[end..].trim(), text[..end].trim())
+    };
+
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(rest) => (!negative, rest),
+        None => (negative, number),
+    };
+    let mut quantity = Decimal::from_str(&number.replace(',', "")).ok()?;
+    if negative {
+        quantity.set_sign_negative(true);
+    }
+
+    Some(Amount::new(quantity, commodity.trim_matches('"')))
+}
+
+/// Parses `2022-12-15`, `2022/12/15`, or `2022.12.15`.
+/// The date without the year, i.e. the effective `12/16`, takes the given year.
+fn parse_date(text: &str, default_year: Option<i32>) -> Result<NaiveDate> {
+    let invalid = || Error::InvalidDate(text.to_owned());
+
+    let parts: Vec<&str> = text.trim().split(['-', '/', '.']).collect();
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|part| part.parse::<u32>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| invalid())?;
+
+    let (year, month, day) = match numbers[..] {
+        [year, month, day] => (year as i32, month, day),
+        [month, day] => (default_year.ok_or_else(invalid)?, month, day),
+        _ => return Err(invalid()),
+    };
+
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)
+}
+
+/// Infers the elided amounts, which balance the postings of the same kind.
+/// The elided amount of a multi-commodity transaction becomes one posting per commodity.
+fn close_transaction(mut entry: Entry) -> Entry {
+    let mut postings = vec![];
+
+    for kind in [PostingKind::Real, PostingKind::BalancedVirtual] {
+        let mut totals: Vec<(String, Decimal)> = vec![];
+        for amount in entry
+            .postings
+            .iter()
+            .filter(|posting| posting.kind == kind)
+            .filter_map(Posting::balance_amount)
+        {
+            match totals.iter_mut().find(|(commodity, _)| *commodity == amount.commodity) {
+                Some((_, total)) => *total += amount.quantity,
+                None => totals.push((amount.commodity.to_owned(), amount.quantity)),
+            }
+        }
+
+        let elided: Vec<&Posting> = entry
+            .postings
+            .iter()
+            .filter(|posting| posting.kind == kind && posting.amount.is_none())
+            .collect();
+        if elided.len() > 1 {
+            log::warn!(
+                "Only one posting with an elided amount is allowed, in {} {}",
+                entry.date,
+                entry.payee
+            );
+        }
+        let Some(elided) = elided.first() else {
+            continue;
+        };
+
+        let mut inferred: HashMap<&str, Posting> = HashMap::new();
+        for (commodity, total) in totals.iter().filter(|(_, total)| !total.is_zero()) {
+            inferred.insert(
+                commodity,
+                Posting {
+                    amount: Some(Amount::new(-total, commodity)),
+                    ..(*elided).clone()
+                },
+            );
+        }
+        // Keep the order of the commodities.
+        for (commodity, _) in &totals {
+            postings.extend(inferred.remove(commodity.as_str()));
+        }
+    }
+
+    if !postings.is_empty() {
+        // Replace the elided postings with the inferred ones.
+        let mut result: Vec<Posting> = vec![];
+        for posting in entry.postings {
+            if posting.amount.is_none() && posting.kind != PostingKind::Virtual {
+                let kind = posting.kind;
+                result.extend(postings.iter().filter(|p| p.kind == kind).cloned());
+                postings.retain(|p| p.kind != kind);
+            } else {
+                result.push(posting);
+            }
+        }
+        entry.postings = result;
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, str::FromStr};
+
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    use super::{
+        parse_amount, parse_journal, parse_note_metadata, pattern_matches, read_journal, Amount,
+        LedgerReaderBackend,
+    };
+    use crate::{
+        account_types::AccountRules,
+        flex_enums::CashAction,
+        journal_backend::{JournalBackend, JournalQuery},
+        Error,
+    };
+
+    fn amount(quantity: &str, commodity: &str) -> Option<Amount> {
+        Some(Amount::new(Decimal::from_str(quantity).unwrap(), commodity))
+    }
+
+    #[test]
+    fn test_parse_transactions() {
+        let content = r#"
+; header comment
+account Income:Investment:IB:TRET_AS
+    note Distributions
+
+2022-12-15=2022-12-16 * (42) TRET_AS Distribution  ; :ib:
+    ; matches the Flex report
+    Income:Investment:IB:TRET_AS                     -38.40 EUR
+    ; isin: NL0009690239
+    Expenses:Investment:IB:Withholding Tax             5.77 EUR  ; [=2022-12-20]
+    Assets:Investment:IB:Cash
+
+comment
+2022-12-16 Ignored
+    Expenses:Food  3 EUR
+end comment
+
+2022/12/17 Exchange
+    Assets:Investment:IB:USD          10.00 USD @ 0.95 EUR
+    Assets:Investment:IB:Cash         -5.00 EUR
+    Assets:Investment:IB:Cash
+"#;
+
+        let actual = parse_journal(content, Path::new("tests")).unwrap();
+
+        assert_eq!(2, actual.len());
+        let entry = &actual[0];
+        assert_eq!("TRET_AS Distribution", entry.payee);
+        assert_eq!(NaiveDate::from_ymd_opt(2022, 12, 16), entry.effective_date);
+        assert_eq!(3, entry.postings.len());
+        assert_eq!(amount("32.63", "EUR"), entry.postings[2].amount);
+        assert_eq!(NaiveDate::from_ymd_opt(2022, 12, 20), entry.postings[1].effective_date);
+        assert!(entry.metadata.contains_key("ib"));
+        assert_eq!("NL0009690239", entry.postings[0].metadata["isin"]);
+
+        // balanced with the price
+        assert_eq!(amount("-4.50", "EUR"), actual[1].postings[2].amount);
+    }
+
+    /// The commodity annotations and the multi-commodity transactions.
+    #[test]
+    fn test_multiple_commodities() {
+        let content = r#"
+2022-12-15 VHYL_AS Purchase and Dividend
+    Assets:Investment:IB:VHYL  10 "VHYL 1" {50.00 EUR} [2022-01-01] (lot)
+    Income:Investment:IB:VHYL_AS  -3.00 USD
+    [Equity:Virtual]  1 EUR
+    [Equity:Balance]
+    (Budget:Investments)  -500 EUR
+    Assets:Investment:IB:Cash
+"#;
+
+        let actual = parse_journal(content, Path::new("tests")).unwrap();
+
+        let postings = &actual[0].postings;
+        assert_eq!(amount("10", "VHYL 1"), postings[0].amount);
+        assert_eq!(amount("-1", "EUR"), postings[3].amount);
+        assert_eq!(amount("-500", "EUR"), postings[4].amount);
+        // The elided amount is split by commodity.
+        assert_eq!(7, postings.len());
+        assert_eq!(amount("-500.00", "EUR"), postings[5].amount);
+        assert_eq!(amount("3.00", "USD"), postings[6].amount);
+    }
+
+    #[test]
+    fn test_automated_transactions() {
+        let content = r#"
+= /^Income:.*:IB/ and not /Interest/
+    (Liabilities:Tax Reserve)  0.25
+    (Budget:Fixed)  1 EUR
+
+2022-12-15 TRET_AS Distribution
+    Income:Investment:IB:TRET_AS  -40 EUR
+    Income:Investment:IB:Interest  -1 EUR
+    Assets:Investment:IB:Cash
+"#;
+
+        let actual = parse_journal(content, Path::new("tests")).unwrap();
+
+        let postings = &actual[0].postings;
+        assert_eq!(5, postings.len());
+        assert_eq!("Liabilities:Tax Reserve", postings[3].account);
+        assert_eq!(amount("-10", "EUR"), postings[3].amount);
+        assert_eq!(amount("1", "EUR"), postings[4].amount);
+    }
+
+    /// The predicates that would select other postings are not misread.
+    #[test]
+    fn test_unsupported_predicates() {
+        for predicate in [
+            "/Income|Revenue/",
+            "/^Income:[A-Z]+/",
+            "(/Income/ or /Revenue/) and /IB/",
+            "payee =~ /TRET/",
+            "expr 'tag(\"isin\")'",
+            "%isin",
+        ] {
+            let content = format!("= {predicate}\n    (Budget)  1 EUR\n");
+
+            let actual = parse_journal(&content, Path::new("tests"));
+
+            assert!(matches!(actual, Err(Error::Ledger(_))), "{predicate}: {actual:?}");
+        }
+
+        let supported = "= expr 'account =~ /^Income:.*:IB$/' | /Dividends/ & ! /Interest/\n";
+        assert!(parse_journal(supported, Path::new("tests")).is_ok());
+    }
+
+    /// The directives that rename the accounts are errors, the others are skipped.
+    #[test]
+    fn test_unsupported_directives() {
+        let transaction = "2022-12-15 TRET_AS Distribution\n    Income:IB:TRET_AS  -40 EUR\n    Assets:Cash\n";
+
+        for directive in ["apply account Investment", "alias IB=Income:Investment:IB"] {
+            let content = format!("{directive}\n{transaction}");
+
+            let actual = parse_journal(&content, Path::new("tests"));
+
+            assert!(matches!(actual, Err(Error::Ledger(_))), "{directive}: {actual:?}");
+        }
+
+        let content = format!("apply tag broker\n{transaction}end apply tag\n");
+        let actual = parse_journal(&content, Path::new("tests")).unwrap();
+        assert_eq!(1, actual.len());
+        assert_eq!("Income:IB:TRET_AS", actual[0].postings[0].account);
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(amount("-38.40", "EUR"), parse_amount("-38.40 EUR"));
+        assert_eq!(amount("-38.40", "EUR"), parse_amount("EUR -38.40"));
+        assert_eq!(amount("-10", "$"), parse_amount("$-10"));
+        assert_eq!(amount("-10", "$"), parse_amount("-$10"));
+        assert_eq!(amount("1000.5", "EUR"), parse_amount("1,000.5 EUR"));
+        assert_eq!(amount("15", ""), parse_amount("15"));
+        assert_eq!(None, parse_amount("EUR"));
+    }
+
+    #[test]
+    fn test_parse_note_metadata() {
+        let actual = parse_note_metadata(" ib_tx: 1234\\n; :ib:dividend: \\nPaid:: yes\n matches the report: 1");
+
+        assert_eq!(4, actual.len());
+        assert_eq!("1234", actual["ib_tx"]);
+        assert_eq!("", actual["ib"]);
+        assert_eq!("", actual["dividend"]);
+        assert_eq!("yes", actual["Paid"]);
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("income", "Income:Investment:IB"));
+        assert!(pattern_matches("^income:.*:ib$", "Income:Investment:IB"));
+        assert!(!pattern_matches("^investment", "Income:Investment:IB"));
+        assert!(!pattern_matches("investment$", "Income:Investment:IB"));
+    }
+
+    #[test]
+    fn test_include() {
+        let content = "include journal.ledger\n";
+
+        let actual = parse_journal(content, Path::new("tests")).unwrap();
+
+        assert!(actual.iter().any(|entry| entry.payee == "TRET_AS Distribution"));
+    }
+
+    /// The same postings as from Ledger, in `ledger_runner::test_get_ledger_tx`.
+    #[test]
+    fn test_get_transactions() {
+        let query = JournalQuery {
+            journal_file: Some("tests/journal.ledger".into()),
+            start_date: "2022-01-01".into(),
+            effective_dates: false,
+            account_rules: &AccountRules::default(),
+            types: &[CashAction::Dividend, CashAction::WhTax],
+        };
+
+        let actual = LedgerReaderBackend.get_transactions(&query).unwrap();
+
+        assert_eq!(2, actual.len());
+        assert_eq!("TRET_AS", actual[0].symbol);
+        assert_eq!(CashAction::Dividend, actual[0].r#type);
+        assert_eq!(Decimal::from_str("5.77").unwrap(), actual[1].amount);
+        assert_eq!(
+            read_journal(Path::new("tests/journal.ledger")).unwrap().len(),
+            4
+        );
+    }
+}
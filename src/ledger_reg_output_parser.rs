@@ -4,12 +4,15 @@
 
 use std::str::FromStr;
 
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::NaiveDate;
 use rust_decimal::Decimal;
 
-use crate::{flex_enums::CashAction, model::CommonTransaction, ISO_DATE_FORMAT};
+use crate::{
+    account_types::AccountRules, flex_enums::CashAction, model::CommonTransaction, Error, Result,
+    ISO_DATE_FORMAT,
+};
 
-/**
+/*
  * Ledger Register row.
  */
 // pub struct RegisterRow {}
@@ -28,7 +31,7 @@ pub fn clean_up_register_output(lines: Vec<&str>) -> Vec<String> {
         }
 
         // Check the account line. If empty, skip. This is just the running total.
-        if line.chars().nth(50).unwrap() == ' ' {
+        if line.chars().nth(50) == Some(' ') {
             continue;
         }
 
@@ -40,39 +43,49 @@ pub fn clean_up_register_output(lines: Vec<&str>) -> Vec<String> {
 
 /**
  * Parse raw lines from the ledger register output and get RegisterRow.
+ * The account rules give the transaction type of each posting.
  */
-pub fn get_rows_from_register(ledger_lines: Vec<String>) -> Vec<CommonTransaction> {
+pub fn get_rows_from_register(
+    ledger_lines: Vec<String>,
+    account_rules: &AccountRules,
+) -> Result<Vec<CommonTransaction>> {
     let mut txs: Vec<CommonTransaction> = vec![];
     // remember the transaction row, with the medatada: date, symbol...
     let empty_tx = CommonTransaction::default();
     let mut prev_row = &empty_tx;
 
     for line in ledger_lines {
-        let tx = get_row_from_register_line(&line, prev_row);
+        let tx = get_row_from_register_line(&line, prev_row, account_rules)?;
 
         txs.push(tx);
 
         prev_row = txs.last().unwrap();
     }
-    txs
+    Ok(txs)
 }
 
 /// Parse one register line into a Transaction object
-fn get_row_from_register_line(line: &str, header: &CommonTransaction) -> CommonTransaction {
+fn get_row_from_register_line(
+    line: &str,
+    header: &CommonTransaction,
+    account_rules: &AccountRules,
+) -> Result<CommonTransaction> {
     // header is the transaction with the date (and other metadata?)
 
     log::debug!("parsing: {:?}", line);
 
-    if line.is_empty() {
-        panic!("The lines must be prepared by `clean_up_register_output`");
-    }
+    let column = |start: usize, end: usize| {
+        line.get(start..end)
+            .map(str::trim)
+            .ok_or_else(|| Error::Ledger(format!("Unexpected register line: {line:?}")))
+    };
 
-    let has_symbol = line.chars().nth(1).unwrap() != ' ';
+    let has_symbol = line.chars().nth(1).is_some_and(|c| c != ' ');
 
-    let date_str = &line[0..10].trim();
-    let payee_str = &line[11..46].trim();
-    let account_str = &line[46..85].trim();
-    let amount_str = &line[85..107].trim();
+    let date_str = column(0, 10)?;
+    let payee_str = column(11, 46)?;
+    let account_str = column(46, 85)?;
+    let amount_str = column(85, 107)?;
 
     let mut tx = CommonTransaction::default();
 
@@ -83,9 +96,9 @@ fn get_row_from_register_line(line: &str, header: &CommonTransaction) -> CommonT
         // parse
         // log::debug!("parsing date: {:?}", date_str);
 
-        let tx_date =
-            NaiveDate::parse_from_str(date_str, ISO_DATE_FORMAT).expect("valid date expected");
-        NaiveDateTime::from(tx_date.and_hms_opt(0, 0, 0).unwrap())
+        let tx_date = NaiveDate::parse_from_str(date_str, ISO_DATE_FORMAT)
+            .map_err(|_| Error::InvalidDate(date_str.to_owned()))?;
+        tx_date.and_hms_opt(0, 0, 0).unwrap()
     };
 
     // Report Date
@@ -112,16 +125,13 @@ fn get_row_from_register_line(line: &str, header: &CommonTransaction) -> CommonT
     };
 
     // Type
-    // Get just the first 2 characters.
-    let account = &account_str[0..2];
-    tx.r#type = if account == "In" {
-        CashAction::Dividend.to_string()
-    } else if account == "Ex" {
-        CashAction::WhTax.to_string()
-    } else {
-        log::warn!("Could not parse type {:?}", account);
+    tx.r#type = match account_rules.get_type(account_str) {
+        Some(tx_type) => tx_type,
+        None => {
+            log::warn!("Could not parse type {:?}", account_str);
 
-        "Error!".to_string()
+            CashAction::Other("Error!".to_string())
+        }
     };
 
     // Account
@@ -130,18 +140,18 @@ fn get_row_from_register_line(line: &str, header: &CommonTransaction) -> CommonT
     // Amount
     // Get from the end.
     let parts: Vec<&str> = amount_str.split_whitespace().collect();
-    if parts.len() > 2 {
-        println!("cannot parse: {:?}", tx);
+    if parts.len() != 2 {
+        log::debug!("cannot parse: {:?}", tx);
+        return Err(Error::InvalidAmount(amount_str.to_owned()));
     }
-    assert!(parts.len() == 2, "Invalid amount in ledger: {:?}", amount_str);
 
     let amount = parts[0].replace(",", "");
-    tx.amount = Decimal::from_str(&amount).expect("a valid number");
+    tx.amount = Decimal::from_str(&amount).map_err(|_| Error::InvalidAmount(amount_str.to_owned()))?;
 
     // Currency
     tx.currency = parts[1].to_string();
 
-    tx
+    Ok(tx)
 }
 
 // tests
@@ -153,6 +163,8 @@ mod tests {
     use rust_decimal::Decimal;
 
     use crate::{
+        account_types::AccountRules,
+        flex_enums::CashAction,
         ledger_reg_output_parser::{clean_up_register_output, get_rows_from_register},
         model::CommonTransaction,
     };
@@ -169,7 +181,7 @@ mod tests {
 
         let header = CommonTransaction::default();
 
-        let actual = get_row_from_register_line(line, &header);
+        let actual = get_row_from_register_line(line, &header, &AccountRules::default()).unwrap();
 
         log::debug!("actual: {:?}", actual);
 
@@ -188,7 +200,7 @@ mod tests {
         // Symbol
         //assert!(!actual.symbol.is_empty());
         // Type
-        assert!(!actual.r#type.is_empty());
+        assert_ne!(CashAction::default(), actual.r#type);
         // Amount
         assert!(!actual.amount.is_zero());
         assert_eq!(actual.amount, Decimal::from(15));
@@ -205,7 +217,7 @@ mod tests {
         log::debug!("lines: {:?}", lines);
 
         let clean_lines = clean_up_register_output(lines);
-        let rows = get_rows_from_register(clean_lines);
+        let rows = get_rows_from_register(clean_lines, &AccountRules::default()).unwrap();
 
         log::debug!("rows: {:?}", rows);
 
@@ -219,6 +231,7 @@ mod tests {
         // 2nd row
 
         assert_eq!(Decimal::from_str_exact("5.77").unwrap(), rows[1].amount);
+        assert_eq!(CashAction::WhTax, rows[1].r#type);
 
         // todo: assert other fields
     }
@@ -234,7 +247,7 @@ mod tests {
             .and_hms_opt(0, 0, 0)
             .unwrap();
         let header = CommonTransaction {
-            date: date,
+            date,
             report_date: String::default(), // The report date comes from the Flex report.
             payee: "Supermarket".to_string(),
             account: "Expenses:Food".to_string(),
@@ -242,12 +255,13 @@ mod tests {
             currency: "EUR".to_string(),
             description: String::default(),
             symbol: String::default(),
-            r#type: String::default(),
+            r#type: CashAction::default(),
+            ..Default::default()
         };
 
         let line = r#"                                              Assets:Bank:Checking                              -15.00 EUR                    0"#;
 
-        let actual = get_row_from_register_line(line, &header);
+        let actual = get_row_from_register_line(line, &header, &AccountRules::default()).unwrap();
 
         // Date
         assert_eq!(actual.date.year(), 2022);
@@ -260,7 +274,7 @@ mod tests {
         assert!(!actual.account.is_empty());
         assert_eq!(actual.account, "Assets:Bank:Checking");
         // Type
-        assert!(!actual.r#type.is_empty());
+        assert_ne!(CashAction::default(), actual.r#type);
         // Amount
         assert!(!actual.amount.is_zero());
         assert_eq!(actual.amount, Decimal::from(-15));
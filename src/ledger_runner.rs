@@ -7,78 +7,108 @@ use std::process::{Command, Output};
 use chrono::{Days, Local, NaiveDate};
 
 use crate::{
-    compare::TRANSACTION_DAYS, ledger_print_output_parser, ledger_reg_output_parser,
-    model::CommonTransaction, ISO_DATE_FORMAT,
+    account_types::AccountRules, compare::TRANSACTION_DAYS, flex_enums::CashAction, ledger_csv_output_parser, ledger_reg_output_parser,
+    model::CommonTransaction, Error, Result, ISO_DATE_FORMAT,
 };
 
+/**
+ * The format of the `csv` report: the default one, with the posting note and
+ * the transaction note in separate columns. The default note column has the
+ * transaction note only for the postings without their own note.
+ */
+const CSV_FORMAT: &str = r#"%(quoted(date)),%(quoted(code)),%(quoted(payee)),%(quoted(display_account)),%(quoted(commodity(scrub(display_amount)))),%(quoted(quantity(scrub(display_amount)))),%(quoted(cleared ? "*" : (pending ? "!" : ""))),%(quoted(join(note))),%(quoted(join(xact.note)))\n"#;
+
+/// The Ledger reports that can be parsed into the transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LedgerReport {
+    /// `ledger csv`, one record per posting.
+    Csv,
+    /// `ledger register`, with the fixed-width columns. The fallback.
+    Register,
+}
+
+impl LedgerReport {
+    fn command(&self) -> &str {
+        match self {
+            LedgerReport::Csv => "csv",
+            LedgerReport::Register => "r",
+        }
+    }
+
+    fn parse(&self, output: &str, account_rules: &AccountRules) -> Result<Vec<CommonTransaction>> {
+        match self {
+            LedgerReport::Csv => ledger_csv_output_parser::parse_csv_output(output, account_rules),
+            LedgerReport::Register => {
+                let lines: Vec<&str> = output.lines().collect();
+                let clean_lines = ledger_reg_output_parser::clean_up_register_output(lines);
+                ledger_reg_output_parser::get_rows_from_register(clean_lines, account_rules)
+            }
+        }
+    }
+}
+
 /// Get ledger transactions
 /// Ledger must be callable from the current directory.
+/// The accounts of the rules for the given types are selected, and all the
+/// account rules give the transaction types, so the more specific rules of the
+/// other types still apply. The caller drops the postings of the other types.
+/// Uses the `csv` report, and the `register` report if that fails.
 pub fn get_ledger_tx(
     ledger_journal_file: Option<String>,
     start_date: String,
     use_effective_dates: bool,
-) -> Vec<CommonTransaction> {
-    //let date_param = get_ledger_date_param(comparison_date);
-    let date_param = start_date;
-
-    let cmd = get_ledger_cmd(
-        &date_param,
-        ledger_journal_file,
-        use_effective_dates,
-    );
+    account_rules: &AccountRules,
+    types: &[CashAction],
+) -> Result<Vec<CommonTransaction>> {
+    let selected_rules = account_rules.for_types(types);
+    let query = |report: LedgerReport| {
+        let cmd = get_ledger_cmd(
+            report,
+            &start_date,
+            ledger_journal_file.to_owned(),
+            use_effective_dates,
+            &selected_rules,
+        );
+        let output = run_ledger_cmd(&cmd)?;
+        report.parse(&output, account_rules)
+    };
+
+    query(LedgerReport::Csv).or_else(|e| {
+        log::warn!("Could not read the Ledger csv report, using the register: {e}");
+        query(LedgerReport::Register)
+    })
+}
 
+/// Runs the Ledger command and returns the output.
+fn run_ledger_cmd(cmd: &str) -> Result<String> {
     log::debug!("running: {}", cmd);
 
-    let args = shell_words::split(&cmd).expect("valid ledger command");
+    let args = shell_words::split(cmd)
+        .map_err(|e| Error::Ledger(format!("Invalid command {cmd}: {e}")))?;
     let output = Command::new(&args[0])
         .args(&args[1..])
         .output()
-        .expect("ledger command ran");
+        .map_err(|e| Error::Ledger(format!("Could not run {}: {e}", args[0])))?;
 
     if !output.status.success() {
         let err = String::from_utf8_lossy(&output.stderr);
-        panic!("Error running Ledger command: {}", err);
+        return Err(Error::Ledger(format!("Error running Ledger command: {}", err.trim())));
     }
-    let out = String::from_utf8(output.stdout).expect("utf8 stdout");
-
-    // log::debug!("ledger output: {:?}", out);
-
-    let lines: Vec<&str> = out.lines().collect();
-
-    let parser = 0;
-    let txs = match parser {
-        0 => {
-            // Register parsing
-            // cleanup
-            let clean_lines = ledger_reg_output_parser::clean_up_register_output(lines);
-            // Parse output.
-            ledger_reg_output_parser::get_rows_from_register(clean_lines)
-        }
-        1 => {
-            // Print parsing
-            ledger_print_output_parser::parse_print_output(lines)
-        }
-        _ => {
-            panic!("invalid value!");
-        }
-    };
-
-    txs
+    String::from_utf8(output.stdout).map_err(|e| Error::Ledger(format!("Invalid output: {e}")))
 }
 
 /// Determines the starting date from which to take Ledger transactions.
 /// This is one month from the comparison date.
-pub fn get_ledger_start_date(comparison_date: Option<String>) -> String {
+pub fn get_ledger_start_date(comparison_date: Option<String>) -> Result<String> {
     let end_date = match &comparison_date {
-        Some(date_value) => {
-            NaiveDate::parse_from_str(&date_value, ISO_DATE_FORMAT).expect("correct date")
-        }
+        Some(date_value) => NaiveDate::parse_from_str(date_value, ISO_DATE_FORMAT)
+            .map_err(|_| Error::InvalidDate(date_value.to_owned()))?,
         None => Local::now().date_naive(),
     };
 
     let start_date = end_date
         .checked_sub_days(Days::new(TRANSACTION_DAYS.into()))
-        .expect("calculated start date");
+        .ok_or_else(|| Error::InvalidDate(end_date.to_string()))?;
     let date_param = start_date.format(ISO_DATE_FORMAT).to_string();
 
     log::debug!(
@@ -87,7 +117,7 @@ pub fn get_ledger_start_date(comparison_date: Option<String>) -> String {
         date_param
     );
 
-    date_param
+    Ok(date_param)
 }
 
 fn run_ledger_args(args: Vec<String>) -> Output {
@@ -103,14 +133,15 @@ fn run_ledger_args(args: Vec<String>) -> Output {
 
 /// Assemble the Ledger query command.
 fn get_ledger_cmd(
+    report: LedgerReport,
     start_date: &str,
     ledger_journal_file: Option<String>,
     effective_dates: bool,
+    account_rules: &AccountRules,
 ) -> String {
-    let mut cmd = format!("ledger r -b {start_date} -d");
+    let mut cmd = format!("ledger {} -b {start_date} -d", report.command());
 
-    cmd.push_str(r#" "(account =~ /income/ and account =~ /ib/) or"#);
-    cmd.push_str(r#" (account =~ /expenses/ and account =~ /ib/ and account =~ /withh/)""#);
+    cmd.push_str(&format!(r#" "{}""#, account_rules.to_query()));
 
     if effective_dates {
         cmd.push_str(" --effective");
@@ -123,8 +154,11 @@ fn get_ledger_cmd(
 
     // Ensure ISO date format, for parsing.
     cmd.push_str(" --date-format %Y-%m-%d");
-    // use wide display
-    cmd.push_str(" --wide");
+    match report {
+        LedgerReport::Csv => cmd.push_str(&format!(" --csv-format='{CSV_FORMAT}'")),
+        // use wide display
+        LedgerReport::Register => cmd.push_str(" --wide"),
+    }
 
     cmd
 }
@@ -153,13 +187,14 @@ fn run_ledger(args: Vec<String>) -> Vec<String> {
     result
 }
 
-////
+//
 // Tests
-////
+//
 
 #[cfg(test)]
 mod tests {
-    use super::get_ledger_tx;
+    use super::{get_ledger_cmd, get_ledger_tx, LedgerReport};
+    use crate::{account_types::AccountRules, flex_enums::CashAction};
     use super::run_ledger;
     use crate::test_fixtures::*;
     use std::process::Command;
@@ -173,7 +208,6 @@ mod tests {
 
         let args = cmd
             .split_whitespace()
-            .into_iter()
             .map(|item| item.to_owned())
             .collect();
         let actual = run_ledger(args);
@@ -192,7 +226,8 @@ mod tests {
         let path_opt = Some(ledger_journal_path);
         //let start_date = get_ledger_start_date(None);
         let start_date = "2022-01-01".to_owned();
-        let actual = get_ledger_tx(path_opt, start_date, false);
+        let types = [CashAction::Dividend, CashAction::WhTax];
+        let actual = get_ledger_tx(path_opt, start_date, false, &AccountRules::default(), &types).unwrap();
 
         println!("txs: {:?}", actual);
 
@@ -200,6 +235,25 @@ mod tests {
         assert_eq!(2, actual.len());
     }
 
+    /// The csv report is not affected by the terminal width.
+    #[test]
+    fn test_csv_cmd() {
+        let actual = get_ledger_cmd(
+            LedgerReport::Csv,
+            "2022-03-01",
+            Some("tests/journal.ledger".into()),
+            true,
+            &AccountRules::default(),
+        );
+
+        assert!(actual.starts_with("ledger csv -b 2022-03-01 -d "));
+        assert!(actual.contains(" --effective -f tests/journal.ledger"));
+        assert!(!actual.contains("--wide"));
+
+        let args = shell_words::split(&actual).unwrap();
+        assert!(args.last().unwrap().ends_with(r#"%(quoted(join(xact.note)))\n"#));
+    }
+
     /// Run the complex query on Ledger, using shell-words.
     #[test_log::test]
     fn test_ledger_words() {
@@ -0,0 +1,277 @@
+/*!
+ * Generates Ledger journal entries from the new IB transactions.
+ *
+ * A dividend and its withholding tax, booked on the same dates for the same symbol,
+ * end up in one Ledger transaction. The account names are templates so that the
+ * generated entries follow the layout of the existing book.
+ * The entries are tagged with the ISIN and the IB transaction ids, which the
+ * comparison matches first.
+ */
+
+use std::fmt::Write;
+
+use rust_decimal::Decimal;
+
+use crate::{
+    flex_enums::CashAction,
+    model::{CommonTransaction, ISIN_TAG, TRANSACTION_ID_TAG},
+    ISO_DATE_FORMAT,
+};
+
+pub const DEFAULT_INCOME_ACCOUNT: &str = "Income:Investment:IB:{symbol}";
+pub const DEFAULT_WITHHOLDING_ACCOUNT: &str = "Expenses:Investment:IB:Withholding Tax";
+pub const DEFAULT_CASH_ACCOUNT: &str = "Assets:Investment:IB:Cash";
+
+/// Width of the account column in the generated postings.
+const ACCOUNT_WIDTH: usize = 44;
+/// Width of the amount column in the generated postings.
+const AMOUNT_WIDTH: usize = 12;
+/// The types with the account templates. No entries are generated for the other types.
+const WRITTEN_TYPES: [CashAction; 2] = [CashAction::Dividend, CashAction::WhTax];
+/// The indentation of the postings.
+const INDENT: &str = "    ";
+
+/**
+ * Account name templates for the generated entries.
+ * The placeholders `{symbol}` and `{currency}` are replaced with the values
+ * from the transaction.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerAccounts {
+    pub income: String,
+    pub withholding_tax: String,
+    pub cash: String,
+}
+
+impl Default for LedgerAccounts {
+    fn default() -> Self {
+        Self {
+            income: DEFAULT_INCOME_ACCOUNT.to_owned(),
+            withholding_tax: DEFAULT_WITHHOLDING_ACCOUNT.to_owned(),
+            cash: DEFAULT_CASH_ACCOUNT.to_owned(),
+        }
+    }
+}
+
+/// The IB records that end up in one Ledger transaction.
+pub(crate) struct EntryGroup<'a> {
+    pub report_date: String,
+    pub date: String,
+    pub symbol: String,
+    pub currency: String,
+    pub txs: Vec<&'a CommonTransaction>,
+}
+
+impl EntryGroup<'_> {
+    fn accepts(&self, tx: &CommonTransaction) -> bool {
+        self.report_date == tx.report_date
+            && self.date == tx.date.format(ISO_DATE_FORMAT).to_string()
+            && self.symbol == tx.symbol
+            && self.currency == tx.currency
+    }
+
+    pub fn has_dividend(&self) -> bool {
+        self.txs
+            .iter()
+            .any(|tx| tx.r#type == CashAction::Dividend)
+    }
+
+    /// The ISIN of the security, if known.
+    pub fn isin(&self) -> Option<&str> {
+        self.txs
+            .iter()
+            .map(|tx| tx.isin.as_str())
+            .find(|isin| !isin.is_empty())
+    }
+
+    /// The account template for the IB transaction.
+    fn account_template<'t>(tx: &CommonTransaction, accounts: &'t LedgerAccounts) -> &'t str {
+        if tx.r#type == CashAction::WhTax {
+            &accounts.withholding_tax
+        } else {
+            &accounts.income
+        }
+    }
+
+    /// The account and the amount of the posting for the IB transaction.
+    /// The books record the IB amounts with the opposite sign.
+    pub fn posting(&self, tx: &CommonTransaction, accounts: &LedgerAccounts) -> (String, Decimal) {
+        let template = EntryGroup::account_template(tx, accounts);
+        let account = expand_template(template, &self.symbol, &self.currency);
+        (account, tx.amount * Decimal::NEGATIVE_ONE)
+    }
+
+    /// The account and the amount of the cash posting, which balances the transaction.
+    pub fn cash_posting(&self, accounts: &LedgerAccounts) -> (String, Decimal) {
+        let account = expand_template(&accounts.cash, &self.symbol, &self.currency);
+        (account, self.txs.iter().map(|tx| tx.amount).sum())
+    }
+}
+
+/// Groups the IB transactions into the journal transactions, keeping the order.
+/// The transactions of the types without the account templates are skipped.
+pub(crate) fn group_transactions<'a>(
+    txs: impl IntoIterator<Item = &'a CommonTransaction>,
+) -> Vec<EntryGroup<'a>> {
+    let mut groups: Vec<EntryGroup> = vec![];
+
+    for tx in txs {
+        if !WRITTEN_TYPES.contains(&tx.r#type) {
+            log::warn!("No entry is generated for the {} transaction: {}", tx.r#type, tx);
+            continue;
+        }
+        match groups.iter_mut().find(|group| group.accepts(tx)) {
+            Some(group) => group.txs.push(tx),
+            None => groups.push(EntryGroup {
+                report_date: tx.report_date.to_owned(),
+                date: tx.date.format(ISO_DATE_FORMAT).to_string(),
+                symbol: tx.symbol.to_owned(),
+                currency: tx.currency.to_owned(),
+                txs: vec![tx],
+            }),
+        }
+    }
+
+    groups
+}
+
+/**
+ * Generates the Ledger transactions for the given (new) IB transactions.
+ * The transactions are expected in the order in which they should be written.
+ */
+pub fn write_transactions<'a>(
+    txs: impl IntoIterator<Item = &'a CommonTransaction>,
+    accounts: &LedgerAccounts,
+) -> String {
+    group_transactions(txs)
+        .iter()
+        .map(|group| write_transaction(group, accounts))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Writes one Ledger transaction, with the effective date and the balancing cash posting.
+fn write_transaction(group: &EntryGroup, accounts: &LedgerAccounts) -> String {
+    let payee = if group.has_dividend() {
+        format!("{} Distribution", group.symbol)
+    } else {
+        format!("{} Tax Adjustment", group.symbol)
+    };
+
+    let mut output = String::default();
+    writeln!(output, "{}={} {}", group.report_date, group.date, payee).unwrap();
+    if let Some(isin) = group.isin() {
+        writeln!(output, "    ; {ISIN_TAG}: {isin}").unwrap();
+    }
+
+    for tx in &group.txs {
+        writeln!(output, "    ; {}", tx.description).unwrap();
+
+        let (account, amount) = group.posting(tx, accounts);
+        write_posting(&mut output, INDENT, &account, amount, &group.currency);
+        if !tx.transaction_id.is_empty() {
+            writeln!(output, "    ; {TRANSACTION_ID_TAG}: {}", tx.transaction_id).unwrap();
+        }
+    }
+
+    let (cash_account, cash) = group.cash_posting(accounts);
+    write_posting(&mut output, INDENT, &cash_account, cash, &group.currency);
+
+    output
+}
+
+/// Writes the posting line, with the aligned amount.
+pub(crate) fn write_posting(
+    output: &mut String,
+    indent: &str,
+    account: &str,
+    amount: Decimal,
+    currency: &str,
+) {
+    writeln!(
+        output,
+        "{indent}{:ACCOUNT_WIDTH$} {:>AMOUNT_WIDTH$} {}",
+        account, amount, currency
+    )
+    .unwrap();
+}
+
+/// Replaces the placeholders in the account name template.
+pub(crate) fn expand_template(template: &str, symbol: &str, currency: &str) -> String {
+    template
+        .replace("{symbol}", symbol)
+        .replace("{currency}", currency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_transactions, LedgerAccounts};
+    use crate::{flex_enums::CashAction, model::CommonTransaction, test_fixtures::*};
+
+    /// The dividend and the withholding tax are grouped into one transaction.
+    #[rstest::rstest]
+    fn test_dividend_with_tax(dividend_with_tax: Vec<CommonTransaction>) {
+        let actual = write_transactions(&dividend_with_tax, &LedgerAccounts::default());
+
+        let expected = r#"2022-12-14=2022-12-15 TCBT_AS Distribution
+    ; TCBT CASH DIVIDEND
+    Income:Investment:IB:TCBT_AS                        -6.05 EUR
+    ; TCBT CASH DIVIDEND - NL TAX
+    Expenses:Investment:IB:Withholding Tax               0.91 EUR
+    Assets:Investment:IB:Cash                            5.14 EUR
+"#;
+        assert_eq!(expected, actual);
+    }
+
+    /// The ISIN and the transaction ids are written as the metadata.
+    #[rstest::rstest]
+    fn test_tags(tagged_dividend_with_tax: Vec<CommonTransaction>) {
+        let actual = write_transactions(&tagged_dividend_with_tax, &LedgerAccounts::default());
+
+        let expected = r#"2022-12-14=2022-12-15 TCBT_AS Distribution
+    ; isin: NL0009690247
+    ; TCBT CASH DIVIDEND
+    Income:Investment:IB:TCBT_AS                        -6.05 EUR
+    ; ib_tx: 1001
+    ; TCBT CASH DIVIDEND - NL TAX
+    Expenses:Investment:IB:Withholding Tax               0.91 EUR
+    ; ib_tx: 1002
+    Assets:Investment:IB:Cash                            5.14 EUR
+"#;
+        assert_eq!(expected, actual);
+    }
+
+    /// The account names come from the templates.
+    #[test]
+    fn test_account_templates() {
+        let txs = vec![new_tx(CashAction::WhTax, "0.66", "TAX REFUND")];
+        let accounts = LedgerAccounts {
+            income: "Income:Dividends:{symbol}".into(),
+            withholding_tax: "Expenses:Tax:{currency}".into(),
+            cash: "Assets:Broker:{currency}".into(),
+        };
+
+        let actual = write_transactions(&txs, &accounts);
+
+        assert!(actual.starts_with("2022-12-14=2022-12-15 TCBT_AS Tax Adjustment\n"));
+        assert!(actual.contains("    Expenses:Tax:EUR "));
+        assert!(actual.contains("    Assets:Broker:EUR "));
+        assert!(!actual.contains("Income:"));
+    }
+
+    /// No entry is generated for the types without the account templates.
+    #[test]
+    fn test_skip_deposit() {
+        let deposit = CommonTransaction {
+            symbol: String::default(),
+            ..new_tx(CashAction::DepositWithdraw, "1500", "CASH RECEIPTS")
+        };
+        let txs = vec![deposit.clone(), new_tx(CashAction::Dividend, "6.05", "TCBT CASH DIVIDEND")];
+
+        let actual = write_transactions(&txs, &LedgerAccounts::default());
+
+        assert!(actual.starts_with("2022-12-14=2022-12-15 TCBT_AS Distribution\n"));
+        assert!(!actual.contains("CASH RECEIPTS"));
+        assert!(write_transactions(&[deposit], &LedgerAccounts::default()).is_empty());
+    }
+}
@@ -4,17 +4,34 @@
  * Project documentation: <https://github.com/alensiljak/interactive-brokers-flex-rs>
  */
 
+pub mod account_types;
+pub mod beancount_reader;
+pub mod beancount_writer;
 pub mod compare;
+pub mod config;
 pub mod download;
+pub mod error;
+pub mod flex_enums;
 pub mod flex_query;
 pub mod flex_reader;
 pub mod flex_statement;
+pub mod hledger_runner;
+pub mod inspect;
+pub mod journal_backend;
+pub mod ledger_csv_output_parser;
 pub mod ledger_print_output_parser;
+pub mod ledger_reader;
 pub mod ledger_reg_output_parser;
 pub mod ledger_runner;
+pub mod ledger_writer;
 pub mod model;
-mod flex_enums;
+pub mod report_filename;
+pub mod report_writer;
+mod flex_serde;
+mod journal_reader;
 #[cfg(test)]
 mod test_fixtures;
 
+pub use error::{Error, Result};
+
 pub const ISO_DATE_FORMAT: &str ="%Y-%m-%d";
@@ -2,9 +2,18 @@
  * CLI for ibflex library
  */
 
+use std::time::Duration;
+
 use clap::Parser;
-use cli::{Cli, Commands};
-use ibflex::{compare::CompareParams, download::DownloadParams};
+use cli::{Cli, Commands, DlParams};
+use ibflex::{
+    account_types::AccountRules, beancount_writer, compare::CompareParams, config::Config,
+    download::DownloadParams, flex_reader::get_report_path, inspect, journal_backend::Backend,
+    ledger_writer::LedgerAccounts,
+    report_filename,
+    report_writer::{self, OutputFormat},
+    Error,
+};
 
 /*
  * CLI for operating the library
@@ -16,25 +25,141 @@ fn main() {
 
     let cli = Cli::parse();
 
+    // The command-line arguments take precedence over the configuration.
+    let config = Config::load().unwrap_or_else(|e| exit_with_error(e));
+
     match &cli.command {
         Commands::Dl(params) => {
-            println!("downloading report...");
+            let profiles: Vec<Option<String>> = if params.all {
+                if config.profiles.is_empty() {
+                    exit_with_error(Error::Config("No profiles configured.".into()));
+                }
+                config.profiles.keys().cloned().map(Some).collect()
+            } else {
+                vec![params.profile.to_owned()]
+            };
+            // The reports of several profiles need different names.
+            let filename_template =
+                params.filename_template.as_deref().or(config.filename_template.as_deref());
+            if let Some(template) = filename_template
+                .filter(|template| profiles.len() > 1 && !template.contains("{profile}"))
+            {
+                exit_with_error(Error::Config(format!(
+                    "The filename template {template:?} must contain {{profile}} to download several profiles."
+                )));
+            }
 
-            let dl_params = DownloadParams::new(params.query_id, &params.token);
-            let path = ibflex::download::download(dl_params);
+            let mut exit_code = 0;
+            for profile in profiles {
+                match &profile {
+                    Some(name) => println!("downloading report {name}..."),
+                    None => println!("downloading report..."),
+                }
 
-            println!("Flex Query saved to {path}");
+                let result = get_dl_params(params, &config, profile)
+                    .and_then(ibflex::download::download);
+                match result {
+                    Ok(path) => println!("Flex Query saved to {path}"),
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        exit_code = get_exit_code(&e);
+                    }
+                }
+            }
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
         }
 
         Commands::Cmp(params) => {
+            let mut ledger_accounts = match params.backend {
+                Backend::Beancount => beancount_writer::default_accounts(),
+                _ => LedgerAccounts::default(),
+            };
+            if let Some(account) = &params.income_account {
+                ledger_accounts.income = account.to_owned();
+            }
+            if let Some(account) = &params.tax_account {
+                ledger_accounts.withholding_tax = account.to_owned();
+            }
+            if let Some(account) = &params.cash_account {
+                ledger_accounts.cash = account.to_owned();
+            }
+
+            // The report location given as an argument replaces both configured ones.
+            let (flex_report_path, flex_reports_dir) =
+                if params.flex_report_path.is_some() || params.flex_reports_dir.is_some() {
+                    (params.flex_report_path.to_owned(), params.flex_reports_dir.to_owned())
+                } else {
+                    (config.flex_report_path, config.flex_reports_dir)
+                };
+            let filename_template = report_filename::get_lookup_template(
+                params.filename_template.as_deref().or(config.filename_template.as_deref()),
+                params.profile.as_deref(),
+            );
+            let account_rules = if config.account_types.is_empty() {
+                AccountRules::default()
+            } else {
+                AccountRules::new(config.account_types).unwrap_or_else(|e| exit_with_error(e))
+            };
+            let Some(symbols_path) = params.symbols_path.to_owned().or(config.symbols_path)
+            else {
+                exit_with_error(Error::Config(format!(
+                    "The symbols path is mandatory. Use --symbols-path, {}, or {}.",
+                    ibflex::config::ENV_SYMBOLS_PATH,
+                    ibflex::config::CONFIG_FILENAME
+                )));
+            };
+
             let cmp_params = CompareParams {
-                flex_report_path: params.flex_report_path.to_owned(),
-                flex_reports_dir: params.flex_reports_dir.to_owned(),
-                ledger_journal_file: params.ledger_journal_file.to_owned(),
-                symbols_path: params.symbols_path.to_owned(),
+                flex_report_path,
+                flex_reports_dir,
+                filename_template: Some(filename_template),
+                ledger_journal_file: params
+                    .ledger_journal_file
+                    .to_owned()
+                    .or(config.ledger_journal_file),
+                symbols_path,
                 effective_dates: params.effective,
+                bidirectional: params.bidirectional,
+                date_tolerance: params.date_tolerance,
+                included_types: params.include.to_owned(),
+                account_rules,
+                backend: params.backend,
             };
-            ibflex::compare::compare(cmp_params).expect("transactions compared");
+            let report =
+                ibflex::compare::compare(cmp_params).unwrap_or_else(|e| exit_with_error(e));
+
+            let output = report_writer::write_report(&report, params.format)
+                .unwrap_or_else(|e| exit_with_error(e));
+            print!("{output}");
+            if params.format == OutputFormat::Text && params.ledger_entries && !report.new.is_empty() {
+                println!();
+                let entries = match params.backend {
+                    Backend::Beancount => report.beancount_entries(&ledger_accounts),
+                    _ => report.ledger_entries(&ledger_accounts),
+                };
+                print!("{entries}");
+            }
+        }
+
+        Commands::Inspect(params) => {
+            let (flex_report_path, flex_reports_dir) =
+                if params.flex_report_path.is_some() || params.flex_reports_dir.is_some() {
+                    (params.flex_report_path.to_owned(), params.flex_reports_dir.to_owned())
+                } else {
+                    (config.flex_report_path, config.flex_reports_dir)
+                };
+            let filename_template = report_filename::get_lookup_template(
+                params.filename_template.as_deref().or(config.filename_template.as_deref()),
+                params.profile.as_deref(),
+            );
+            let report_path = get_report_path(flex_report_path, flex_reports_dir, Some(&filename_template))
+                .unwrap_or_else(|e| exit_with_error(e));
+            println!("Using {report_path}");
+
+            let unmodelled = inspect::inspect_report(&report_path).unwrap_or_else(|e| exit_with_error(e));
+            print!("{unmodelled}");
         }
 
         // Commands::Setup => {
@@ -43,3 +168,70 @@ fn main() {
         // }
     }
 }
+
+/// Assembles the download parameters for the given profile.
+/// The arguments take precedence over the profile, which takes precedence over
+/// the main configuration.
+fn get_dl_params(
+    params: &DlParams,
+    config: &Config,
+    profile: Option<String>,
+) -> ibflex::Result<DownloadParams> {
+    let (query_id, token) = match &profile {
+        Some(name) => {
+            let query = config.profile(name)?;
+            (
+                params.query_id.or(query.flex_query_id),
+                params
+                    .token
+                    .to_owned()
+                    .or(query.ib_token.to_owned())
+                    .or(config.ib_token.to_owned()),
+            )
+        }
+        None => (
+            params.query_id.or(config.flex_query_id),
+            params.token.to_owned().or(config.ib_token.to_owned()),
+        ),
+    };
+
+    let mut dl_params = DownloadParams::new(query_id, &token);
+    if let Some(url) = &config.flex_url {
+        dl_params.base_url = url.to_owned();
+    }
+    dl_params.profile = profile;
+    dl_params.output_dir = params.output_dir.to_owned().or(config.flex_reports_dir.to_owned());
+    dl_params.filename_template = params
+        .filename_template
+        .to_owned()
+        .or(config.filename_template.to_owned());
+    if let Some(secs) = params.retry_delay {
+        dl_params.retry.initial_delay = Duration::from_secs(secs);
+    }
+    if let Some(secs) = params.max_retry_delay {
+        dl_params.retry.max_delay = Duration::from_secs(secs);
+    }
+    if let Some(secs) = params.timeout {
+        dl_params.retry.timeout = Duration::from_secs(secs);
+    }
+
+    Ok(dl_params)
+}
+
+fn exit_with_error(error: Error) -> ! {
+    eprintln!("Error: {error}");
+    std::process::exit(get_exit_code(&error));
+}
+
+/// The process exit code for the error. 2 is also used by clap for invalid arguments.
+fn get_exit_code(error: &Error) -> i32 {
+    match error {
+        Error::Output(_) => 1,
+        Error::Config(_) => 2,
+        Error::Io { .. } | Error::ReportNotFound(_) => 3,
+        Error::Xml(_) | Error::InvalidDate(_) | Error::InvalidAmount(_) => 4,
+        Error::Symbols(_) => 5,
+        Error::Ledger(_) => 6,
+        Error::Http(_) | Error::FlexService(_) | Error::StatementTimeout { .. } => 7,
+    }
+}
@@ -2,12 +2,12 @@
  * The domain model.
  */
 
-use std::{fmt::Display, str::FromStr};
+use std::fmt::Display;
 
-use chrono::{NaiveDateTime, NaiveDate};
+use chrono::{NaiveDateTime, NaiveTime};
 use rust_decimal::Decimal;
 
-use crate::{flex_query::CashTransaction, ISO_DATE_FORMAT, flex_enums::{self, CashAction}};
+use crate::{flex_query::CashTransaction, ISO_DATE_FORMAT, flex_enums::CashAction, Error};
 
 /**
  * The ledger transaction record.
@@ -15,7 +15,7 @@ use crate::{flex_query::CashTransaction, ISO_DATE_FORMAT, flex_enums::{self, Cas
  * This transaction has only one posting. The posting is merged together with the
  * transaction details because the Flex report has individual lines only.
  */
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct CommonTransaction {
     pub date: NaiveDateTime,
     pub report_date: String,
@@ -25,14 +25,44 @@ pub struct CommonTransaction {
     pub amount: Decimal,
     pub currency: String,
     pub symbol: String, // required for IB Cash Transactions
-    pub r#type: String,
+    pub r#type: CashAction,
     pub description: String,
+    /// The IB symbol with the exchange, before it is mapped to the Ledger symbol.
+    pub ib_symbol: String,
+    pub isin: String,
+    pub account_id: String,
+    /// The IB transaction id. In the journal, the `ib_tx` metadata.
+    pub transaction_id: String,
+    /// The IB action id. In the journal, the `ib_action` metadata.
+    pub action_id: String,
+}
+
+/// The journal metadata tag with the IB transaction id.
+pub const TRANSACTION_ID_TAG: &str = "ib_tx";
+/// The journal metadata tag with the IB action id.
+pub const ACTION_ID_TAG: &str = "ib_action";
+/// The journal metadata tag with the security's ISIN.
+pub const ISIN_TAG: &str = "isin";
+
+impl CommonTransaction {
+    /// Sets the field from the journal metadata. The other tags are ignored.
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        let value = value.trim().to_owned();
+        match key.to_lowercase().as_str() {
+            TRANSACTION_ID_TAG => self.transaction_id = value,
+            ACTION_ID_TAG => self.action_id = value,
+            ISIN_TAG => self.isin = value,
+            _ => {}
+        }
+    }
 }
 
 // const ISO_DATE_FMT: &str = "%Y-%m-%d";
 
-impl From<&CashTransaction> for CommonTransaction {
-    fn from(value: &CashTransaction) -> Self {
+impl TryFrom<&CashTransaction> for CommonTransaction {
+    type Error = Error;
+
+    fn try_from(value: &CashTransaction) -> Result<Self, Error> {
         log::debug!("converting ib tx: {:?}", value);
 
         // prepare symbol
@@ -42,43 +72,44 @@ impl From<&CashTransaction> for CommonTransaction {
             symbol = symbol[..symbol.len() - 1].to_string();
         }
 
-        // Add the exchange
-        symbol = format!("{}:{}", value.listing_exchange.as_deref().unwrap_or(""), symbol);
+        // Add the exchange. Deposits, interest, etc. have no symbol.
+        if !symbol.is_empty() {
+            symbol = format!("{}:{}", value.listing_exchange.as_deref().unwrap_or(""), symbol);
+        }
         // TODO: lookup the ledger symbol
 
-        CommonTransaction {
-            date: match value.date_time.len() {
-                10 => {
-                    log::debug!("the date is {}", value.date_time);
-
-                    let tx_date = NaiveDate::parse_from_str(&value.date_time.as_str(), ISO_DATE_FORMAT)
-                    .expect("valid date expected");
-                    NaiveDateTime::from(tx_date.and_hms_opt(0, 0, 0).unwrap())
-                },
-                19 => NaiveDateTime::parse_from_str(&value.date_time.as_str(), "%Y-%m-%d;%H:%M:%S")
-                    .expect("valid date/time expected"),
-                _ => panic!("Invalid date/time"),
-            },
-            report_date: value.report_date.to_owned(),
+        // Either date can stand in for the other.
+        let (date, report_date) = match (value.date_time, value.report_date) {
+            (Some(date_time), Some(report_date)) => (date_time, report_date),
+            (Some(date_time), None) => (date_time, date_time.date()),
+            (None, Some(report_date)) => (report_date.and_time(NaiveTime::MIN), report_date),
+            (None, None) => return Err(Error::InvalidDate(format!("No dateTime or reportDate in {value}"))),
+        };
+
+        Ok(CommonTransaction {
+            date,
+            report_date: report_date.format(ISO_DATE_FORMAT).to_string(),
             payee: String::default(),   // not used
             account: String::default(), // not used
-            amount: Decimal::from_str(value.amount.as_str()).unwrap(),
+            amount: value.amount,
             currency: value.currency.to_owned(),
+            ib_symbol: symbol.to_owned(),
             symbol,
             r#type: get_simplified_tx_type(&value.r#type),
             description: value.description.to_owned(),
-        }
+            isin: value.isin.to_owned(),
+            account_id: value.account_id.to_owned(),
+            transaction_id: value.transaction_id.to_owned(),
+            action_id: value.action_id.to_owned(),
+        })
     }
 }
 
-fn get_simplified_tx_type(ib_tx_type: &String) -> String {
-    let tx_type = flex_enums::cash_action(ib_tx_type.as_str()).to_owned();
-
+fn get_simplified_tx_type(tx_type: &CashAction) -> CashAction {
     // mask in-lieu payments as regular dividends (?!) for comparison
-    if tx_type == CashAction::PaymentInLieu.to_string() {
-        CashAction::Dividend.to_string()
-    } else {
-        tx_type
+    match tx_type {
+        CashAction::PaymentInLieu => CashAction::Dividend,
+        _ => tx_type.to_owned(),
     }
 }
 
@@ -104,7 +135,7 @@ mod tests {
     use rust_decimal::Decimal;
     use std::str::FromStr;
 
-    use crate::{flex_query::CashTransaction, model::CommonTransaction};
+    use crate::{flex_enums::CashAction, flex_query::CashTransaction, model::CommonTransaction, Error};
 
     #[fixture]
     fn cash_transactions() -> Vec<CashTransaction> {
@@ -113,10 +144,66 @@ mod tests {
 
     #[rstest]
     fn conversion_test(cash_transactions: Vec<CashTransaction>) {
-        let t1 = CommonTransaction::from(&cash_transactions[0]);
+        let t1 = CommonTransaction::try_from(&cash_transactions[0]).unwrap();
 
         // assert
         assert_eq!(String::default(), t1.account);
         assert_eq!(Decimal::from_str("-0.91").unwrap(), t1.amount);
+        assert_eq!("AEB:TCBT", t1.ib_symbol);
+        assert_eq!(t1.ib_symbol, t1.symbol);
+        assert_eq!(CashAction::WhTax, t1.r#type);
+    }
+
+    /// The payments in lieu are compared as dividends.
+    #[rstest]
+    fn payment_in_lieu_test(cash_transactions: Vec<CashTransaction>) {
+        let ib_tx = CashTransaction {
+            r#type: CashAction::PaymentInLieu,
+            ..cash_transactions[0].clone()
+        };
+
+        let actual = CommonTransaction::try_from(&ib_tx).unwrap();
+
+        assert_eq!(CashAction::Dividend, actual.r#type);
+    }
+
+    #[test]
+    fn test_set_metadata() {
+        let mut tx = CommonTransaction::default();
+
+        tx.set_metadata("ib_tx", " 1234567890");
+        tx.set_metadata("ISIN", "NL0009690247");
+        tx.set_metadata("note", "ignored");
+
+        assert_eq!("1234567890", tx.transaction_id);
+        assert_eq!("NL0009690247", tx.isin);
+        assert!(tx.action_id.is_empty());
+    }
+
+    #[rstest]
+    fn missing_date_test(cash_transactions: Vec<CashTransaction>) {
+        let ib_tx = CashTransaction {
+            date_time: None,
+            report_date: None,
+            ..cash_transactions[0].clone()
+        };
+
+        let actual = CommonTransaction::try_from(&ib_tx);
+
+        assert!(matches!(actual, Err(Error::InvalidDate(_))));
+    }
+
+    /// The report date is the date when the date/time is missing.
+    #[rstest]
+    fn report_date_only_test(cash_transactions: Vec<CashTransaction>) {
+        let ib_tx = CashTransaction {
+            date_time: None,
+            ..cash_transactions[0].clone()
+        };
+
+        let actual = CommonTransaction::try_from(&ib_tx).unwrap();
+
+        assert_eq!("2022-12-14", actual.report_date);
+        assert_eq!("2022-12-14 00:00:00", actual.date.to_string());
     }
 }
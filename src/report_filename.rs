@@ -0,0 +1,303 @@
+/*!
+ * The filenames of the downloaded Flex reports.
+ *
+ * The same template is used to name the downloaded report and to find the
+ * latest report for comparison. The latest report is the one with the latest
+ * `{date}` (or `{to_date}`) in the filename.
+ * Placeholders: `{date}` (download date), `{from_date}`, `{to_date}`, `{query_name}`,
+ * `{account_id}`, and `{profile}`.
+ */
+
+use chrono::NaiveDate;
+
+use crate::{
+    flex_query::{format_date, FlexQueryResponse},
+    ISO_DATE_FORMAT,
+};
+
+/// The filename used when no template is given.
+pub const DEFAULT_TEMPLATE: &str = "{date}_cash-tx.xml";
+/// The filename used when no template is given, for the query profiles.
+pub const PROFILE_TEMPLATE: &str = "{date}_{account_id}_{profile}.xml";
+
+/// The length of the dates in the filenames, i.e. 2023-03-08.
+const ISO_DATE_LENGTH: usize = 10;
+
+const PLACEHOLDERS: [&str; 6] = [
+    "{date}",
+    "{from_date}",
+    "{to_date}",
+    "{query_name}",
+    "{account_id}",
+    "{profile}",
+];
+
+/**
+ * The values for the filename placeholders.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FilenameValues {
+    pub date: String,
+    pub from_date: String,
+    pub to_date: String,
+    pub query_name: String,
+    pub account_id: String,
+    pub profile: String,
+}
+
+impl FilenameValues {
+    /**
+     * Reads the values from the report contents (xml).
+     * The report period spans all the statements. The account ids of multiple
+     * statements are joined with a dash.
+     */
+    pub fn from_report(report: &str, profile: Option<&str>, date: NaiveDate) -> Self {
+        let mut values = FilenameValues {
+            date: date.format(ISO_DATE_FORMAT).to_string(),
+            profile: profile.unwrap_or_default().to_owned(),
+            ..Default::default()
+        };
+
+        let response = match FlexQueryResponse::from_xml(report) {
+            Ok(response) => response,
+            Err(e) => {
+                log::warn!("Could not read the filename values from the report: {e}");
+                return values;
+            }
+        };
+        let statements = &response.flex_statements.flex_statement;
+
+        values.query_name = response.query_name.to_owned();
+        values.from_date = statements
+            .iter()
+            .filter_map(|stmt| stmt.from_date)
+            .min()
+            .map(|date| format_date(Some(date)))
+            .unwrap_or_default();
+        values.to_date = statements
+            .iter()
+            .filter_map(|stmt| stmt.to_date)
+            .max()
+            .map(|date| format_date(Some(date)))
+            .unwrap_or_default();
+        values.account_id = statements
+            .iter()
+            .map(|stmt| stmt.account_id.to_owned())
+            .filter(|account_id| !account_id.is_empty())
+            .collect::<Vec<String>>()
+            .join("-");
+
+        values
+    }
+}
+
+/**
+ * The template for the reports of the profile: the given template or, without
+ * one, the default for the profile.
+ */
+pub fn get_template(filename_template: Option<&str>, profile: Option<&str>) -> String {
+    match (filename_template, profile) {
+        (Some(template), _) => template.to_owned(),
+        (None, Some(_)) => PROFILE_TEMPLATE.to_owned(),
+        (None, None) => DEFAULT_TEMPLATE.to_owned(),
+    }
+}
+
+/**
+ * The template to find the reports of the profile, downloaded with
+ * [`get_template`]. The profile name is filled in.
+ */
+pub fn get_lookup_template(filename_template: Option<&str>, profile: Option<&str>) -> String {
+    let template = get_template(filename_template, profile);
+    match profile {
+        Some(name) => template.replace("{profile}", &sanitize(name)),
+        None => template,
+    }
+}
+
+/// Creates the filename from the template.
+pub fn render(template: &str, values: &FilenameValues) -> String {
+    template
+        .replace("{date}", &sanitize(&values.date))
+        .replace("{from_date}", &sanitize(&values.from_date))
+        .replace("{to_date}", &sanitize(&values.to_date))
+        .replace("{query_name}", &sanitize(&values.query_name))
+        .replace("{account_id}", &sanitize(&values.account_id))
+        .replace("{profile}", &sanitize(&values.profile))
+}
+
+/// Creates the glob pattern that matches all the filenames from the template.
+pub fn to_glob(template: &str) -> String {
+    PLACEHOLDERS
+        .iter()
+        .fold(template.to_owned(), |pattern, placeholder| {
+            pattern.replace(placeholder, "*")
+        })
+}
+
+/**
+ * Reads the placeholder values back from the filename created with the template.
+ * The dates are expected in the ISO format. Returns None if the filename does
+ * not match the template.
+ */
+pub fn parse(template: &str, filename: &str) -> Option<FilenameValues> {
+    let mut values = FilenameValues::default();
+    if match_template(template, filename, &mut values) {
+        Some(values)
+    } else {
+        None
+    }
+}
+
+/// Matches the rest of the filename against the rest of the template.
+fn match_template(template: &str, filename: &str, values: &mut FilenameValues) -> bool {
+    let Some(start) = template.find('{') else {
+        return template == filename;
+    };
+    let Some(placeholder) = PLACEHOLDERS
+        .iter()
+        .find(|placeholder| template[start..].starts_with(*placeholder))
+    else {
+        // Not a placeholder, the brace is a part of the name.
+        return filename.starts_with(&template[..=start])
+            && match_template(&template[start + 1..], &filename[start + 1..], values);
+    };
+    if !filename.starts_with(&template[..start]) {
+        return false;
+    }
+    let template = &template[start + placeholder.len()..];
+    let filename = &filename[start..];
+
+    // The dates have a fixed length, the other values are matched by trying.
+    let lengths: Vec<usize> = if placeholder.ends_with("date}") {
+        vec![ISO_DATE_LENGTH]
+    } else {
+        filename
+            .char_indices()
+            .skip(1)
+            .map(|(index, _)| index)
+            .chain([filename.len()])
+            .collect()
+    };
+    for length in lengths.into_iter().filter(|length| filename.is_char_boundary(*length)) {
+        let value = &filename[..length];
+        if placeholder.ends_with("date}")
+            && NaiveDate::parse_from_str(value, ISO_DATE_FORMAT).is_err()
+        {
+            continue;
+        }
+        if match_template(template, &filename[length..], values) {
+            set_value(values, placeholder, value);
+            return true;
+        }
+    }
+    false
+}
+
+fn set_value(values: &mut FilenameValues, placeholder: &str, value: &str) {
+    let field = match placeholder {
+        "{date}" => &mut values.date,
+        "{from_date}" => &mut values.from_date,
+        "{to_date}" => &mut values.to_date,
+        "{query_name}" => &mut values.query_name,
+        "{account_id}" => &mut values.account_id,
+        _ => &mut values.profile,
+    };
+    *field = value.to_owned();
+}
+
+/// Replaces the characters that do not belong in a filename.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{
+        get_lookup_template, get_template, parse, render, to_glob, FilenameValues,
+        DEFAULT_TEMPLATE, PROFILE_TEMPLATE,
+    };
+
+    #[test]
+    fn test_values_from_report() {
+        let report = std::fs::read_to_string("tests/report_1.xml").unwrap();
+        let date = NaiveDate::from_ymd_opt(2022, 12, 25).unwrap();
+
+        let actual = FilenameValues::from_report(&report, Some("cash"), date);
+
+        assert_eq!("2022-12-25", actual.date);
+        assert_eq!("2022-11-24", actual.from_date);
+        assert_eq!("2022-12-23", actual.to_date);
+        assert_eq!("cash tx, last 30 days", actual.query_name);
+        assert_eq!("U2550509", actual.account_id);
+        assert_eq!("cash", actual.profile);
+    }
+
+    #[test]
+    fn test_render() {
+        let values = FilenameValues {
+            date: "2022-12-25".into(),
+            from_date: "2022-11-24".into(),
+            to_date: "2022-12-23".into(),
+            query_name: "cash tx, last 30 days".into(),
+            account_id: "U2550509".into(),
+            profile: "cash".into(),
+        };
+
+        assert_eq!("2022-12-25_cash-tx.xml", render(DEFAULT_TEMPLATE, &values));
+        assert_eq!("2022-12-25_U2550509_cash.xml", render(PROFILE_TEMPLATE, &values));
+        assert_eq!(
+            "U2550509/cash_tx__last_30_days_2022-11-24_2022-12-23.xml",
+            render("{account_id}/{query_name}_{from_date}_{to_date}.xml", &values)
+        );
+    }
+
+    #[test]
+    fn test_glob() {
+        assert_eq!("*_cash-tx.xml", to_glob(DEFAULT_TEMPLATE));
+        assert_eq!("*_*_*.xml", to_glob(PROFILE_TEMPLATE));
+    }
+
+    /// The profile's reports are found with the template they were saved with.
+    #[test]
+    fn test_lookup_template() {
+        assert_eq!(DEFAULT_TEMPLATE, get_template(None, None));
+        assert_eq!(PROFILE_TEMPLATE, get_template(None, Some("trades")));
+        assert_eq!("{profile}.xml", get_template(Some("{profile}.xml"), Some("trades")));
+
+        assert_eq!("{date}_{account_id}_trades.xml", get_lookup_template(None, Some("trades")));
+        assert_eq!("*_*_trades.xml", to_glob(&get_lookup_template(None, Some("trades"))));
+        assert_eq!(DEFAULT_TEMPLATE, get_lookup_template(None, None));
+    }
+
+    #[test]
+    fn test_parse() {
+        let actual = parse(PROFILE_TEMPLATE, "2023-03-08_U1234567_cash-tx.xml").unwrap();
+        assert_eq!("2023-03-08", actual.date);
+        assert_eq!("U1234567", actual.account_id);
+        assert_eq!("cash-tx", actual.profile);
+
+        let actual = parse(
+            "{query_name}_{from_date}_{to_date}.xml",
+            "cash_tx__last_30_days_2022-11-24_2022-12-23.xml",
+        )
+        .unwrap();
+        assert_eq!("cash_tx__last_30_days", actual.query_name);
+        assert_eq!("2022-11-24", actual.from_date);
+        assert_eq!("2022-12-23", actual.to_date);
+
+        assert_eq!(None, parse(DEFAULT_TEMPLATE, "2023-03-08_trades.xml"));
+        assert_eq!(None, parse(DEFAULT_TEMPLATE, "latest_cash-tx.xml"));
+    }
+}
@@ -0,0 +1,323 @@
+/*!
+ * Writes the comparison report in the text, JSON, or CSV format.
+ */
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::Serialize;
+
+use crate::{
+    compare::{ComparisonReport, ComparisonSummary},
+    flex_query::format_date,
+    model::CommonTransaction,
+    Error, Result, ISO_DATE_FORMAT,
+};
+
+/// The output format of the comparison.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The human-readable output, as displayed by the CLI.
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(Error::Config(format!(
+                "Unknown output format {s:?}. Use text, json, or csv."
+            ))),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The comparison outcome for one IB transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordStatus {
+    New,
+    Matched,
+    Skipped,
+    /// The Ledger posting without a matching IB transaction.
+    LedgerOnly,
+}
+
+/**
+ * One IB transaction in the comparison output.
+ * The amount is the IB amount. The skipped transactions are not converted, so
+ * they contain the values as in the Flex report.
+ * The Ledger-only postings have the amount with the IB sign, and no IB symbol.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComparisonRecord {
+    pub status: RecordStatus,
+    pub report_date: String,
+    pub effective_date: String,
+    /// The IB symbol, with the exchange.
+    pub symbol: String,
+    /// The Ledger symbol.
+    pub mapped_symbol: String,
+    pub r#type: String,
+    pub amount: String,
+    pub currency: String,
+    pub description: String,
+    pub isin: String,
+    pub account_id: String,
+    /// The Ledger account of the matching posting.
+    pub ledger_account: String,
+    /// The days from the IB date to the matching Ledger posting.
+    pub date_offset: Option<i64>,
+    /// The Ledger amount of the matching posting minus the expected one.
+    pub amount_difference: Option<String>,
+    /// The reason for skipping the transaction.
+    pub reason: String,
+}
+
+impl ComparisonRecord {
+    fn from_common(status: RecordStatus, tx: &CommonTransaction) -> Self {
+        ComparisonRecord {
+            status,
+            report_date: tx.report_date.to_owned(),
+            effective_date: tx.date.format(ISO_DATE_FORMAT).to_string(),
+            symbol: tx.ib_symbol.to_owned(),
+            mapped_symbol: tx.symbol.to_owned(),
+            r#type: tx.r#type.to_string(),
+            amount: tx.amount.to_string(),
+            currency: tx.currency.to_owned(),
+            description: tx.description.to_owned(),
+            isin: tx.isin.to_owned(),
+            account_id: tx.account_id.to_owned(),
+            ledger_account: String::default(),
+            date_offset: None,
+            amount_difference: None,
+            reason: String::default(),
+        }
+    }
+}
+
+/// The JSON document.
+#[derive(Serialize)]
+struct JsonReport {
+    summary: ComparisonSummary,
+    records: Vec<ComparisonRecord>,
+}
+
+/// Lists the new, matched, skipped transactions, and the Ledger-only postings, in that order.
+pub fn get_records(report: &ComparisonReport) -> Vec<ComparisonRecord> {
+    let new = report
+        .new
+        .iter()
+        .map(|tx| ComparisonRecord::from_common(RecordStatus::New, tx));
+
+    let matched = report.matched.iter().map(|matched| ComparisonRecord {
+        ledger_account: matched.ledger.account.to_owned(),
+        date_offset: Some(matched.date_offset),
+        amount_difference: Some(matched.amount_difference.to_string()),
+        ..ComparisonRecord::from_common(RecordStatus::Matched, &matched.ib)
+    });
+
+    let skipped = report.skipped.iter().map(|skipped| {
+        let tx = &skipped.transaction;
+        let exchange = tx.listing_exchange.as_deref().unwrap_or_default();
+        let symbol = if tx.symbol.is_empty() {
+            String::default()
+        } else {
+            format!("{exchange}:{}", tx.symbol)
+        };
+        ComparisonRecord {
+            status: RecordStatus::Skipped,
+            report_date: format_date(tx.report_date),
+            effective_date: format_date(tx.date_time.map(|date_time| date_time.date())),
+            symbol,
+            mapped_symbol: String::default(),
+            r#type: tx.r#type.ib_name().to_owned(),
+            amount: tx.amount.to_string(),
+            currency: tx.currency.to_owned(),
+            description: tx.description.to_owned(),
+            isin: tx.isin.to_owned(),
+            account_id: tx.account_id.to_owned(),
+            ledger_account: String::default(),
+            date_offset: None,
+            amount_difference: None,
+            reason: skipped.reason.to_string(),
+        }
+    });
+
+    let ledger_only = report.unmatched_ledger.iter().map(|tx| ComparisonRecord {
+        symbol: String::default(),
+        amount: (-tx.amount).to_string(),
+        ledger_account: tx.account.to_owned(),
+        ..ComparisonRecord::from_common(RecordStatus::LedgerOnly, tx)
+    });
+
+    new.chain(matched).chain(skipped).chain(ledger_only).collect()
+}
+
+/// Writes the report in the given format.
+pub fn write_report(report: &ComparisonReport, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Text => Ok(report.to_text()),
+        OutputFormat::Json => write_json(report),
+        OutputFormat::Csv => write_csv(report),
+    }
+}
+
+fn write_json(report: &ComparisonReport) -> Result<String> {
+    let document = JsonReport {
+        summary: report.summary(),
+        records: get_records(report),
+    };
+    let mut output = serde_json::to_string_pretty(&document)
+        .map_err(|e| Error::Output(format!("Could not write JSON: {e}")))?;
+    output.push('\n');
+    Ok(output)
+}
+
+fn write_csv(report: &ComparisonReport) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for record in get_records(report) {
+        writer
+            .serialize(record)
+            .map_err(|e| Error::Output(format!("Could not write CSV: {e}")))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| Error::Output(format!("Could not write CSV: {e}")))?;
+    String::from_utf8(bytes).map_err(|e| Error::Output(format!("Could not write CSV: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    use super::{get_records, write_report, OutputFormat, RecordStatus};
+    use crate::{
+        compare::{ComparisonReport, MatchedTransaction, SkipReason, SkippedTransaction},
+        flex_enums::CashAction,
+        flex_query::CashTransaction,
+        model::CommonTransaction,
+    };
+
+    fn report() -> ComparisonReport {
+        let ib_tx = CommonTransaction {
+            date: NaiveDate::from_ymd_opt(2022, 12, 15)
+                .unwrap()
+                .and_hms_opt(12, 20, 0)
+                .unwrap(),
+            report_date: "2022-12-14".into(),
+            amount: Decimal::from_str("6.05").unwrap(),
+            currency: "EUR".into(),
+            symbol: "TCBT_AS".into(),
+            ib_symbol: "AEB:TCBT".into(),
+            r#type: CashAction::Dividend,
+            description: "TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE".into(),
+            isin: "NL0009690247".into(),
+            account_id: "U1234567".into(),
+            ..Default::default()
+        };
+        let ledger_tx = CommonTransaction {
+            account: "Income:Investment:IB:TCBT_AS".into(),
+            amount: Decimal::from_str("-6.05").unwrap(),
+            ..ib_tx.clone()
+        };
+        let skipped = CashTransaction {
+            report_date: NaiveDate::from_ymd_opt(2022, 11, 30),
+            date_time: NaiveDate::from_ymd_opt(2022, 11, 30).unwrap().and_hms_opt(16, 0, 0),
+            r#type: CashAction::DepositWithdraw,
+            amount: Decimal::from(1500),
+            currency: "EUR".into(),
+            description: "CASH RECEIPTS".into(),
+            account_id: "U1234567".into(),
+            ..Default::default()
+        };
+
+        ComparisonReport {
+            new: vec![ib_tx.clone()],
+            matched: vec![MatchedTransaction {
+                ib: ib_tx,
+                ledger: ledger_tx,
+                date_offset: 0,
+                amount_difference: Decimal::ZERO,
+            }],
+            skipped: vec![SkippedTransaction {
+                transaction: skipped,
+                reason: SkipReason::ExcludedType(CashAction::DepositWithdraw),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let actual = get_records(&report());
+
+        assert_eq!(3, actual.len());
+        assert_eq!(RecordStatus::New, actual[0].status);
+        assert_eq!("2022-12-15", actual[0].effective_date);
+        assert_eq!("AEB:TCBT", actual[0].symbol);
+        assert_eq!("TCBT_AS", actual[0].mapped_symbol);
+        assert_eq!(RecordStatus::Matched, actual[1].status);
+        assert_eq!("Income:Investment:IB:TCBT_AS", actual[1].ledger_account);
+        assert_eq!(RecordStatus::Skipped, actual[2].status);
+        assert_eq!("DepositWithdraw is not compared", actual[2].reason);
+    }
+
+    #[test]
+    fn test_csv() {
+        let actual = write_report(&report(), OutputFormat::Csv).unwrap();
+
+        let lines: Vec<&str> = actual.lines().collect();
+        assert_eq!(4, lines.len());
+        assert_eq!(
+            "status,report_date,effective_date,symbol,mapped_symbol,type,amount,currency,description,isin,account_id,ledger_account,date_offset,amount_difference,reason",
+            lines[0]
+        );
+        assert_eq!(
+            "new,2022-12-14,2022-12-15,AEB:TCBT,TCBT_AS,Dividend,6.05,EUR,TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE,NL0009690247,U1234567,,,,",
+            lines[1]
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let actual = write_report(&report(), OutputFormat::Json).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&actual).unwrap();
+        assert_eq!(1, json["summary"]["new"]);
+        assert_eq!(1, json["summary"]["skipped"]);
+        assert_eq!("matched", json["records"][1]["status"]);
+        assert_eq!("6.05", json["records"][1]["amount"]);
+        assert_eq!(0, json["records"][1]["date_offset"]);
+        assert!(json["records"][0]["date_offset"].is_null());
+        assert_eq!("0", json["records"][1]["amount_difference"]);
+        assert_eq!(0, json["summary"]["amount_differences"]);
+        assert_eq!("Deposits/Withdrawals", json["records"][2]["type"]);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(OutputFormat::Json, "JSON".parse().unwrap());
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+}
@@ -2,12 +2,16 @@
  * Test fixtures
  */
 
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
-use chrono::Local;
+use chrono::{Local, NaiveDate};
 use rstest::fixture;
+use rust_decimal::Decimal;
 
-use crate::{compare::CompareParams, flex_query::CashTransaction, ISO_DATE_FORMAT};
+use crate::{
+    compare::CompareParams, flex_enums::CashAction, flex_query::CashTransaction,
+    model::CommonTransaction,
+};
 
 #[fixture]
 pub fn tests_directory_path() -> String {
@@ -43,34 +47,83 @@ pub fn cmp_params(flex_report_path: String, ledger_journal_path: String,
         ledger_journal_file: Some(ledger_journal_path),
         symbols_path: symbols_path.as_path().to_str().unwrap().to_owned(),
         effective_dates: false,
+        ..Default::default()
     }
 }
 
 #[fixture]
 pub fn cash_transactions() -> Vec<CashTransaction> {
     let tx1 = CashTransaction {
-        report_date: "2022-12-14".to_string(),
-        date_time: "2022-12-15;12:20:00".to_string(),
-        amount: "-0.91".to_string(),
+        report_date: NaiveDate::from_ymd_opt(2022, 12, 14),
+        date_time: NaiveDate::from_ymd_opt(2022, 12, 15).unwrap().and_hms_opt(12, 20, 0),
+        amount: Decimal::from_str("-0.91").unwrap(),
         currency: "EUR".to_string(),
         description: "TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE - NL TAX".to_string(),
         listing_exchange: Some("AEB".to_string()),
         symbol: "TCBT".to_string(),
         isin: String::new(),
-        r#type: "Withholding Tax".to_string(),
+        account_id: String::new(),
+        r#type: CashAction::WhTax,
+        transaction_id: String::new(),
+        action_id: String::new(),
+        extra: Default::default(),
     };
 
     let dist = CashTransaction {
-        report_date: Local::now().format(ISO_DATE_FORMAT).to_string(),
-        date_time: "2022-12-26".into(),
-        amount: "10".into(),
+        report_date: Some(Local::now().date_naive()),
+        date_time: NaiveDate::from_ymd_opt(2022, 12, 26).unwrap().and_hms_opt(0, 0, 0),
+        amount: Decimal::TEN,
         currency: "EUR".into(),
         description: "TCBT distribution".into(),
-        r#type: "Dividends".into(),
-        listing_exchange: "AMS".into(),
+        r#type: CashAction::Dividend,
+        listing_exchange: Some("AMS".into()),
         symbol: "TCBT".into(),
         isin: String::new(),
+        account_id: String::new(),
+        transaction_id: String::new(),
+        action_id: String::new(),
+        extra: Default::default(),
     };
 
     vec![tx1, dist]
 }
+
+/// A new TCBT_AS transaction, for the entry writers.
+pub fn new_tx(r#type: CashAction, amount: &str, description: &str) -> CommonTransaction {
+    CommonTransaction {
+        date: NaiveDate::from_ymd_opt(2022, 12, 15)
+            .unwrap()
+            .and_hms_opt(12, 20, 0)
+            .unwrap(),
+        report_date: "2022-12-14".into(),
+        amount: Decimal::from_str(amount).unwrap(),
+        currency: "EUR".into(),
+        symbol: "TCBT_AS".into(),
+        r#type,
+        description: description.into(),
+        ..Default::default()
+    }
+}
+
+/// The dividend and its withholding tax.
+#[fixture]
+pub fn dividend_with_tax() -> Vec<CommonTransaction> {
+    vec![
+        new_tx(CashAction::Dividend, "6.05", "TCBT CASH DIVIDEND"),
+        new_tx(CashAction::WhTax, "-0.91", "TCBT CASH DIVIDEND - NL TAX"),
+    ]
+}
+
+/// The dividend and its withholding tax, with the ISIN and the transaction ids.
+#[fixture]
+pub fn tagged_dividend_with_tax(dividend_with_tax: Vec<CommonTransaction>) -> Vec<CommonTransaction> {
+    dividend_with_tax
+        .into_iter()
+        .zip(["1001", "1002"])
+        .map(|(tx, transaction_id)| CommonTransaction {
+            isin: "NL0009690247".into(),
+            transaction_id: transaction_id.into(),
+            ..tx
+        })
+        .collect()
+}
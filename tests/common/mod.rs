@@ -0,0 +1,126 @@
+/*!
+ * A fake Flex Web Service, running in-process, for the download tests.
+ */
+
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// The response to the statement request (1st step), with the reference code.
+pub const REQUEST_SUCCESS: &str = r"<FlexStatementResponse timestamp='17 January, 2023 12:51 PM EST'>
+<Status>Success</Status>
+<ReferenceCode>1234567890</ReferenceCode>
+<Url>https://gdcdyn.interactivebrokers.com/Universal/servlet/FlexStatementService.GetStatement</Url>
+</FlexStatementResponse>
+";
+
+/// The statement is not generated yet.
+pub const STATEMENT_IN_PROGRESS: &str = r"<FlexStatementResponse timestamp='17 January, 2023 12:51 PM EST'>
+<Status>Warn</Status>
+<ErrorCode>1019</ErrorCode>
+<ErrorMessage>Statement generation in progress. Please try again shortly.</ErrorMessage>
+</FlexStatementResponse>
+";
+
+/// Creates an error response with the given code.
+pub fn error_response(code: u32, message: &str) -> String {
+    format!(
+        r"<FlexStatementResponse timestamp='17 January, 2023 12:51 PM EST'>
+<Status>Fail</Status>
+<ErrorCode>{code}</ErrorCode>
+<ErrorMessage>{message}</ErrorMessage>
+</FlexStatementResponse>
+"
+    )
+}
+
+/**
+ * Serves the prepared responses for the SendRequest and GetStatement endpoints.
+ * The responses of each endpoint are served in order. The last one is repeated.
+ */
+pub struct FakeFlexServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeFlexServer {
+    pub fn start(request_response: &str, statement_responses: Vec<String>) -> Self {
+        Self::start_with_requests(vec![request_response.to_owned()], statement_responses)
+    }
+
+    /// Serves the given responses to the statement requests, in order.
+    pub fn start_with_requests(request_responses: Vec<String>, statement_responses: Vec<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("listener bound");
+        let base_url = format!("http://{}/servlet/", listener.local_addr().unwrap());
+
+        let requests = Arc::new(Mutex::new(vec![]));
+        let request_responses = Arc::new(Mutex::new(VecDeque::from(request_responses)));
+        let statements = Arc::new(Mutex::new(VecDeque::from(statement_responses)));
+
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let path = read_request_path(&stream);
+                log.lock().unwrap().push(path.to_owned());
+
+                let body = if path.contains("FlexStatementService.SendRequest") {
+                    next_response(&request_responses)
+                } else if path.contains("FlexStatementService.GetStatement") {
+                    next_response(&statements)
+                } else {
+                    String::default()
+                };
+
+                write_response(stream, &body);
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    /// The paths (with the query strings) of the received requests.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// The next response in the queue. The last one is repeated.
+fn next_response(responses: &Mutex<VecDeque<String>>) -> String {
+    let mut queue = responses.lock().unwrap();
+    if queue.len() > 1 {
+        queue.pop_front().unwrap()
+    } else {
+        queue.front().cloned().unwrap_or_default()
+    }
+}
+
+fn read_request_path(stream: &TcpStream) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::default();
+    reader.read_line(&mut request_line).unwrap();
+
+    // Skip the headers.
+    let mut line = String::default();
+    while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+        line.clear();
+    }
+
+    request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn write_response(mut stream: TcpStream, body: &str) {
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).unwrap();
+}
@@ -147,3 +147,128 @@ Complete.
 "#;
     assert.success().stdout(expected);
 }
+
+/// The new transactions are printed as ready-to-paste Ledger entries.
+#[rstest::rstest]
+fn test_ledger_entries(mut app_cmd: Command) {
+    let assert = app_cmd
+        .args(vec![
+            "cmp",
+            "--ledger-entries",
+            "--flex-report-path",
+            "tests/report_1.xml",
+            "--symbols-path",
+            "tests/symbols.csv",
+            "--ledger-journal-file",
+            "tests/journal.ledger",
+        ])
+        .assert();
+
+    let expected = r#"Complete.
+
+2022-12-14=2022-12-15 TCBT_AS Distribution
+    ; TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE (Ordinary Dividend)
+    Income:Investment:IB:TCBT_AS                        -6.05 EUR
+    ; TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE - NL TAX
+    Expenses:Investment:IB:Withholding Tax               0.91 EUR
+    Assets:Investment:IB:Cash                            5.14 EUR
+"#;
+
+    let output = assert.success().get_output().stdout.to_owned();
+    let actual = String::from_utf8(output).unwrap();
+    assert!(actual.ends_with(expected), "unexpected output:\n{actual}");
+}
+
+/// The report and the symbols are taken from ibflex.toml in the current directory.
+#[rstest::rstest]
+fn test_comparison_w_config(mut app_cmd: Command) {
+    let assert = app_cmd
+        .args(vec!["cmp", "--ledger-journal-file", "tests/journal.ledger"])
+        .assert();
+
+    let output = assert.success().get_output().stdout.to_owned();
+    let actual = String::from_utf8(output).unwrap();
+    assert!(actual.starts_with("Using ./tests/report_1.xml\n"));
+}
+
+/// The environment variables take precedence over the configuration file.
+#[rstest::rstest]
+fn test_comparison_w_env(mut app_cmd: Command) {
+    let assert = app_cmd
+        .env("IBFLEX_REPORT_PATH", "tests/tcf.xml")
+        .env("IBFLEX_JOURNAL_FILE", "tests/tcf.ledger")
+        .args(vec!["cmp"])
+        .assert();
+
+    assert.success().stdout("Using tests/tcf.xml\nComplete.\n");
+}
+
+/// A missing symbols file is reported as an error, with an exit code.
+#[rstest::rstest]
+fn test_missing_symbols_file(mut app_cmd: Command) {
+    let assert = app_cmd
+        .args(vec![
+            "cmp",
+            "--flex-report-path",
+            "tests/report_1.xml",
+            "--symbols-path",
+            "tests/no-symbols.csv",
+        ])
+        .assert();
+
+    assert
+        .code(5)
+        .stderr("Error: Symbols: The symbols file \"tests/no-symbols.csv\" does not exist!\n");
+}
+
+/// The CSV output contains only the records, with a header.
+#[rstest::rstest]
+fn test_csv_output(mut app_cmd: Command) {
+    let assert = app_cmd
+        .args(vec![
+            "cmp",
+            "--flex-report-path",
+            "tests/report_1.xml",
+            "--symbols-path",
+            "tests/symbols.csv",
+            "--ledger-journal-file",
+            "tests/journal.ledger",
+            "--format",
+            "csv",
+        ])
+        .assert();
+
+    let output = assert.success().get_output().stdout.to_owned();
+    let actual = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = actual.lines().collect();
+    assert_eq!(8, lines.len());
+    assert!(lines[0].starts_with("status,report_date,effective_date,symbol,mapped_symbol,"));
+    assert!(lines[1].starts_with("new,2022-12-14,2022-12-15,AEB:TCBT,TCBT_AS,Dividend,6.05,EUR,"));
+    assert!(lines[1].ends_with(",U2550509,,,,"));
+    assert!(lines[3].starts_with("matched,"));
+    assert!(lines[7].starts_with("skipped,2022-12-05,2022-12-05,,,Broker Interest Received,2.77,AUD,"));
+}
+
+/// Ledger postings without a matching IB transaction are listed.
+#[rstest::rstest]
+fn test_bidirectional(mut app_cmd: Command) {
+    let assert = app_cmd
+        .args(vec![
+            "cmp",
+            "--flex-report-path",
+            "tests/report_1.xml",
+            "--symbols-path",
+            "tests/symbols.csv",
+            "--ledger-journal-file",
+            "tests/reverse.ledger",
+            "--bidirectional",
+        ])
+        .assert();
+
+    let output = assert.success().get_output().stdout.to_owned();
+    let actual = String::from_utf8(output).unwrap();
+    assert!(actual.contains(
+        "Not in IB: 2022-12-14 TCBT_AS Dividend   -6.50 EUR, Income:Investment:IB:TCBT_AS\n"
+    ));
+    assert!(!actual.contains("-40.00"));
+}
@@ -0,0 +1,291 @@
+/*!
+ * Test the download of the Flex reports, using a fake Flex Web Service.
+ */
+
+use std::time::Duration;
+
+use ibflex::{
+    download::{download, download_report, DownloadParams, RetryPolicy},
+    flex_reader::get_latest_report_path,
+    flex_statement::FlexErrorCode,
+    Error,
+};
+
+mod common;
+use common::*;
+
+fn dl_params(server: &FakeFlexServer) -> DownloadParams {
+    DownloadParams {
+        query_id: Some(123456),
+        token: Some("abcdef".into()),
+        base_url: server.base_url.to_owned(),
+        retry: RetryPolicy {
+            initial_delay: Duration::from_millis(10),
+            backoff_factor: 2,
+            max_delay: Duration::from_millis(40),
+            timeout: Duration::from_secs(1),
+        },
+        ..Default::default()
+    }
+}
+
+fn report() -> String {
+    std::fs::read_to_string("tests/report_1.xml").unwrap()
+}
+
+#[test]
+fn test_download() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+
+    let actual = download_report(&dl_params(&server)).expect("report downloaded");
+
+    assert_eq!(report(), actual);
+    let requests = server.requests();
+    assert_eq!(2, requests.len());
+    assert!(requests[0].contains("FlexStatementService.SendRequest?v=3&t=abcdef&q=123456"));
+    assert!(requests[1].contains("FlexStatementService.GetStatement?v=3&q=1234567890&t=abcdef"));
+}
+
+/// The report is saved into the output directory and found there by the template.
+#[test]
+fn test_download_into_dir() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+    let output_dir = std::env::temp_dir().join(format!("ibflex-dl-{}", std::process::id()));
+    let template = "{account_id}_{from_date}_{to_date}.xml";
+    let params = DownloadParams {
+        output_dir: Some(output_dir.display().to_string()),
+        filename_template: Some(template.into()),
+        ..dl_params(&server)
+    };
+
+    let actual = download(params).expect("report saved");
+
+    let expected = output_dir.join("U2550509_2022-11-24_2022-12-23.xml");
+    assert_eq!(expected.display().to_string(), actual);
+    assert_eq!(report(), std::fs::read_to_string(&expected).unwrap());
+    let found = get_latest_report_path(Some(output_dir.display().to_string()), template).unwrap();
+    assert_eq!(actual, found);
+
+    std::fs::remove_dir_all(output_dir).unwrap();
+}
+
+/// The statement download is retried while the statement is being generated.
+#[test]
+fn test_download_retries_in_progress() {
+    let responses = vec![
+        STATEMENT_IN_PROGRESS.to_owned(),
+        STATEMENT_IN_PROGRESS.to_owned(),
+        report(),
+    ];
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, responses);
+
+    let actual = download_report(&dl_params(&server)).expect("report downloaded");
+
+    assert_eq!(report(), actual);
+    assert_eq!(4, server.requests().len());
+}
+
+/// The statement request is retried while the service is busy.
+#[test]
+fn test_request_retries_busy() {
+    let requests = vec![
+        error_response(1018, "Too many requests have been made from this token."),
+        error_response(1009, "The server is under heavy load."),
+        REQUEST_SUCCESS.to_owned(),
+    ];
+    let server = FakeFlexServer::start_with_requests(requests, vec![report()]);
+
+    let actual = download_report(&dl_params(&server)).expect("report downloaded");
+
+    assert_eq!(report(), actual);
+    let requests = server.requests();
+    assert_eq!(4, requests.len());
+    assert!(requests[2].contains("FlexStatementService.SendRequest"));
+}
+
+/// The download gives up once the timeout is reached.
+#[test]
+fn test_download_timeout() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![STATEMENT_IN_PROGRESS.to_owned()]);
+    let mut params = dl_params(&server);
+    params.retry.timeout = Duration::from_millis(100);
+
+    let actual = download_report(&params).expect_err("timed out");
+
+    let Error::StatementTimeout { source, .. } = actual else {
+        panic!("expected a timeout, got {:?}", actual)
+    };
+    assert_eq!(FlexErrorCode::GenerationInProgress, source.code);
+}
+
+/// The error in the statement request is returned.
+#[test]
+fn test_request_error() {
+    let server = FakeFlexServer::start(&error_response(1015, "Token is invalid."), vec![]);
+
+    let actual = download_report(&dl_params(&server)).expect_err("invalid token");
+
+    let Error::FlexService(error) = actual else {
+        panic!("expected a service error, got {:?}", actual)
+    };
+    assert_eq!(FlexErrorCode::InvalidToken, error.code);
+    assert_eq!("Token is invalid.", error.message);
+    assert_eq!(1, server.requests().len());
+}
+
+/// The error in the statement download is not retried.
+#[test]
+fn test_statement_error() {
+    let responses = vec![error_response(1012, "Token has expired.")];
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, responses);
+
+    let actual = download_report(&dl_params(&server)).expect_err("expired token");
+
+    let Error::FlexService(error) = actual else {
+        panic!("expected a service error, got {:?}", actual)
+    };
+    assert_eq!(FlexErrorCode::TokenExpired, error.code);
+    assert_eq!(2, server.requests().len());
+}
+
+/// Creates a directory with the configuration for the CLI tests.
+fn cli_config_dir(name: &str, server: &FakeFlexServer) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("ibflex-cli-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = format!(
+        r#"
+ib_token = "main-token"
+flex_reports_dir = "reports"
+flex_url = "{}"
+
+[profiles.cash-tx]
+flex_query_id = 111111
+
+[profiles.trades]
+flex_query_id = 222222
+ib_token = "trades-token"
+"#,
+        server.base_url
+    );
+    std::fs::write(dir.join("ibflex.toml"), config).unwrap();
+    dir
+}
+
+/// Runs the CLI in the given directory, without the configuration from the environment.
+fn ibflex_cmd(dir: &std::path::Path) -> assert_cmd::Command {
+    let mut cmd = assert_cmd::Command::cargo_bin("ibflex").unwrap();
+    cmd.current_dir(dir);
+    for (name, _) in std::env::vars().filter(|(name, _)| name.starts_with("IBFLEX_")) {
+        cmd.env_remove(name);
+    }
+    cmd
+}
+
+/// The names of the saved reports.
+fn saved_reports(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir.join("reports"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The profile's query id and token are used, and its name is in the filename.
+#[test]
+fn test_cli_download_profile() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+    let dir = cli_config_dir("profile", &server);
+
+    ibflex_cmd(&dir)
+        .args(["dl", "--profile", "trades", "--retry-delay", "0"])
+        .assert()
+        .success();
+
+    let requests = server.requests();
+    assert!(requests[0].contains("SendRequest?v=3&t=trades-token&q=222222"));
+    let reports = saved_reports(&dir);
+    assert_eq!(1, reports.len());
+    assert!(reports[0].ends_with("_U2550509_trades.xml"), "{reports:?}");
+
+    // The report is found by the profile.
+    let assert = ibflex_cmd(&dir).args(["inspect", "--profile", "trades"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    let expected = std::path::Path::new("reports").join(&reports[0]);
+    assert!(stdout.starts_with(&format!("Using {}\n", expected.display())), "{stdout}");
+    ibflex_cmd(&dir).args(["inspect", "--profile", "cash-tx"]).assert().code(3);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// All the profiles are downloaded into separate files.
+#[test]
+fn test_cli_download_all() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+    let dir = cli_config_dir("all", &server);
+
+    ibflex_cmd(&dir)
+        .args(["dl", "--all", "--retry-delay", "0"])
+        .assert()
+        .success();
+
+    let requests = server.requests();
+    assert_eq!(4, requests.len());
+    assert!(requests[0].contains("SendRequest?v=3&t=main-token&q=111111"));
+    assert!(requests[2].contains("SendRequest?v=3&t=trades-token&q=222222"));
+    let reports = saved_reports(&dir);
+    assert_eq!(2, reports.len());
+    assert!(reports[0].ends_with("_U2550509_cash-tx.xml"), "{reports:?}");
+    assert!(reports[1].ends_with("_U2550509_trades.xml"), "{reports:?}");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// The query id and the token would replace the ones in every profile.
+#[test]
+fn test_cli_download_all_with_query_id() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+    let dir = cli_config_dir("all-query-id", &server);
+
+    for args in [["dl", "--all", "--query-id", "333333"], ["dl", "--all", "--token", "other"]] {
+        let assert = ibflex_cmd(&dir).args(args).assert().failure();
+
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+        assert!(stderr.contains("cannot be used with"), "{stderr}");
+    }
+
+    assert!(server.requests().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// The reports of all the profiles would be saved under the same name.
+#[test]
+fn test_cli_download_all_template_without_profile() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+    let dir = cli_config_dir("all-template", &server);
+
+    let assert = ibflex_cmd(&dir)
+        .args(["dl", "--all", "--filename-template", "{date}_{account_id}.xml"])
+        .assert()
+        .code(2);
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("must contain {profile}"), "{stderr}");
+    assert!(server.requests().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// An unknown profile is a configuration error.
+#[test]
+fn test_cli_download_unknown_profile() {
+    let server = FakeFlexServer::start(REQUEST_SUCCESS, vec![report()]);
+    let dir = cli_config_dir("unknown", &server);
+
+    let assert = ibflex_cmd(&dir).args(["dl", "--profile", "positions"]).assert().code(2);
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("The profile \"positions\" is not configured"), "{stderr}");
+
+    assert!(server.requests().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -2,31 +2,49 @@
  * Test parsing the Flex report
  */
 
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime};
 use ibflex::{
     compare::CompareParams,
-    flex_query::{CashTransaction, FlexQueryResponse},
+    flex_enums::{AssetCategory, BuySell, CashAction, Code, CorporateActionType, OpenClose, PutCall, TradeType},
+    flex_query::{CashTransaction, FlexQueryResponse, FlexStatement, TradeItem},
 };
+use rust_decimal::Decimal;
+
+fn date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::from_str(value).ok()
+}
+
+fn date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
 
 #[rstest::rstest]
 #[test_log::test]
 fn parse_file_test() {
     let mut expected = FlexQueryResponse::default();
     let tx1 = CashTransaction {
-        report_date: "2022-12-14".to_string(),
-        amount: "-0.91".to_string(),
+        report_date: date("2022-12-14"),
+        amount: Decimal::from_str("-0.91").unwrap(),
         currency: "EUR".to_string(),
-        date_time: "2022-12-15;12:20:00".to_string(),
+        date_time: date_time("2022-12-15 12:20:00"),
         description: "TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE - NL TAX".to_string(),
         listing_exchange: Some("AEB".to_string()),
         symbol: "TCBT".to_string(),
-        r#type: "Withholding Tax".to_string(),
+        r#type: CashAction::WhTax,
+        ..Default::default()
     };
-    expected
-        .flex_statements
-        .flex_statement
+    let mut statement = FlexStatement::default();
+    statement
         .cash_transactions
         .cash_transaction
         .push(tx1);
+    expected.flex_statements.flex_statement.push(statement);
 
     let cmp_params = CompareParams {
         flex_report_path: Some("tests/report_1.xml".to_string()),
@@ -34,21 +52,23 @@ fn parse_file_test() {
         ledger_journal_file: None,
         symbols_path: "tests/symbols.csv".to_owned(),
         effective_dates: false,
+        ..Default::default()
     };
 
-    let report = ibflex::flex_reader::load_report(cmp_params.flex_report_path, cmp_params.flex_reports_dir);
-    let actual = FlexQueryResponse::from(report);
+    let report = ibflex::flex_reader::load_report(cmp_params.flex_report_path, cmp_params.flex_reports_dir, None)
+        .expect("report loaded");
+    let actual = FlexQueryResponse::try_from(report).expect("report parsed");
 
     //assert_eq!(expected, actual);
     assert_eq!(
         expected
             .flex_statements
-            .flex_statement
+            .flex_statement[0]
             .cash_transactions
             .cash_transaction[0],
         actual
             .flex_statements
-            .flex_statement
+            .flex_statement[0]
             .cash_transactions
             .cash_transaction[0]
     );
@@ -73,23 +93,23 @@ fn parse_string_test() {
     </FlexStatements>
 </FlexQueryResponse>
     "#.to_string();
-    let actual = FlexQueryResponse::from(content);
+    let actual = FlexQueryResponse::try_from(content).expect("report parsed");
 
     assert_ne!(actual, FlexQueryResponse::default());
     assert_eq!(actual.flex_statements.count, 1);
     // statement
-    let stmt = &actual.flex_statements.flex_statement;
+    let stmt = &actual.flex_statements.flex_statement[0];
     assert_eq!("U3550519".to_string(), stmt.account_id);
-    assert_eq!("2022-11-24", stmt.from_date);
-    assert_eq!("2022-12-23", stmt.to_date);
+    assert_eq!(date("2022-11-24"), stmt.from_date);
+    assert_eq!(date("2022-12-23"), stmt.to_date);
     assert_eq!("Last30CalendarDays", stmt.period);
-    assert_eq!("2022-12-25;14:53:12", stmt.when_generated);
+    assert_eq!(date_time("2022-12-25 14:53:12"), stmt.when_generated);
     // cash transactions
     assert_eq!(
         7,
         actual
             .flex_statements
-            .flex_statement
+            .flex_statement[0]
             .cash_transactions
             .cash_transaction
             .len()
@@ -97,18 +117,251 @@ fn parse_string_test() {
     // cash transaction
     let tx1 = &actual
         .flex_statements
-        .flex_statement
+        .flex_statement[0]
         .cash_transactions
         .cash_transaction[0];
-    assert_eq!("2022-12-14", tx1.report_date);
-    assert_eq!("2022-12-15;12:20:00", tx1.date_time);
+    assert_eq!(date("2022-12-14"), tx1.report_date);
+    assert_eq!(date_time("2022-12-15 12:20:00"), tx1.date_time);
     assert_eq!("TCBT", tx1.symbol);
-    assert_eq!("AEB", tx1.listing_exchange);
-    assert_eq!("Withholding Tax", tx1.r#type);
-    assert_eq!("-0.91", tx1.amount);
+    assert_eq!(Some("AEB"), tx1.listing_exchange.as_deref());
+    assert_eq!(CashAction::WhTax, tx1.r#type);
+    assert_eq!(Decimal::from_str("-0.91").unwrap(), tx1.amount);
     assert_eq!("EUR", tx1.currency);
     assert_eq!(
         "TCBT(NL0009690247) CASH DIVIDEND EUR 0.05 PER SHARE - NL TAX",
         tx1.description
     );
 }
+
+#[test]
+fn parse_codes_test() {
+    let content = r#"
+<FlexQueryResponse queryName="trades" type="AF">
+    <FlexStatements count="1">
+        <FlexStatement accountId="U1234567" fromDate="20230301" toDate="20230308" period="LastBusinessWeek" whenGenerated="20230308;101500">
+            <Trades>
+                <Trade assetCategory="STK" symbol="VHYL" transactionType="ExchTrade" buySell="BUY" openCloseIndicator="O" notes="P;O" quantity="10" tradePrice="58.5" />
+                <Trade assetCategory="OPT" symbol="SPY" transactionType="NewTradeType" buySell="SELL (Ca.)" openCloseIndicator="" notes="Ex;XYZ" quantity="-1" />
+            </Trades>
+            <CorporateActions>
+                <CorporateAction assetCategory="STK" symbol="ABC" type="RS" actionID="123" quantity="-100" />
+                <CorporateAction assetCategory="STK" symbol="DEF" type="ZZ" actionID="124" />
+            </CorporateActions>
+        </FlexStatement>
+    </FlexStatements>
+</FlexQueryResponse>
+    "#
+    .to_string();
+    let actual = FlexQueryResponse::try_from(content).expect("report parsed");
+
+    let stmt = &actual.flex_statements.flex_statement[0];
+    let trades: Vec<_> = stmt
+        .trades
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TradeItem::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(2, trades.len());
+    assert_eq!(Some(AssetCategory::Stock), trades[0].asset_category);
+    assert_eq!(Some(TradeType::ExchTrade), trades[0].transaction_type);
+    assert_eq!(Some(BuySell::Buy), trades[0].buy_sell);
+    assert_eq!(Some(OpenClose::Open), trades[0].open_close_indicator);
+    assert_eq!(vec![Code::PartialExecution, Code::Opening], trades[0].notes);
+    // the unknown and the empty codes
+    assert_eq!(Some(TradeType::Unknown("NewTradeType".into())), trades[1].transaction_type);
+    assert_eq!(Some(BuySell::CancelSell), trades[1].buy_sell);
+    assert_eq!(None, trades[1].open_close_indicator);
+    assert_eq!(vec![Code::Exercise, Code::Unknown("XYZ".into())], trades[1].notes);
+
+    let actions = &stmt.corporate_actions.corporate_action;
+    assert_eq!(Some(CorporateActionType::ReverseSplit), actions[0].r#type);
+    assert_eq!(Some(CorporateActionType::Unknown("ZZ".into())), actions[1].r#type);
+}
+
+/// The elements that are not modelled are kept in order, in the Trades
+/// section and in the other sections.
+#[test]
+fn parse_unknown_elements_test() {
+    let content = r#"
+<FlexQueryResponse queryName="orders" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="20230301" toDate="20230331" period="LastMonth" whenGenerated="20230401;083015">
+<Trades>
+<Order assetCategory="STK" symbol="AIR" quantity="4" levelOfDetail="ORDER" />
+<Trade assetCategory="STK" symbol="AIR" quantity="4" />
+<SymbolSummary assetCategory="STK" symbol="AIR" quantity="4" />
+<AssetSummary assetCategory="STK" quantity="4" />
+<WashSale symbol="AIR" quantity="-2" />
+</Trades>
+<CashTransactions>
+<CashTransaction type="Dividends" amount="38.4" />
+<CashTransactionSummary amount="38.4" />
+</CashTransactions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>
+"#
+    .to_string();
+
+    let actual = FlexQueryResponse::try_from(content).expect("report parsed");
+
+    let stmt = &actual.flex_statements.flex_statement[0];
+    let names: Vec<&str> = stmt
+        .trades
+        .items
+        .iter()
+        .map(|item| match item {
+            TradeItem::Trade(_) => "Trade",
+            TradeItem::Lot(_) => "Lot",
+            TradeItem::Unknown(element) => element.name.as_str(),
+        })
+        .collect();
+    assert_eq!(vec!["Order", "Trade", "SymbolSummary", "AssetSummary", "WashSale"], names);
+    let TradeItem::Unknown(order) = &stmt.trades.items[0] else {
+        panic!("Order is not modelled");
+    };
+    assert_eq!("ORDER", order.attributes["levelOfDetail"]);
+
+    assert_eq!(1, stmt.cash_transactions.cash_transaction.len());
+    assert_eq!("CashTransactionSummary", stmt.cash_transactions.elements[0].name);
+    assert_eq!("38.4", stmt.cash_transactions.elements[0].attributes["amount"]);
+}
+
+/// All the modelled statement sections, in `tests/sections.xml`.
+#[test]
+fn parse_sections_test() {
+    let content = std::fs::read_to_string("tests/sections.xml").unwrap();
+    let actual = FlexQueryResponse::try_from(content).expect("report parsed");
+    let stmt = &actual.flex_statements.flex_statement[0];
+
+    let account = &stmt.account_information;
+    assert_eq!("Jane Doe", account.name);
+    assert_eq!("Individual", account.account_type);
+    assert_eq!(date("2019-01-14"), account.date_opened);
+    assert_eq!(None, account.date_closed);
+
+    let equity = &stmt.equity_summary_in_base.equity_summary_by_report_date_in_base;
+    assert_eq!(2, equity.len());
+    assert_eq!(date("2023-03-31"), equity[1].report_date);
+    assert_eq!(dec("50366.2"), equity[1].total);
+    assert_eq!(dec("-95"), equity[1].options_short);
+
+    let nav = &stmt.change_in_nav;
+    assert_eq!(dec("49102.4"), nav.starting_value);
+    assert_eq!(dec("-5.77"), nav.withholding_tax);
+    assert_eq!(dec("50366.2"), nav.ending_value);
+    assert_eq!(Some(dec("1.5786")), nav.twr);
+
+    let rates = &stmt.conversion_rates.conversion_rate;
+    assert_eq!(2, rates.len());
+    assert_eq!("USD", rates[0].from_currency);
+    assert_eq!(Some(dec("0.92015")), rates[0].rate);
+
+    let transfer = &stmt.transfers.transfer[0];
+    assert_eq!(Some(AssetCategory::Stock), transfer.asset_category);
+    assert_eq!("IN", transfer.direction);
+    assert_eq!(dec("25"), transfer.quantity);
+    assert_eq!(date_time("2023-03-10 12:00:00"), transfer.date_time);
+    assert!(transfer.code.is_empty());
+
+    let interest = &stmt.interest_accruals.interest_accruals_currency[0];
+    assert_eq!(dec("2.82"), interest.interest_accrued);
+    assert_eq!(dec("1.25"), interest.ending_accrual_balance);
+
+    let dividend = &stmt.change_in_dividend_accruals.change_in_dividend_accrual[0];
+    assert_eq!("NL0009690239", dividend.isin);
+    assert_eq!(date("2023-03-01"), dividend.ex_date);
+    assert_eq!(dec("32.63"), dividend.net_amount);
+    assert_eq!(vec![Code::AccrualReversal], dividend.code);
+    assert_eq!("120374569", dividend.action_id);
+
+    let open_dividend = &stmt.open_dividend_accruals.open_dividend_accrual[0];
+    assert_eq!(date("2023-03-30"), open_dividend.pay_date);
+    assert_eq!(Some(dec("0.92015")), open_dividend.fx_rate_to_base);
+
+    let security = &stmt.securities_info.security_info[0];
+    assert_eq!(Some(AssetCategory::Option), security.asset_category);
+    assert_eq!(Some(PutCall::Put), security.put_call);
+    assert_eq!(Some(dec("380")), security.strike);
+    assert_eq!(date("2023-04-21"), security.expiry);
+    assert_eq!(None, security.maturity);
+
+    let eae = &stmt.option_eae.option_eae[0];
+    assert_eq!("Expiration", eae.transaction_type);
+    assert_eq!(dec("104.5"), eae.realized_pnl);
+
+    let fx = &stmt.fx_transactions.fx_transaction[0];
+    assert_eq!("USD", fx.fx_currency);
+    assert_eq!(date_time("2023-03-30 20:20:00"), fx.date_time);
+    assert_eq!(dec("-8.01"), fx.cost);
+    assert_eq!(vec![Code::Opening], fx.code);
+
+    let tax = &stmt.transaction_taxes.transaction_tax[0];
+    assert_eq!(date("2023-03-08"), tax.date);
+    assert_eq!(dec("-0.5"), tax.tax_amount);
+    assert_eq!("French Daily Trade Charge Tax", tax.tax_description);
+
+    let commission = &stmt.unbundled_commission_details.unbundled_commission_detail[0];
+    assert_eq!(Some(BuySell::Buy), commission.buy_sell);
+    assert_eq!(dec("-1.25"), commission.broker_execution_charge);
+    assert_eq!(dec("0"), commission.reg_finra_trading_activity_fee);
+
+    let slb = &stmt.slb_activities.slb_activity[0];
+    assert_eq!("SLB-0001", slb.slb_transaction_id);
+    assert_eq!(dec("520"), slb.collateral_amount);
+
+    let funds = &stmt.stmt_funds.statement_of_funds_line;
+    assert_eq!(2, funds.len());
+    assert_eq!(None, funds[0].buy_sell);
+    assert_eq!(Decimal::ZERO, funds[0].debit);
+    assert_eq!(date("2023-03-10"), funds[1].settle_date);
+    assert_eq!(dec("1019.02"), funds[1].balance);
+
+    let mtm = &stmt.mtm_performance_summary_in_base.mtm_performance_summary_underlying[0];
+    assert_eq!(Some(dec("123.2")), mtm.close_price);
+    assert_eq!(dec("-8.53"), mtm.total);
+
+    let fifo = &stmt.fifo_performance_summary_in_base.fifo_performance_summary_underlying[0];
+    assert_eq!(dec("-8.53"), fifo.unrealized_st_loss);
+    assert_eq!(dec("-8.53"), fifo.total_fifo_pnl);
+}
+
+/// The attributes and the sections that are not modelled are kept.
+#[test]
+fn parse_unmodelled_test() {
+    let content = r#"
+<FlexQueryResponse queryName="extra" type="AF">
+    <FlexStatements count="1">
+        <FlexStatement accountId="U1234567" fromDate="20230301" toDate="20230331" period="LastMonth" whenGenerated="20230401;083015">
+            <CashTransactions>
+                <CashTransaction reportDate="20230315" type="Dividends" amount="38.4" currency="EUR" fxRateToBase="1" levelOfDetail="DETAIL" />
+            </CashTransactions>
+            <NetStockPositionSummary>
+                <NetStockPosition symbol="AIR" netShares="4" />
+                <NetStockPosition symbol="VHYL" netShares="25" />
+            </NetStockPositionSummary>
+        </FlexStatement>
+    </FlexStatements>
+</FlexQueryResponse>
+    "#
+    .to_string();
+    let actual = FlexQueryResponse::try_from(content).expect("report parsed");
+
+    let stmt = &actual.flex_statements.flex_statement[0];
+    let tx = &stmt.cash_transactions.cash_transaction[0];
+    assert_eq!(dec("38.4"), tx.amount);
+    assert_eq!(2, tx.extra.len());
+    assert_eq!("DETAIL", tx.extra["levelOfDetail"]);
+    assert!(stmt.extra.is_empty());
+
+    assert_eq!(1, stmt.sections.len());
+    let section = &stmt.sections[0];
+    assert_eq!("NetStockPositionSummary", section.name);
+    assert_eq!(2, section.children.len());
+    assert_eq!("NetStockPosition", section.children[1].name);
+    assert_eq!("VHYL", section.children[1].attributes["symbol"]);
+    assert_eq!("25", section.children[1].attributes["netShares"]);
+}
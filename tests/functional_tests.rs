@@ -11,7 +11,7 @@ fn test_same_symbols_different_exchange() {
 
     let expected = r#"Using tests/same_symbol.xml
 New: 2023-09-14/2023-09-15 SDIV    Dividend    5.04 USD, SDIV(US37960A6698) CASH DIVIDEND USD 0.21 PER SHARE (Ordinary Dividend)
-New: 2023-09-21/2023-09-22 SDIV    Dividend   10.26 USD, SDIV(IE00077FRP95) CASH DIVIDEND USD 0.09 PER SHARE (Mixed Income)
+New: 2023-09-21/2023-09-22 SDIV_MI Dividend   10.26 USD, SDIV(IE00077FRP95) CASH DIVIDEND USD 0.09 PER SHARE (Mixed Income)
 Complete.
 "#;
 
@@ -20,3 +20,15 @@ Complete.
     // assert!(!actual.is_empty());
     // assert_eq!(expected, actual);
 }
+
+#[test]
+fn test_inspect() {
+    let mut app_cmd = Command::cargo_bin("ibflex").unwrap();
+    let args = shell_words::split("inspect --flex-report-path tests/sections.xml").unwrap();
+
+    let assert = app_cmd.args(args).assert();
+
+    assert
+        .success()
+        .stdout("Using tests/sections.xml\nAll the attributes and sections are modelled.\n");
+}